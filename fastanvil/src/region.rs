@@ -388,6 +388,42 @@ pub const fn unstable_div_ceil(lhs: usize, rhs: usize) -> usize {
     }
 }
 
+/// Get the payload of a chunk from the raw bytes of its sectors in a region
+/// file, as found at the location in the region header. The chunk header gives
+/// the length of the payload, so the padding that fills out the last sector is
+/// dropped.
+///
+/// The payload starts with the byte giving its [`CompressionScheme`], followed
+/// by the chunk's compressed NBT data.
+///
+/// ```
+/// # use fastanvil::strip_sector_padding;
+/// let mut slot = vec![0, 0, 0, 4, 3, b'a', b'b', b'c'];
+/// slot.resize(4096, 0);
+///
+/// assert_eq!(strip_sector_padding(&slot)?, &[3, b'a', b'b', b'c']);
+/// # Ok::<(), fastanvil::Error>(())
+/// ```
+pub fn strip_sector_padding(raw: &[u8]) -> Result<&[u8]> {
+    let mut header = raw;
+    let len = header.read_u32::<BigEndian>()? as usize;
+    if len == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "chunk length does not include its compression scheme",
+        )
+        .into());
+    }
+
+    header.get(..len).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "chunk length is longer than its sectors",
+        )
+        .into()
+    })
+}
+
 fn header_pos(x: usize, z: usize) -> u64 {
     (4 * ((x % 32) + (z % 32) * 32)) as u64
 }
@@ -196,3 +196,31 @@ fn load_from_existing_buffer() {
 // abstraction on top of this providing this. Something that copies a region and
 // only write the to copy until done, then atomically moves the file over the
 // old region.
+
+#[test]
+fn strip_sector_padding_returns_exact_payload() {
+    let mut r = new_empty();
+    r.write_compressed_chunk(0, 0, Uncompressed, &[1, 2, 3])
+        .unwrap();
+    let raw = r.into_inner().unwrap().into_inner();
+
+    // The chunk is in the third sector, padded to fill it.
+    let mut slot = raw[2 * SECTOR_SIZE..].to_vec();
+    slot.resize(SECTOR_SIZE, 0);
+    assert_eq!(crate::strip_sector_padding(&slot).unwrap(), &[3, 1, 2, 3]);
+
+    // A payload exactly filling its sectors.
+    let payload = n_sector_chunk(1);
+    let mut slot = vec![];
+    slot.extend_from_slice(&(payload.len() as u32 + 1).to_be_bytes());
+    slot.push(Uncompressed as u8);
+    slot.extend_from_slice(&payload);
+    assert_eq!(slot.len(), SECTOR_SIZE);
+    let stripped = crate::strip_sector_padding(&slot).unwrap();
+    assert_eq!(stripped.len(), SECTOR_SIZE - 4);
+    assert_eq!(&stripped[1..], payload.as_slice());
+
+    assert!(crate::strip_sector_padding(&[0, 0]).is_err());
+    assert!(crate::strip_sector_padding(&[0, 0, 0, 0, 3]).is_err());
+    assert!(crate::strip_sector_padding(&[0, 0, 0, 5, 3, 1, 2]).is_err());
+}
@@ -0,0 +1,61 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use fastnbt::{de::BulkDeserializer, nbt, DeOpts, Value};
+
+fn documents() -> Vec<Vec<u8>> {
+    (0..1000)
+        .map(|i| {
+            fastnbt::to_bytes(&nbt!({
+                "id": "minecraft:zombie",
+                "CustomName": format!("Zombie \u{1F600} {}", i),
+                "Pos": [i as f64, 64.0, -(i as f64)],
+                "UUID": [I; i, i + 1, i + 2, i + 3],
+            }))
+            .unwrap()
+        })
+        .collect()
+}
+
+pub fn bulk_benchmark(c: &mut Criterion) {
+    let docs = documents();
+
+    c.bench_function("from_bytes many", |b| {
+        b.iter(|| {
+            for doc in &docs {
+                let v: Value = fastnbt::from_bytes(doc).unwrap();
+                black_box(v);
+            }
+        });
+    });
+
+    c.bench_function("bulk parse many", |b| {
+        let mut bulk = BulkDeserializer::new(DeOpts::new());
+        b.iter(|| {
+            for doc in &docs {
+                let v: Value = bulk.parse(doc).unwrap();
+                black_box(v);
+            }
+        });
+    });
+
+    c.bench_function("from_reader many", |b| {
+        b.iter(|| {
+            for doc in &docs {
+                let v: Value = fastnbt::from_reader(doc.as_slice()).unwrap();
+                black_box(v);
+            }
+        });
+    });
+
+    c.bench_function("bulk parse_reader many", |b| {
+        let mut bulk = BulkDeserializer::new(DeOpts::new());
+        b.iter(|| {
+            for doc in &docs {
+                let v: Value = bulk.parse_reader(doc.as_slice()).unwrap();
+                black_box(v);
+            }
+        });
+    });
+}
+
+criterion_group!(benches, bulk_benchmark);
+criterion_main!(benches);
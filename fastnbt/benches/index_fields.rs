@@ -0,0 +1,45 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use fastnbt::Value;
+use serde::Deserialize;
+
+const CHUNK: &[u8] = include_bytes!("../src/test/resources/chunk1.14.nbt");
+
+#[derive(Deserialize)]
+struct Chunk {
+    #[serde(rename = "DataVersion")]
+    data_version: i32,
+    #[serde(rename = "Level")]
+    level: Level,
+}
+
+#[derive(Deserialize)]
+struct Level {
+    #[serde(rename = "xPos")]
+    x_pos: i32,
+}
+
+pub fn index_benchmark(c: &mut Criterion) {
+    c.bench_function("chunk to Value", |b| {
+        b.iter(|| {
+            let v: Value = fastnbt::from_bytes(black_box(CHUNK)).unwrap();
+            black_box(v);
+        });
+    });
+
+    c.bench_function("chunk index fields", |b| {
+        b.iter(|| {
+            let chunk: Chunk = fastnbt::from_bytes(black_box(CHUNK)).unwrap();
+            black_box((chunk.data_version, chunk.level.x_pos));
+        });
+    });
+
+    c.bench_function("chunk index fields from_reader", |b| {
+        b.iter(|| {
+            let chunk: Chunk = fastnbt::from_reader(black_box(CHUNK)).unwrap();
+            black_box((chunk.data_version, chunk.level.x_pos));
+        });
+    });
+}
+
+criterion_group!(benches, index_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,61 @@
+use std::{
+    fs::File,
+    io::{BufReader, Seek, SeekFrom, Write},
+};
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use fastnbt::{nbt, Value};
+
+/// A document of a few hundred entities.
+fn document() -> Vec<u8> {
+    let entities: Vec<Value> = (0..500)
+        .map(|i| {
+            nbt!({
+                "id": "minecraft:zombie",
+                "CustomName": format!("Zombie {}", i),
+                "Pos": [i as f64, 64.0, -(i as f64)],
+                "Motion": [0.0, -0.08, 0.0],
+                "Health": 20.0f32,
+                "UUID": [I; i, i + 1, i + 2, i + 3],
+                "Tags": ["hostile", "undead"],
+            })
+        })
+        .collect();
+    fastnbt::to_bytes(&nbt!({ "Entities": entities })).unwrap()
+}
+
+pub fn reader_benchmark(c: &mut Criterion) {
+    let data = document();
+    let path = std::env::temp_dir().join(format!("fastnbt-bench-{}.nbt", std::process::id()));
+    File::create(&path).unwrap().write_all(&data).unwrap();
+    let mut file = File::open(&path).unwrap();
+
+    c.bench_function("from_reader slice", |b| {
+        b.iter(|| {
+            let v: Value = fastnbt::from_reader(data.as_slice()).unwrap();
+            black_box(v);
+        });
+    });
+
+    // Every small read is a system call.
+    c.bench_function("from_reader file unbuffered", |b| {
+        b.iter(|| {
+            file.seek(SeekFrom::Start(0)).unwrap();
+            let v: Value = fastnbt::from_reader(&mut file).unwrap();
+            black_box(v);
+        });
+    });
+
+    c.bench_function("from_reader file buffered", |b| {
+        b.iter(|| {
+            file.seek(SeekFrom::Start(0)).unwrap();
+            let v: Value = fastnbt::from_reader(BufReader::new(&mut file)).unwrap();
+            black_box(v);
+        });
+    });
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+criterion_group!(benches, reader_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,121 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use fastnbt::{ser::NbtWriter, IntArray, LongArray, Tag, Value};
+
+/// A chunk-like value: many sections, each with a palette and block states.
+fn large_value() -> Value {
+    let sections = (0..24)
+        .map(|y| {
+            let palette = (0..16)
+                .map(|i| {
+                    fastnbt::nbt!({
+                        "Name": format!("minecraft:block_{}", i),
+                        "Properties": {"facing": "north", "lit": "false"},
+                    })
+                })
+                .collect();
+            let mut section = fastnbt::nbt!({"Y": (y as i8)});
+            if let Value::Compound(map) = &mut section {
+                map.insert("Palette".to_string(), Value::List(palette));
+                map.insert(
+                    "BlockStates".to_string(),
+                    Value::LongArray(LongArray::new(vec![0x0123_4567_89ab_cdef; 256])),
+                );
+            }
+            section
+        })
+        .collect();
+
+    let mut v = fastnbt::nbt!({"DataVersion": 3120, "Status": "full"});
+    if let Value::Compound(map) = &mut v {
+        map.insert("sections".to_string(), Value::List(sections));
+        map.insert(
+            "Biomes".to_string(),
+            Value::IntArray(IntArray::new(vec![1; 1024])),
+        );
+    }
+    v
+}
+
+/// A compound with many entries directly in it.
+fn wide_value() -> Value {
+    let mut v = fastnbt::nbt!({});
+    if let Value::Compound(map) = &mut v {
+        for i in 0..2000 {
+            map.insert(format!("field_{}", i), Value::Int(i));
+            map.insert(format!("name_{}", i), Value::String(format!("value {}", i)));
+            map.insert(
+                format!("data_{}", i),
+                Value::LongArray(LongArray::new(vec![i as i64; 8])),
+            );
+        }
+    }
+    v
+}
+
+/// Write a value with the streaming writer, for comparison with serde.
+fn write_direct<W: std::io::Write>(w: &mut NbtWriter<W>, name: &str, v: &Value) {
+    match v {
+        Value::Byte(v) => w.write_byte(name, *v).unwrap(),
+        Value::Short(v) => w.write_short(name, *v).unwrap(),
+        Value::Int(v) => w.write_int(name, *v).unwrap(),
+        Value::Long(v) => w.write_long(name, *v).unwrap(),
+        Value::Float(v) => w.write_float(name, *v).unwrap(),
+        Value::Double(v) => w.write_double(name, *v).unwrap(),
+        Value::String(v) => w.write_string(name, v).unwrap(),
+        Value::ByteArray(v) => w.write_byte_array(name, v).unwrap(),
+        Value::IntArray(v) => w.write_int_array(name, v).unwrap(),
+        Value::LongArray(v) => w.write_long_array(name, v).unwrap(),
+        Value::List(list) => {
+            let tag = list.first().map_or(Tag::End, Value::tag);
+            w.start_list(name, tag, list.len()).unwrap();
+            for v in list {
+                write_direct(w, "", v);
+            }
+            w.end_list().unwrap();
+        }
+        Value::Compound(map) => {
+            w.start_compound(name).unwrap();
+            for (k, v) in map {
+                write_direct(w, k, v);
+            }
+            w.end_compound().unwrap();
+        }
+    }
+}
+
+pub fn serialize_value(c: &mut Criterion) {
+    let v = large_value();
+
+    c.bench_function("value to_bytes", |b| {
+        b.iter(|| black_box(fastnbt::to_bytes(&v).unwrap()));
+    });
+
+    c.bench_function("value direct NbtWriter", |b| {
+        b.iter(|| {
+            let mut w = NbtWriter::new(Vec::new());
+            write_direct(&mut w, "", &v);
+            black_box(w.finish().unwrap())
+        });
+    });
+
+    let wide = wide_value();
+
+    c.bench_function("wide value to_bytes", |b| {
+        b.iter(|| black_box(fastnbt::to_bytes(&wide).unwrap()));
+    });
+
+    // Working out the size each time.
+    c.bench_function("wide value to_bytes_with_capacity", |b| {
+        b.iter(|| {
+            black_box(fastnbt::to_bytes_with_capacity(&wide, wide.serialized_size()).unwrap())
+        });
+    });
+
+    let size = wide.serialized_size();
+    c.bench_function("wide value to_bytes_with_capacity known size", |b| {
+        b.iter(|| black_box(fastnbt::to_bytes_with_capacity(&wide, size).unwrap()));
+    });
+}
+
+criterion_group!(benches, serialize_value);
+criterion_main!(benches);
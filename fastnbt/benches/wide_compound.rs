@@ -0,0 +1,62 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use fastnbt::{nbt, Value};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+#[allow(dead_code)]
+struct Wide {
+    field_00: i32,
+    field_05: i32,
+    field_10: i32,
+    field_15: i32,
+    field_20: i32,
+    field_25: i32,
+    field_30: i32,
+    field_35: i32,
+    field_40: i32,
+    field_45: i32,
+    name_00: String,
+    name_10: String,
+    name_20: String,
+    name_30: String,
+    name_40: String,
+}
+
+fn wide_compound() -> Vec<u8> {
+    let mut v = nbt!({});
+    if let Value::Compound(map) = &mut v {
+        for i in 0..50 {
+            map.insert(format!("field_{:02}", i), Value::Int(i));
+            map.insert(
+                format!("name_{:02}", i),
+                Value::String(format!("value {}", i)),
+            );
+            map.insert(
+                format!("data_{:02}", i),
+                Value::IntArray(fastnbt::IntArray::new(vec![i; 16])),
+            );
+        }
+    }
+    fastnbt::to_bytes(&v).unwrap()
+}
+
+pub fn wide_benchmark(c: &mut Criterion) {
+    let data = wide_compound();
+
+    c.bench_function("wide struct from_bytes", |b| {
+        b.iter(|| {
+            let v: Wide = fastnbt::from_bytes(&data).unwrap();
+            black_box(v);
+        });
+    });
+
+    c.bench_function("wide struct from_reader", |b| {
+        b.iter(|| {
+            let v: Wide = fastnbt::from_reader(data.as_slice()).unwrap();
+            black_box(v);
+        });
+    });
+}
+
+criterion_group!(benches, wide_benchmark);
+criterion_main!(benches);
@@ -1,6 +1,6 @@
 use std::ops::{Deref, DerefMut};
 
-use byteorder::{BigEndian, ReadBytesExt};
+use byteorder::{BigEndian, ByteOrder};
 use serde::{de::Visitor, Deserialize, Serialize};
 use serde_bytes::ByteBuf;
 
@@ -8,6 +8,34 @@ pub(crate) const BYTE_ARRAY_TOKEN: &str = "__fastnbt_byte_array";
 pub(crate) const INT_ARRAY_TOKEN: &str = "__fastnbt_int_array";
 pub(crate) const LONG_ARRAY_TOKEN: &str = "__fastnbt_long_array";
 
+/// Decode the payload of an NBT IntArray, a sequence of big endian `i32`s,
+/// without the length prefix. Any trailing bytes that do not make up a whole
+/// `i32` are ignored.
+///
+/// This is useful for deferring the conversion of an array borrowed from the
+/// input, such as the bytes underlying a [`borrow::IntArray`][`crate::borrow::IntArray`],
+/// until the values are needed.
+///
+/// ```
+/// let payload = [0, 0, 0, 1, 0xff, 0xff, 0xff, 0xfe];
+/// assert_eq!(fastnbt::decode_int_array(&payload), [1, -2]);
+/// ```
+pub fn decode_int_array(payload: &[u8]) -> Vec<i32> {
+    payload.chunks_exact(4).map(BigEndian::read_i32).collect()
+}
+
+/// Decode the payload of an NBT LongArray, a sequence of big endian `i64`s,
+/// without the length prefix. Any trailing bytes that do not make up a whole
+/// `i64` are ignored. See [`decode_int_array`].
+///
+/// ```
+/// let payload = [0, 0, 0, 0, 0, 0, 0, 1];
+/// assert_eq!(fastnbt::decode_long_array(&payload), [1]);
+/// ```
+pub fn decode_long_array(payload: &[u8]) -> Vec<i64> {
+    payload.chunks_exact(8).map(BigEndian::read_i64).collect()
+}
+
 /// NBT ByteArray that owns its data. This type preserves the exact NBT type
 /// when (de)serializing. This dereferences into a i8 slice, so should be usable
 /// basically anywhere a slice should be.
@@ -73,6 +101,16 @@ impl ByteArray {
         Self { data }
     }
 
+    /// Take the data out of the array.
+    pub fn into_inner(self) -> Vec<i8> {
+        self.data
+    }
+
+    /// Number of elements the array has allocated space for.
+    pub(crate) fn capacity(&self) -> usize {
+        self.data.capacity()
+    }
+
     /// Produce a ByteArray from raw data.
     pub(crate) fn from_bytes(data: &[u8]) -> Self {
         // Safe to treat [u8] as [i8].
@@ -95,7 +133,7 @@ impl ByteArray {
     }
 
     pub(crate) fn to_bytes(&self) -> Vec<u8> {
-        self.data.iter().flat_map(|i| i.to_be_bytes()).collect()
+        self.data.iter().map(|&i| i as u8).collect()
     }
 }
 
@@ -128,18 +166,27 @@ impl IntArray {
         Self { data }
     }
 
+    /// Take the data out of the array.
+    pub fn into_inner(self) -> Vec<i32> {
+        self.data
+    }
+
+    /// Number of elements the array has allocated space for.
+    pub(crate) fn capacity(&self) -> usize {
+        self.data.capacity()
+    }
+
     /// Produce a IntArray from raw data. This data should be big endian!
     pub(crate) fn from_bytes(data: &[u8]) -> std::io::Result<Self> {
-        let data = data
-            .chunks_exact(4)
-            .map(|mut bs| bs.read_i32::<BigEndian>())
-            .collect::<std::io::Result<Vec<i32>>>()?;
-
-        Ok(IntArray { data })
+        Ok(IntArray {
+            data: decode_int_array(data),
+        })
     }
 
     pub(crate) fn to_bytes(&self) -> Vec<u8> {
-        self.data.iter().flat_map(|i| i.to_be_bytes()).collect()
+        let mut bytes = vec![0; self.data.len() * 4];
+        BigEndian::write_i32_into(&self.data, &mut bytes);
+        bytes
     }
 }
 
@@ -222,17 +269,26 @@ impl LongArray {
         Self { data }
     }
 
-    pub(crate) fn from_bytes(data: &[u8]) -> std::io::Result<Self> {
-        let data = data
-            .chunks_exact(8)
-            .map(|mut bs| bs.read_i64::<BigEndian>())
-            .collect::<std::io::Result<Vec<i64>>>()?;
+    /// Take the data out of the array.
+    pub fn into_inner(self) -> Vec<i64> {
+        self.data
+    }
+
+    /// Number of elements the array has allocated space for.
+    pub(crate) fn capacity(&self) -> usize {
+        self.data.capacity()
+    }
 
-        Ok(LongArray { data })
+    pub(crate) fn from_bytes(data: &[u8]) -> std::io::Result<Self> {
+        Ok(LongArray {
+            data: decode_long_array(data),
+        })
     }
 
     pub(crate) fn to_bytes(&self) -> Vec<u8> {
-        self.data.iter().flat_map(|i| i.to_be_bytes()).collect()
+        let mut bytes = vec![0; self.data.len() * 8];
+        BigEndian::write_i64_into(&self.data, &mut bytes);
+        bytes
     }
 }
 
@@ -0,0 +1,372 @@
+//! Columnar storage for many values that share a structure.
+//!
+//! Storing many similar values, such as the chunks of a world, one after the
+//! other repeats their keys and tags over and over, and puts unrelated numbers
+//! next to each other. [`encode`] instead writes the structure once, followed
+//! by one column per value in the structure: all the `xPos` values together,
+//! then all the `zPos` values, and so on. Similar data ends up together, so
+//! the result usually compresses much better than the values do. [`decode`]
+//! rebuilds the values.
+//!
+//! Every value must have exactly the same structure as the first: the same
+//! keys in every compound, the same tags, and the same number of elements in
+//! every list. Only the contents of numbers, strings and NBT arrays can
+//! differ. Values that are only similar are better stored as NBT.
+//!
+//! The structure must hold at least one number, string or array for more than
+//! one value to be encoded. Values without any, such as empty compounds, have
+//! no columns, so nothing in a blob would back how many values it claims to
+//! hold.
+//!
+//! ```
+//! # use fastnbt::{columnar, nbt};
+//! let chunks: Vec<_> = (0..100)
+//!     .map(|i| nbt!({"xPos": i, "zPos": -i, "Status": "full"}))
+//!     .collect();
+//!
+//! let blob = columnar::encode(&chunks)?;
+//! assert_eq!(columnar::decode(&blob)?, chunks);
+//! # Ok::<(), fastnbt::error::Error>(())
+//! ```
+//!
+//! # Format
+//!
+//! All numbers are big endian, as in NBT. A [`ColumnarBlob`] is:
+//!
+//! * the number of values, a `u32`. If this is zero nothing follows.
+//! * the structure of the values. This is written like an NBT value, but with
+//!   the payload of every number, string and array left out, and with the
+//!   number of entries before the entries of a compound, rather than an End
+//!   tag after them. Entries are sorted by key.
+//! * for each number, string and array in the structure, in the order they
+//!   appear in it, the length in bytes of its column as a `u32`, then its
+//!   payload from each value in turn, encoded as in NBT.
+
+use std::collections::HashMap;
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+
+use crate::{
+    error::{Error, Result},
+    input::{try_size, MAX_DEPTH},
+    ser::WriteNbt,
+    ByteArray, IntArray, LongArray, Tag, Value,
+};
+
+/// Values encoded by [`encode`]. See the [module documentation][`self`] for
+/// the format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnarBlob {
+    data: Vec<u8>,
+}
+
+impl ColumnarBlob {
+    /// Wrap data previously taken from a blob, such as after storing it. It is
+    /// checked when it is decoded.
+    pub fn from_bytes(data: Vec<u8>) -> Self {
+        Self { data }
+    }
+
+    /// The encoded data.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Take the encoded data out of the blob.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+/// Encode `values` into columns. This errors if any value does not have
+/// exactly the same structure as the first, or if there is more than one value
+/// and the structure has no numbers, strings or arrays.
+pub fn encode(values: &[Value]) -> Result<ColumnarBlob> {
+    let mut data = Vec::new();
+    data.write_len(values.len())?;
+
+    let first = match values.first() {
+        Some(first) => first,
+        None => return Ok(ColumnarBlob { data }),
+    };
+
+    let shape = Shape::of(first);
+    if values.len() > 1 && shape.leaves() == 0 {
+        return Err(Error::bespoke(
+            "cannot encode more than one value with no numbers, strings or arrays",
+        ));
+    }
+    shape.write(&mut data)?;
+
+    let mut columns = vec![Vec::new(); shape.leaves()];
+    for (i, v) in values.iter().enumerate() {
+        let mut path = String::new();
+        if !shape.gather(v, &mut columns, &mut 0, &mut path)? {
+            return Err(Error::bespoke(format!(
+                "value {} does not have the same structure as the first, at '{}'",
+                i, path
+            )));
+        }
+    }
+
+    for column in columns {
+        data.write_len(column.len())?;
+        data.extend(column);
+    }
+    Ok(ColumnarBlob { data })
+}
+
+/// Decode the values in `blob`.
+pub fn decode(blob: &ColumnarBlob) -> Result<Vec<Value>> {
+    let mut input = blob.as_bytes();
+    let count = input.read_u32::<BigEndian>()?;
+    if count == 0 {
+        return finish(input, Vec::new());
+    }
+
+    let shape = Shape::read(&mut input, 1)?;
+    // Every other value takes up space in the columns, which bounds the count,
+    // but these take none.
+    if count > 1 && shape.leaves() == 0 {
+        return Err(Error::bespoke(
+            "columnar data has more than one value with no numbers, strings or arrays",
+        ));
+    }
+
+    let mut columns = Vec::with_capacity(shape.leaves());
+    for _ in 0..shape.leaves() {
+        let len = input.read_u32::<BigEndian>()? as usize;
+        columns.push(take(&mut input, len)?);
+    }
+
+    let mut values = Vec::new();
+    for _ in 0..count {
+        values.push(shape.build(&mut columns, &mut 0)?);
+    }
+
+    if columns.iter().any(|column| !column.is_empty()) {
+        return Err(Error::bespoke("columnar data has values left over"));
+    }
+    finish(input, values)
+}
+
+fn finish(input: &[u8], values: Vec<Value>) -> Result<Vec<Value>> {
+    if input.is_empty() {
+        Ok(values)
+    } else {
+        Err(Error::bespoke("columnar data has trailing bytes"))
+    }
+}
+
+fn take<'a>(input: &mut &'a [u8], n: usize) -> Result<&'a [u8]> {
+    if n > input.len() {
+        return Err(Error::unexpected_eof());
+    }
+    let (bytes, rest) = input.split_at(n);
+    *input = rest;
+    Ok(bytes)
+}
+
+/// The structure shared by the values in a blob.
+enum Shape {
+    /// A number, string or array, which gets a column of its own.
+    Leaf(Tag),
+    List(Vec<Shape>),
+    /// Entries sorted by key.
+    Compound(Vec<(String, Shape)>),
+}
+
+impl Shape {
+    fn of(v: &Value) -> Shape {
+        match v {
+            Value::List(elements) => Shape::List(elements.iter().map(Shape::of).collect()),
+            Value::Compound(map) => {
+                let mut entries: Vec<_> =
+                    map.iter().map(|(k, v)| (k.clone(), Shape::of(v))).collect();
+                entries.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+                Shape::Compound(entries)
+            }
+            v => Shape::Leaf(v.tag()),
+        }
+    }
+
+    /// The number of columns for this shape.
+    fn leaves(&self) -> usize {
+        match self {
+            Shape::Leaf(_) => 1,
+            Shape::List(elements) => elements.iter().map(Shape::leaves).sum(),
+            Shape::Compound(entries) => entries.iter().map(|(_, s)| s.leaves()).sum(),
+        }
+    }
+
+    fn write(&self, out: &mut Vec<u8>) -> Result<()> {
+        match self {
+            Shape::Leaf(tag) => out.write_tag(*tag),
+            Shape::List(elements) => {
+                out.write_tag(Tag::List)?;
+                out.write_len(elements.len())?;
+                elements.iter().try_for_each(|s| s.write(out))
+            }
+            Shape::Compound(entries) => {
+                out.write_tag(Tag::Compound)?;
+                out.write_len(entries.len())?;
+                entries.iter().try_for_each(|(key, s)| {
+                    out.write_size_prefixed_str(key)?;
+                    s.write(out)
+                })
+            }
+        }
+    }
+
+    fn read(input: &mut &[u8], depth: usize) -> Result<Shape> {
+        let id = input.read_u8()?;
+        let tag = Tag::try_from(id).map_err(|_| Error::invalid_tag(id))?;
+        if matches!(tag, Tag::List | Tag::Compound) && depth > MAX_DEPTH {
+            return Err(Error::bespoke(format!(
+                "columnar structure nested deeper than {}",
+                MAX_DEPTH
+            )));
+        }
+
+        Ok(match tag {
+            Tag::End => return Err(Error::bespoke("unexpected end tag in columnar structure")),
+            Tag::List => {
+                let len = input.read_u32::<BigEndian>()?;
+                let elements = (0..len)
+                    .map(|_| Shape::read(input, depth + 1))
+                    .collect::<Result<_>>()?;
+                Shape::List(elements)
+            }
+            Tag::Compound => {
+                let len = input.read_u32::<BigEndian>()?;
+                let entries = (0..len)
+                    .map(|_| Ok((read_str(input)?, Shape::read(input, depth + 1)?)))
+                    .collect::<Result<_>>()?;
+                Shape::Compound(entries)
+            }
+            tag => Shape::Leaf(tag),
+        })
+    }
+
+    /// Append the payload of each leaf of `v` to its column. Returns false
+    /// if `v` does not have this shape, leaving `path` as a pointer to where
+    /// it differs.
+    fn gather(
+        &self,
+        v: &Value,
+        columns: &mut [Vec<u8>],
+        next: &mut usize,
+        path: &mut String,
+    ) -> Result<bool> {
+        match (self, v) {
+            (Shape::Leaf(tag), v) if v.tag() == *tag => {
+                write_payload(v, &mut columns[*next])?;
+                *next += 1;
+            }
+            (Shape::List(shapes), Value::List(elements)) if shapes.len() == elements.len() => {
+                for (i, (s, v)) in shapes.iter().zip(elements).enumerate() {
+                    let len = path.len();
+                    path.push_str(&format!("/{}", i));
+                    if !s.gather(v, columns, next, path)? {
+                        return Ok(false);
+                    }
+                    path.truncate(len);
+                }
+            }
+            (Shape::Compound(entries), Value::Compound(map)) if entries.len() == map.len() => {
+                for (key, s) in entries {
+                    let len = path.len();
+                    path.push('/');
+                    path.push_str(&key.replace('~', "~0").replace('/', "~1"));
+                    match map.get(key) {
+                        Some(v) if s.gather(v, columns, next, path)? => path.truncate(len),
+                        _ => return Ok(false),
+                    }
+                }
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// Build the next value of this shape from the columns.
+    fn build(&self, columns: &mut [&[u8]], next: &mut usize) -> Result<Value> {
+        match self {
+            Shape::Leaf(tag) => {
+                let v = read_payload(*tag, &mut columns[*next])?;
+                *next += 1;
+                Ok(v)
+            }
+            Shape::List(shapes) => Ok(Value::List(
+                shapes
+                    .iter()
+                    .map(|s| s.build(columns, next))
+                    .collect::<Result<_>>()?,
+            )),
+            Shape::Compound(entries) => Ok(Value::Compound(
+                entries
+                    .iter()
+                    .map(|(key, s)| Ok((key.clone(), s.build(columns, next)?)))
+                    .collect::<Result<HashMap<_, _>>>()?,
+            )),
+        }
+    }
+}
+
+fn write_payload(v: &Value, out: &mut Vec<u8>) -> Result<()> {
+    match v {
+        Value::Byte(v) => out.write_i8(*v)?,
+        Value::Short(v) => out.write_i16::<BigEndian>(*v)?,
+        Value::Int(v) => out.write_i32::<BigEndian>(*v)?,
+        Value::Long(v) => out.write_i64::<BigEndian>(*v)?,
+        Value::Float(v) => out.write_f32::<BigEndian>(*v)?,
+        Value::Double(v) => out.write_f64::<BigEndian>(*v)?,
+        Value::String(v) => out.write_size_prefixed_str(v)?,
+        Value::ByteArray(v) => {
+            out.write_len(v.len())?;
+            out.extend(v.to_bytes());
+        }
+        Value::IntArray(v) => {
+            out.write_len(v.len())?;
+            out.extend(v.to_bytes());
+        }
+        Value::LongArray(v) => {
+            out.write_len(v.len())?;
+            out.extend(v.to_bytes());
+        }
+        Value::List(_) | Value::Compound(_) => unreachable!("lists and compounds are not leaves"),
+    }
+    Ok(())
+}
+
+fn read_str(input: &mut &[u8]) -> Result<String> {
+    let len = input.read_u16::<BigEndian>()? as usize;
+    let bytes = take(input, len)?;
+    let s = cesu8::from_java_cesu8(bytes).map_err(|_| Error::nonunicode_string(bytes))?;
+    Ok(s.into_owned())
+}
+
+fn read_payload(tag: Tag, input: &mut &[u8]) -> Result<Value> {
+    Ok(match tag {
+        Tag::Byte => Value::Byte(input.read_i8()?),
+        Tag::Short => Value::Short(input.read_i16::<BigEndian>()?),
+        Tag::Int => Value::Int(input.read_i32::<BigEndian>()?),
+        Tag::Long => Value::Long(input.read_i64::<BigEndian>()?),
+        Tag::Float => Value::Float(input.read_f32::<BigEndian>()?),
+        Tag::Double => Value::Double(input.read_f64::<BigEndian>()?),
+        Tag::String => Value::String(read_str(input)?),
+        Tag::ByteArray => {
+            let len = input.read_i32::<BigEndian>()?;
+            Value::ByteArray(ByteArray::from_bytes(take(input, try_size(len, 1)?)?))
+        }
+        Tag::IntArray => {
+            let len = input.read_i32::<BigEndian>()?;
+            Value::IntArray(IntArray::from_bytes(take(input, try_size(len, 4)?)?)?)
+        }
+        Tag::LongArray => {
+            let len = input.read_i32::<BigEndian>()?;
+            Value::LongArray(LongArray::from_bytes(take(input, try_size(len, 8)?)?)?)
+        }
+        Tag::End | Tag::List | Tag::Compound => unreachable!("not a leaf tag"),
+    })
+}
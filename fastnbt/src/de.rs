@@ -55,6 +55,38 @@
 //! bytes will be Java's CESU-8 format. You can use [`cesu8::from_java_cesu8`]
 //! to decode this.
 //!
+//! Compound keys cannot be deserialized as bytes, so for data that encodes
+//! them in some other way install a [`KeyDecoder`] with
+//! [`DeOpts::key_decoder`][`crate::DeOpts::key_decoder`].
+//!
+//! ## What allocates
+//!
+//! All allocation goes through the global allocator. Deserializing into
+//! allocator-parameterized collections, or building a [`Value`][`crate::Value`]
+//! in an arena, is not supported, as Rust's allocator API is not yet stable.
+//! Instead, this is what allocates, so that it can be kept to a minimum:
+//!
+//! * The deserializer has a single scratch buffer, a `Vec<u8>`. With
+//!   [`from_reader`][`crate::from_reader`] every string and array is read into
+//!   it, so it grows to the largest of them. With
+//!   [`from_bytes`][`crate::from_bytes`] it is only used for strings that need
+//!   converting from CESU-8. A [`BulkDeserializer`] keeps this buffer between
+//!   documents.
+//! * Each owned `String`, `Vec` and map in the output is allocated by its own
+//!   `Deserialize` implementation, as with any serde format. Lists do not give
+//!   a size hint, so a `Vec` grows as elements are added rather than being
+//!   allocated up front from a length in the input.
+//! * Owned NBT arrays, including those in a [`Value`][`crate::Value`], are
+//!   first copied into a temporary byte buffer and then converted into their
+//!   final `Vec`, so there are two allocations per array.
+//! * A [`Value`][`crate::Value`] allocates a `HashMap` per compound, a
+//!   `String` per key and string, and a `Vec` per list and array.
+//! * [`from_bytes_lenient`][`crate::from_bytes_lenient`] also allocates the
+//!   key of every compound entry, to track the path to each value.
+//!
+//! Nothing else is allocated per value. Skipped values, such as fields not in
+//! a struct, are not allocated at all.
+//!
 //! # Representation of NBT arrays
 //!
 //! In order for [`Value`][`crate::Value`] to preserve all NBT information, the
@@ -69,6 +101,13 @@
 //! `i128` or `u128`, IntArray with length 4 are accepted. This is parsed as big
 //! endian i.e. the most significant bit (and int) is first.
 //!
+//! # Custom tags
+//!
+//! Some heavily modded setups write tags with IDs beyond the ones defined by
+//! NBT. By default these are an error, but a [`TagHandler`] can be installed
+//! with [`DeOpts::tag_handler`][`crate::DeOpts::tag_handler`] to read their
+//! payload.
+//!
 //! # Other quirks
 //!
 //! Some other quirks which may not be obvious:
@@ -78,12 +117,18 @@
 //!   vectors.
 //! * Any integral value from NBT can be deserialized to bool. Any non-zero
 //!   value becomes `true`.
+//! * Any integral value from NBT can be deserialized to `f32` or `f64`, for
+//!   hand-edited data that has `5` where `5.0` is expected. This is lossy for
+//!   large values, eg a Long beyond 2^53 read as `f64`.
 //! * You can deserialize a field to the unit type `()` or unit struct. This
 //!   ignores the value but ensures that it existed.
-//! * You cannot deserialize into anything other than a `struct` or similar
-//!   container eg `HashMap`. This is due to a misalignment between the NBT
-//!   format and Rust's types. Attempting to will give an error about no root
-//!   compound. This means you can never do `let s: String = from_bytes(...)`.
+//! * The root of NBT data is normally a compound, which deserializes into a
+//!   `struct` or similar container eg `HashMap`. Some formats have another
+//!   root, such as a list. That deserializes into whatever the same value would
+//!   within a compound, eg `Vec<Value>` for a list, or `i32` for an Int. The
+//!   name of the root is ignored.
+//! * Compounds and lists can be nested at most 512 deep, counting the root, as
+//!   in Minecraft. Deeper data is an error rather than overflowing the stack.
 //!
 //! # Example Minecraft types
 //!
@@ -219,23 +264,308 @@
 //!     Full,
 //! }
 //! ```
-use std::io::Read;
+use std::{
+    borrow::Cow,
+    collections::HashSet,
+    fmt,
+    io::{self, Read},
+    marker::PhantomData,
+    ops::Range,
+};
 
 use serde::{
     de::{
         self,
         value::{BorrowedBytesDeserializer, BorrowedStrDeserializer, BytesDeserializer},
+        IntoDeserializer,
     },
     forward_to_deserialize_any,
 };
 
 use crate::{
     error::{Error, Result},
-    input, DeOpts, Tag, BYTE_ARRAY_TOKEN, INT_ARRAY_TOKEN, LONG_ARRAY_TOKEN,
+    helpers::nested_nbt::NESTED_NBT_TOKEN,
+    input,
+    raw::RAW_TOKEN,
+    DeOpts, Tag, Value, BYTE_ARRAY_TOKEN, INT_ARRAY_TOKEN, LONG_ARRAY_TOKEN,
 };
 
 use crate::input::{Input, Reference};
 
+mod reader;
+
+pub use reader::NbtReader;
+
+/// Handler for custom tag IDs, ie tags outside of the range defined by NBT.
+/// Install one with [`DeOpts::tag_handler`][`crate::DeOpts::tag_handler`].
+///
+/// The handler is given the custom tag ID and a reader positioned at the
+/// payload of the value, after the tag and any name. It must read exactly the
+/// payload and convert it into a [`Value`]. The deserializer then carries on as
+/// if that value had been in the input, so a custom tag can be deserialized
+/// into any type the returned `Value` could be.
+///
+/// Closures of the right signature implement this trait.
+///
+/// ```
+/// use byteorder::{BigEndian, ReadBytesExt};
+/// use fastnbt::{DeOpts, Value};
+///
+/// // Tag 100 is a 4-byte int in some modded format.
+/// let opts = DeOpts::new().tag_handler(|id: u8, reader: &mut dyn std::io::Read| match id {
+///     100 => Ok(Value::Int(reader.read_i32::<BigEndian>()?)),
+///     _ => Err(serde::de::Error::custom(format!("unknown tag {}", id))),
+/// });
+/// # let _ = opts;
+/// ```
+pub trait TagHandler: Send + Sync {
+    /// Read the payload of a value with the custom tag `id`.
+    fn read_payload(&self, id: u8, reader: &mut dyn Read) -> Result<Value>;
+}
+
+impl<F> TagHandler for F
+where
+    F: Fn(u8, &mut dyn Read) -> Result<Value> + Send + Sync,
+{
+    fn read_payload(&self, id: u8, reader: &mut dyn Read) -> Result<Value> {
+        self(id, reader)
+    }
+}
+
+/// Decoder for the names of compound keys. Install one with
+/// [`DeOpts::key_decoder`][`crate::DeOpts::key_decoder`].
+///
+/// The decoder is given the raw bytes of every key, after the length, in place
+/// of the usual Modified UTF-8 decoding. Keys decoded this way are never
+/// borrowed from the input.
+///
+/// Closures of the right signature implement this trait.
+///
+/// ```
+/// use fastnbt::{DeOpts, Value};
+///
+/// // Keys written as Latin-1 by some other tool.
+/// let opts = DeOpts::new().key_decoder(|bytes: &[u8]| Ok(bytes.iter().map(|&b| b as char).collect()));
+/// # let _ = opts;
+/// ```
+pub trait KeyDecoder: Send + Sync {
+    /// Decode the bytes of a key.
+    fn decode_key(&self, bytes: &[u8]) -> Result<String>;
+}
+
+impl<F> KeyDecoder for F
+where
+    F: Fn(&[u8]) -> Result<String> + Send + Sync,
+{
+    fn decode_key(&self, bytes: &[u8]) -> Result<String> {
+        self(bytes)
+    }
+}
+
+/// Deserializer for parsing many NBT documents one after the other, such as
+/// when indexing the entities of an entire world. It keeps scratch space
+/// between documents rather than allocating it anew for each one.
+///
+/// This helps most with [`parse_reader`][`BulkDeserializer::parse_reader`],
+/// which copies every string and array through the scratch space. For
+/// [`parse`][`BulkDeserializer::parse`] most data is borrowed from the input
+/// anyway, so there is less to gain.
+///
+/// ```
+/// use fastnbt::{de::BulkDeserializer, DeOpts, Value};
+///
+/// # let blobs: Vec<Vec<u8>> = vec![fastnbt::to_bytes(&fastnbt::nbt!({"a": 1})).unwrap()];
+/// let mut bulk = BulkDeserializer::new(DeOpts::new());
+/// for blob in &blobs {
+///     let value: Value = bulk.parse(blob)?;
+///     # let _ = value;
+/// }
+/// # Ok::<(), fastnbt::error::Error>(())
+/// ```
+pub struct BulkDeserializer {
+    opts: DeOpts,
+    scratch: Vec<u8>,
+}
+
+impl BulkDeserializer {
+    /// Create a deserializer that uses `opts` for every document.
+    pub fn new(opts: DeOpts) -> Self {
+        Self {
+            opts,
+            scratch: Vec::new(),
+        }
+    }
+
+    /// Deserialize a `T` from some NBT data. This behaves the same as
+    /// [`from_bytes_with_opts`][`crate::from_bytes_with_opts`].
+    pub fn parse<'a, T>(&mut self, data: &'a [u8]) -> Result<T>
+    where
+        T: de::Deserialize<'a>,
+    {
+        let scratch = std::mem::take(&mut self.scratch);
+        let mut des =
+            Deserializer::with_scratch(input::Slice::new(data), self.opts.clone(), scratch);
+        let res = crate::deserialize_slice(data, &mut des);
+        self.scratch = des.into_scratch();
+        res
+    }
+
+    /// Deserialize a `T` from a reader of NBT data. This behaves the same as
+    /// [`from_reader`][`crate::from_reader`], but with this deserializer's
+    /// options.
+    pub fn parse_reader<R, T>(&mut self, reader: R) -> Result<T>
+    where
+        R: Read,
+        T: de::DeserializeOwned,
+    {
+        let scratch = std::mem::take(&mut self.scratch);
+        let mut des = Deserializer::with_scratch(
+            input::Reader::new(reader, &self.opts),
+            self.opts.clone(),
+            scratch,
+        );
+        let res = T::deserialize(&mut des);
+        self.scratch = des.into_scratch();
+        res
+    }
+}
+
+/// Kind of checksum that may trail the NBT data in some containers. Set one
+/// with [`DeOpts::trailing_checksum`][`crate::DeOpts::trailing_checksum`].
+/// Requires the `checksum` feature.
+#[cfg(feature = "checksum")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Checksum {
+    /// A big-endian CRC-32 (as used by zlib and gzip) of the NBT data, from
+    /// the root compound's tag up to and including its End tag.
+    Crc32,
+}
+
+#[cfg(feature = "checksum")]
+impl Checksum {
+    /// The number of bytes the checksum takes up.
+    pub(crate) fn size(self) -> usize {
+        match self {
+            Checksum::Crc32 => 4,
+        }
+    }
+
+    /// Start computing a checksum of this kind.
+    pub(crate) fn hasher(self) -> crc32fast::Hasher {
+        match self {
+            Checksum::Crc32 => crc32fast::Hasher::new(),
+        }
+    }
+
+    /// Check that `trailer` starts with a valid checksum of the data given to
+    /// `hasher`.
+    pub(crate) fn verify(self, hasher: crc32fast::Hasher, trailer: &[u8]) -> Result<()> {
+        match self {
+            Checksum::Crc32 => {
+                let expected = trailer
+                    .get(..4)
+                    .ok_or_else(|| Error::bespoke("missing trailing CRC-32"))?;
+                let expected = u32::from_be_bytes(expected.try_into().unwrap());
+
+                if hasher.finalize() != expected {
+                    return Err(Error::bespoke("trailing CRC-32 did not match data"));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Bytes each list element and compound entry counts as towards
+/// [`max_total_bytes`][`DeOpts::max_total_bytes`], on top of any strings and
+/// arrays within it.
+const ENTRY_SIZE: usize = 16;
+
+/// State for lenient deserialization.
+struct Lenient {
+    /// Path to the value currently being deserialized from the root compound,
+    /// as compound keys and list indices.
+    path: Vec<String>,
+    /// Values skipped so far.
+    skipped: Vec<SkippedField>,
+}
+
+/// A compound field or list element that was skipped by
+/// [`from_bytes_lenient`][`crate::from_bytes_lenient`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkippedField {
+    /// Path to the skipped value, in the format used by
+    /// [`Value::pointer`][`crate::Value::pointer`].
+    pub path: String,
+    /// The error deserializing the value caused.
+    pub error: Error,
+}
+
+/// Something unusual in the input that did not stop it deserializing,
+/// recorded by a deserializer created with
+/// [`with_warnings`][`Deserializer::with_warnings`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    /// What kind of problem was found.
+    pub kind: WarningKind,
+    /// Description of the problem, including the key or tag involved.
+    pub message: String,
+    /// Offset into the input of the tag at fault.
+    pub offset: usize,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at offset {}", self.message, self.offset)
+    }
+}
+
+/// The kind of a [`Warning`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WarningKind {
+    /// A key appears more than once in a compound. Deserializing into a map
+    /// keeps the last value, and into a struct is an error.
+    DuplicateKey,
+    /// An empty list has an element tag other than End. Minecraft writes
+    /// these, but they cannot be preserved by a [`Value`][`crate::Value`].
+    TypedEmptyList,
+    /// The root value is not a compound.
+    RootNotCompound,
+}
+
+/// A tag read from the input. Custom tags are only produced when a
+/// [`TagHandler`] is installed.
+#[derive(Clone, Copy)]
+enum ReadTag {
+    Std(Tag),
+    Custom(u8),
+}
+
+/// Adapts an [`Input`] into a reader for a [`TagHandler`].
+struct InputReader<'a, In>(&'a mut In);
+
+impl<'de, 'a, In: Input<'de>> Read for InputReader<'a, In> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match buf.first_mut() {
+            Some(b) => {
+                // Keep the original error, which is recovered when the
+                // handler's io::Error is converted back.
+                *b = self.0.consume_byte().map_err(|e| {
+                    if e == Error::unexpected_eof() {
+                        io::Error::new(io::ErrorKind::UnexpectedEof, e)
+                    } else {
+                        io::Error::other(e)
+                    }
+                })?;
+                Ok(1)
+            }
+            None => Ok(0),
+        }
+    }
+}
+
 /// Deserializer for NBT data. See the [`de`] module for more information.
 ///
 /// [`de`]: ./index.html
@@ -244,6 +574,11 @@ pub struct Deserializer<In> {
     scratch: Vec<u8>,
     seen_root: bool,
     opts: DeOpts,
+    lenient: Option<Lenient>,
+    /// Bytes of strings and arrays that can still be read.
+    budget: Option<usize>,
+    /// Number of compounds and lists the next value is nested within.
+    depth: usize,
 }
 
 impl<'de, In> Deserializer<In>
@@ -251,11 +586,294 @@ where
     In: Input<'de>,
 {
     pub fn new(input: In, opts: DeOpts) -> Self {
+        Self::with_scratch(input, opts, Vec::new())
+    }
+
+    /// Call `observer` with each chunk of input as it is consumed, for
+    /// example to hash the raw bytes while deserializing them rather than in
+    /// a separate pass. Together the chunks are exactly the bytes read from
+    /// the input, including those of values that are skipped.
+    ///
+    /// ```
+    /// # use fastnbt::{de::Deserializer, nbt, DeOpts, Value};
+    /// # use serde::Deserialize;
+    /// let bytes = fastnbt::to_bytes(&nbt!({"a": 1})).unwrap();
+    ///
+    /// let mut seen = Vec::new();
+    /// let mut de = Deserializer::from_bytes(&bytes, DeOpts::new())
+    ///     .with_observer(|chunk: &[u8]| seen.extend_from_slice(chunk));
+    /// Value::deserialize(&mut de).unwrap();
+    /// drop(de);
+    ///
+    /// assert_eq!(seen, bytes);
+    /// ```
+    pub fn with_observer<F>(self, observer: F) -> Deserializer<input::Observed<In, F>>
+    where
+        F: FnMut(&[u8]),
+    {
+        Deserializer {
+            input: input::Observed {
+                input: self.input,
+                observer,
+                scratch: Vec::new(),
+            },
+            scratch: self.scratch,
+            seen_root: self.seen_root,
+            opts: self.opts,
+            lenient: self.lenient,
+            budget: self.budget,
+            depth: self.depth,
+        }
+    }
+
+    /// Record [`Warning`]s about unusual but valid input in `sink` while
+    /// deserializing, such as a key that appears twice in a compound. These
+    /// do not stop deserialization. Values that are skipped, such as fields
+    /// not in a struct, are not checked.
+    ///
+    /// ```
+    /// # use fastnbt::{de::{Deserializer, WarningKind}, DeOpts, Value};
+    /// # use serde::Deserialize;
+    /// // A compound with the key "a" twice.
+    /// let bytes = [10, 0, 0, 1, 0, 1, b'a', 1, 1, 0, 1, b'a', 2, 0];
+    ///
+    /// let mut warnings = Vec::new();
+    /// let mut de = Deserializer::from_bytes(&bytes, DeOpts::new()).with_warnings(&mut warnings);
+    /// let v = Value::deserialize(&mut de).unwrap();
+    ///
+    /// assert_eq!(v.pointer("/a"), Some(&Value::Byte(2)));
+    /// assert_eq!(warnings[0].kind, WarningKind::DuplicateKey);
+    /// assert_eq!(warnings[0].offset, 8);
+    /// ```
+    pub fn with_warnings(self, sink: &mut Vec<Warning>) -> Deserializer<input::Warned<'_, In>> {
+        Deserializer {
+            input: input::Warned {
+                input: self.input,
+                sink,
+            },
+            scratch: self.scratch,
+            seen_root: self.seen_root,
+            opts: self.opts,
+            lenient: self.lenient,
+            budget: self.budget,
+            depth: self.depth,
+        }
+    }
+
+    /// Create a deserializer that reuses an existing scratch buffer.
+    pub(crate) fn with_scratch(input: In, opts: DeOpts, scratch: Vec<u8>) -> Self {
         Self {
             input,
-            scratch: Vec::new(),
+            scratch,
             seen_root: false,
+            budget: opts.max_total_bytes,
             opts,
+            lenient: None,
+            depth: 0,
+        }
+    }
+
+    /// Run `f` for the contents of a compound or list, erroring if it is
+    /// nested too deeply.
+    fn nested<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        if self.depth >= input::MAX_DEPTH {
+            return Err(Error::nesting_too_deep());
+        }
+        self.depth += 1;
+        let result = f(self);
+        self.depth -= 1;
+        result
+    }
+
+    /// Take `bytes` from the budget for
+    /// [`max_total_bytes`][`DeOpts::max_total_bytes`], erroring if it is
+    /// exhausted.
+    fn spend(budget: &mut Option<usize>, max: Option<usize>, bytes: usize) -> Result<()> {
+        if let Some(remaining) = budget {
+            *remaining = remaining.checked_sub(bytes).ok_or_else(|| {
+                Error::bespoke(format!(
+                    "data exceeds the maximum total size ({} bytes)",
+                    max.unwrap_or_default()
+                ))
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Consume a string, counting it against the budget.
+    fn consume_str(&mut self) -> Result<Reference<'de, '_, str>> {
+        let s = self.input.consume_str(&mut self.scratch)?;
+        Self::spend(
+            &mut self.budget,
+            self.opts.max_total_bytes,
+            s.as_ref().len(),
+        )?;
+        Ok(s)
+    }
+
+    /// Deserialize the NBT document held in the next `size` bytes, as read by
+    /// [`nested_nbt`][`crate::helpers::nested_nbt`]. It shares this
+    /// deserializer's nesting depth and budget for
+    /// [`max_total_bytes`][`DeOpts::max_total_bytes`].
+    fn nested_document<V>(&mut self, size: usize, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        Self::spend(&mut self.budget, self.opts.max_total_bytes, size)?;
+        let bytes = self.input.consume_bytes(size, &mut self.scratch)?;
+
+        let mut inner = Deserializer::from_nested(bytes.as_ref(), &self.opts);
+        inner.depth = self.depth;
+        inner.budget = self.budget;
+        let res = visitor.visit_some(&mut inner);
+        self.budget = inner.budget;
+        res
+    }
+
+    /// Consume a compound key with `decoder` rather than as Modified UTF-8.
+    fn consume_decoded_key(&mut self, decoder: &dyn KeyDecoder) -> Result<String> {
+        let len = self.input.consume_i16()? as u16 as usize;
+        let bytes = self.consume_bytes(len)?;
+        decoder.decode_key(bytes.as_ref())
+    }
+
+    /// Consume `size` bytes of an array or similar, counting them against the
+    /// budget before reading them.
+    fn consume_bytes(&mut self, size: usize) -> Result<Reference<'de, '_, [u8]>> {
+        Self::spend(&mut self.budget, self.opts.max_total_bytes, size)?;
+        self.input.consume_bytes(size, &mut self.scratch)
+    }
+
+    /// Skip values that fail to deserialize where possible, recording them.
+    /// See [`from_bytes_lenient`][`crate::from_bytes_lenient`].
+    pub(crate) fn set_lenient(&mut self) {
+        self.lenient = Some(Lenient {
+            path: Vec::new(),
+            skipped: Vec::new(),
+        });
+    }
+
+    /// Take the values skipped so far from a lenient deserializer.
+    pub(crate) fn take_skipped(&mut self) -> Vec<SkippedField> {
+        self.lenient
+            .as_mut()
+            .map(|l| std::mem::take(&mut l.skipped))
+            .unwrap_or_default()
+    }
+
+    /// In lenient mode, note that the value at `key` within the current
+    /// compound or list is about to be deserialized.
+    fn enter_value(&mut self, key: impl FnOnce() -> String) {
+        if let Some(lenient) = &mut self.lenient {
+            lenient.path.push(key());
+        }
+    }
+
+    /// Counterpart to [`enter_value`][`Deserializer::enter_value`].
+    fn leave_value(&mut self) {
+        if let Some(lenient) = &mut self.lenient {
+            lenient.path.pop();
+        }
+    }
+
+    /// The number of values skipped so far in lenient mode, to pass to
+    /// [`skip_failed`][`Deserializer::skip_failed`].
+    fn skipped_mark(&self) -> usize {
+        self.lenient.as_ref().map_or(0, |l| l.skipped.len())
+    }
+
+    /// In lenient mode, skip the current value, which started at `start` and
+    /// failed with `error`, and record it. Anything skipped within it since
+    /// `mark` is forgotten, as it is part of this value. Returns `error` if the
+    /// value cannot be skipped.
+    fn skip_failed(&mut self, start: usize, tag: ReadTag, mark: usize, error: Error) -> Result<()> {
+        if !self.input.rewind(start) {
+            return Err(error);
+        }
+        self.skip_value(tag)?;
+
+        let lenient = self.lenient.as_mut().expect("deserializer is lenient");
+        lenient.skipped.truncate(mark);
+        lenient.skipped.push(SkippedField {
+            path: lenient
+                .path
+                .iter()
+                .map(|p| format!("/{}", p.replace('~', "~0").replace('/', "~1")))
+                .collect(),
+            error,
+        });
+        Ok(())
+    }
+
+    /// Skip the payload of a value with the given tag.
+    fn skip_value(&mut self, tag: ReadTag) -> Result<()> {
+        match tag {
+            ReadTag::Std(tag) => AnonymousValue {
+                tag,
+                de: self,
+                last_hint: Hint::None,
+            }
+            .ignore(),
+            ReadTag::Custom(id) => self.consume_custom(id).map(drop),
+        }
+    }
+
+    pub(crate) fn into_scratch(self) -> Vec<u8> {
+        self.scratch
+    }
+
+    /// Consume the length prefix of a list or array, rejecting negative lengths.
+    fn consume_len(&mut self) -> Result<usize> {
+        let len = self.input.consume_i32()?;
+        usize::try_from(len).map_err(|_| Error::invalid_size(len))
+    }
+
+    /// Consume the tag and name of the root value, returning the tag, unless
+    /// they have already been consumed.
+    fn consume_root(&mut self) -> Result<Option<Tag>> {
+        if self.seen_root {
+            return Ok(None);
+        }
+
+        let offset = self.input.position();
+        let tag = self.input.consume_tag()?;
+        if tag == Tag::End {
+            return Err(Error::bespoke("invalid nbt: root tag is end"));
+        }
+        if tag != Tag::Compound && self.input.wants_warnings() {
+            self.input.warn(Warning {
+                kind: WarningKind::RootNotCompound,
+                message: format!("root is {} rather than a compound", tag),
+                offset,
+            });
+        }
+        self.input.ignore_str()?;
+        self.seen_root = true;
+        Ok(Some(tag))
+    }
+
+    /// Check the checksum trailing the root value, if one is expected.
+    #[cfg(feature = "checksum")]
+    fn verify_checksum(&mut self) -> Result<()> {
+        match self.opts.trailing_checksum {
+            Some(checksum) => self.input.verify_checksum(checksum),
+            None => Ok(()),
+        }
+    }
+
+    fn consume_tag(&mut self) -> Result<ReadTag> {
+        let tag = self.input.consume_byte()?;
+        match Tag::try_from(tag) {
+            Ok(tag) => Ok(ReadTag::Std(tag)),
+            Err(_) if self.opts.tag_handler.is_some() => Ok(ReadTag::Custom(tag)),
+            Err(_) => Err(Error::invalid_tag(tag)),
+        }
+    }
+
+    fn consume_custom(&mut self, id: u8) -> Result<Value> {
+        match &self.opts.tag_handler {
+            Some(handler) => handler.read_payload(id, &mut InputReader(&mut self.input)),
+            None => Err(Error::invalid_tag(id)),
         }
     }
 }
@@ -266,7 +884,134 @@ impl<'a> Deserializer<input::Slice<'a>> {
     ///
     /// [`de`]: ./index.html
     pub fn from_bytes(bytes: &'a [u8], opts: DeOpts) -> Self {
-        Deserializer::new(input::Slice { data: bytes }, opts)
+        Deserializer::new(input::Slice::new(bytes), opts)
+    }
+
+    /// The input that has not been consumed yet.
+    pub(crate) fn remaining(&self) -> &'a [u8] {
+        self.input.data
+    }
+
+    /// Find the range of bytes taken up by the next value in the input, and
+    /// move past it. The range covers the value's tag, name and payload, so
+    /// can be copied verbatim into a compound elsewhere.
+    ///
+    /// At first the next value is the root compound. To find a value within a
+    /// compound, use [`enter_compound`][`Deserializer::enter_compound`] and
+    /// then [`next_name`][`Deserializer::next_name`] to find the entry, moving
+    /// past the entries before it with this method.
+    ///
+    /// ```
+    /// # use fastnbt::{de::Deserializer, nbt, DeOpts};
+    /// let data = fastnbt::to_bytes(&nbt!({"Level": {"xPos": 1}}))?;
+    ///
+    /// let mut de = Deserializer::from_bytes(&data, DeOpts::new());
+    /// de.enter_compound()?;
+    /// assert_eq!(de.next_name()?.as_deref(), Some("Level"));
+    ///
+    /// let span = de.next_value_span()?;
+    /// let level = &data[span];
+    /// assert_eq!(level[0], 10); // Compound tag.
+    /// assert_eq!(&level[3..8], b"Level");
+    /// # Ok::<(), fastnbt::error::Error>(())
+    /// ```
+    pub fn next_value_span(&mut self) -> Result<Range<usize>> {
+        let start = self.input.position();
+        let tag = self.consume_tag()?;
+        if let ReadTag::Std(Tag::End) = tag {
+            return Err(Error::bespoke("no more values in compound"));
+        }
+        self.input.ignore_str()?;
+        self.skip_value(tag)?;
+        Ok(start..self.input.position())
+    }
+
+    /// Deserialize the next value in the input into a `T`, moving past it.
+    /// Like [`next_value_span`][`Deserializer::next_value_span`] this is for
+    /// picking values out of a compound after
+    /// [`enter_compound`][`Deserializer::enter_compound`].
+    ///
+    /// ```
+    /// # use fastnbt::{de::Deserializer, nbt, DeOpts};
+    /// let data = fastnbt::to_bytes(&nbt!({"DataVersion": 3120}))?;
+    ///
+    /// let mut de = Deserializer::from_bytes(&data, DeOpts::new());
+    /// de.enter_compound()?;
+    /// assert_eq!(de.next_value::<i32>()?, 3120);
+    /// # Ok::<(), fastnbt::error::Error>(())
+    /// ```
+    pub fn next_value<T: de::Deserialize<'a>>(&mut self) -> Result<T> {
+        let tag = self.consume_tag()?;
+        if let ReadTag::Std(Tag::End) = tag {
+            return Err(Error::bespoke("no more values in compound"));
+        }
+        self.input.ignore_str()?;
+        match tag {
+            ReadTag::Std(tag) => T::deserialize(AnonymousValue {
+                tag,
+                de: self,
+                last_hint: Hint::None,
+            }),
+            ReadTag::Custom(id) => self.consume_custom(id).and_then(T::deserialize),
+        }
+    }
+
+    /// The name of the next value in the input, without moving past it. This
+    /// is `None` at the end of a compound.
+    pub fn next_name(&self) -> Result<Option<Cow<'a, str>>> {
+        let mut input = input::Slice::new(self.input.data);
+        if input.consume_byte()? == Tag::End as u8 {
+            return Ok(None);
+        }
+        match input.consume_str(&mut Vec::new())? {
+            Reference::Borrowed(s) => Ok(Some(Cow::Borrowed(s))),
+            Reference::Copied(s) => Ok(Some(Cow::Owned(s.to_owned()))),
+        }
+    }
+
+    /// The element tag and length of the next value in the input, which must
+    /// be a list, without moving past it. This allows choosing how to
+    /// deserialize a list by what it holds, such as a list that older versions
+    /// of a format wrote with different elements.
+    ///
+    /// ```
+    /// # use fastnbt::{de::Deserializer, nbt, DeOpts, Tag, Value};
+    /// let data = fastnbt::to_bytes(&nbt!({"Pos": [1.0, 2.0]}))?;
+    ///
+    /// let mut de = Deserializer::from_bytes(&data, DeOpts::new());
+    /// de.enter_compound()?;
+    /// let pos: Vec<f64> = match de.peek_list_header()? {
+    ///     (Tag::Double, _) => de.next_value()?,
+    ///     (Tag::Float, _) => de.next_value::<Vec<f32>>()?.into_iter().map(f64::from).collect(),
+    ///     (tag, _) => panic!("unexpected list of {}", tag),
+    /// };
+    /// assert_eq!(pos, [1.0, 2.0]);
+    /// # Ok::<(), fastnbt::error::Error>(())
+    /// ```
+    pub fn peek_list_header(&self) -> Result<(Tag, i32)> {
+        let mut input = input::Slice::new(self.input.data);
+        match input.consume_tag()? {
+            Tag::List => {}
+            Tag::End => return Err(Error::bespoke("no more values in compound")),
+            tag => return Err(Error::bespoke(format!("expected list, found {}", tag))),
+        }
+        input.ignore_str()?;
+
+        let element = input.consume_tag()?;
+        let len = input.consume_i32()?;
+        if len < 0 {
+            return Err(Error::invalid_size(len));
+        }
+        Ok((element, len))
+    }
+
+    /// Move into the next value in the input, which must be a compound, so
+    /// that the values that follow are its entries.
+    pub fn enter_compound(&mut self) -> Result<()> {
+        match self.consume_tag()? {
+            ReadTag::Std(Tag::Compound) => self.input.ignore_str(),
+            _ => Err(Error::bespoke("expected compound")),
+        }
     }
 }
 
@@ -274,96 +1019,248 @@ impl<R: Read> Deserializer<input::Reader<R>> {
     /// Create Deserializer for a `T` from some NBT data. See the [`de`] module
     /// for more information.
     ///
+    /// The reader is read a few bytes at a time and never past the end of the
+    /// NBT data, so pass a buffered reader if reads are expensive. See
+    /// [`from_reader`][`crate::from_reader`].
+    ///
     /// [`de`]: ./index.html
     pub fn from_reader(reader: R, opts: DeOpts) -> Self {
-        Deserializer::new(input::Reader { reader }, opts)
+        Deserializer::new(input::Reader::new(reader, &opts), opts)
+    }
+}
+
+impl<'a> Deserializer<input::Reader<Box<dyn Read + 'a>>> {
+    /// Create a deserializer for an NBT document held in a ByteArray, as read
+    /// by [`nested_nbt`][`crate::helpers::nested_nbt`], decompressing it if
+    /// needed. Only the limits of `opts` apply to it.
+    pub(crate) fn from_nested(bytes: &'a [u8], opts: &DeOpts) -> Self {
+        let opts = DeOpts {
+            max_seq_len: opts.max_seq_len,
+            max_total_bytes: opts.max_total_bytes,
+            max_input_bytes: opts.max_input_bytes,
+            ..DeOpts::new()
+        };
+
+        #[cfg(feature = "gzip")]
+        if bytes.starts_with(&[0x1f, 0x8b]) {
+            let opts = DeOpts {
+                max_input_bytes: Some(
+                    opts.max_input_bytes
+                        .unwrap_or(crate::helpers::nested_nbt::MAX_DECOMPRESSED_BYTES),
+                ),
+                ..opts
+            };
+            let decoder = flate2::bufread::GzDecoder::new(bytes);
+            return Deserializer::from_reader(Box::new(decoder), opts);
+        }
+
+        Deserializer::from_reader(Box::new(bytes), opts)
     }
 }
 
+/// Implement the methods of the deserializer for the root value. A compound is
+/// deserialized as a map of its entries, as is everything once the root has
+/// been read. Any other root value is deserialized like a value in a compound.
+macro_rules! deserialize_root {
+    ($($method:ident $(($($arg:ident: $ty:ty),*))?)*) => {
+        $(fn $method<V>(self, $($($arg: $ty,)*)? visitor: V) -> Result<V::Value>
+        where
+            V: de::Visitor<'de>,
+        {
+            let root = self.consume_root()?;
+            let value = match root {
+                Some(tag) if tag != Tag::Compound => de::Deserializer::$method(
+                    AnonymousValue {
+                        tag,
+                        de: &mut *self,
+                        last_hint: Hint::None,
+                    },
+                    $($($arg,)*)?
+                    visitor,
+                ),
+                _ => self.nested(|de| visitor.visit_map(MapAccess::new(de))),
+            }?;
+
+            #[cfg(feature = "checksum")]
+            if root.is_some() {
+                self.verify_checksum()?;
+            }
+            Ok(value)
+        })*
+    };
+}
+
 impl<'de, 'a, In> de::Deserializer<'de> for &'a mut Deserializer<In>
 where
     In: Input<'de>,
 {
     type Error = Error;
 
-    forward_to_deserialize_any! {
-        bool u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string unit unit_struct seq tuple tuple_struct
-        identifier ignored_any bytes enum newtype_struct byte_buf option
+    deserialize_root! {
+        deserialize_any deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32
+        deserialize_i64 deserialize_i128 deserialize_u8 deserialize_u16 deserialize_u32
+        deserialize_u64 deserialize_u128 deserialize_f32 deserialize_f64 deserialize_char
+        deserialize_str deserialize_string deserialize_bytes deserialize_byte_buf
+        deserialize_option deserialize_unit deserialize_seq deserialize_map
+        deserialize_identifier deserialize_ignored_any
+        deserialize_unit_struct(name: &'static str)
+        deserialize_newtype_struct(name: &'static str)
+        deserialize_tuple(len: usize)
+        deserialize_tuple_struct(name: &'static str, len: usize)
+        deserialize_struct(name: &'static str, fields: &'static [&'static str])
+        deserialize_enum(name: &'static str, variants: &'static [&'static str])
     }
+}
 
-    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
-    where
-        V: de::Visitor<'de>,
-    {
-        self.deserialize_map(visitor)
+struct MapAccess<'a, In: 'a> {
+    de: &'a mut Deserializer<In>,
+    tag: ReadTag, // current tag
+    /// Keys seen so far, when looking for duplicates to warn about.
+    keys: Option<HashSet<String>>,
+}
+
+impl<'de, 'a, In: Input<'de> + 'a> MapAccess<'a, In> {
+    pub fn new(de: &'a mut Deserializer<In>) -> Self {
+        Self {
+            keys: de.input.wants_warnings().then(HashSet::new),
+            de,
+            tag: ReadTag::Std(Tag::End),
+        }
     }
+}
 
-    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
+impl<'de, 'a, In: Input<'de> + 'a> de::MapAccess<'de> for MapAccess<'a, In> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
     where
-        V: de::Visitor<'de>,
+        K: de::DeserializeSeed<'de>,
     {
-        if !self.seen_root {
-            let peek = self.input.consume_tag()?;
+        let offset = self.de.input.position();
+        self.tag = self.de.consume_tag()?;
+        if let ReadTag::Std(Tag::End) = self.tag {
+            return Ok(None);
+        }
+        Deserializer::<In>::spend(
+            &mut self.de.budget,
+            self.de.opts.max_total_bytes,
+            ENTRY_SIZE,
+        )?;
+
+        if self.de.lenient.is_none() && self.keys.is_none() {
+            return seed.deserialize(MapKey { de: &mut *self.de }).map(Some);
+        }
 
-            match peek {
-                Tag::Compound => self.input.ignore_str()?,
-                _ => return Err(Error::no_root_compound()),
+        // In lenient mode the key is needed for the path to the value, and
+        // to check for duplicates, so it is read here rather than by
+        // MapKey.
+        let key = match self.de.opts.key_decoder.clone() {
+            Some(decoder) => {
+                let key = self.de.consume_decoded_key(&*decoder)?;
+                arr_check(&key)?;
+                Cow::Owned(key)
+            }
+            None => match self.de.consume_str()? {
+                Reference::Borrowed(s) => Cow::Borrowed(arr_check(s)?),
+                Reference::Copied(s) => Cow::Owned(arr_check(s)?.to_owned()),
+            },
+        };
+
+        if let Some(keys) = &mut self.keys {
+            if !keys.insert(key.clone().into_owned()) {
+                self.de.input.warn(Warning {
+                    kind: WarningKind::DuplicateKey,
+                    message: format!("duplicate key '{}'", key),
+                    offset,
+                });
             }
-
-            self.seen_root = true;
         }
 
-        visitor.visit_map(MapAccess::new(self))
+        self.de.enter_value(|| key.clone().into_owned());
+
+        match key {
+            Cow::Borrowed(s) => seed.deserialize(BorrowedStrDeserializer::new(s)),
+            Cow::Owned(s) => seed.deserialize(s.into_deserializer()),
+        }
+        .map(Some)
     }
 
-    fn deserialize_struct<V>(
-        self,
-        _name: &'static str,
-        _fields: &'static [&'static str],
-        visitor: V,
-    ) -> Result<V::Value>
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
     where
-        V: de::Visitor<'de>,
+        V: de::DeserializeSeed<'de>,
     {
-        self.deserialize_map(visitor)
+        let res = self.value(seed);
+        self.de.leave_value();
+        res
     }
-}
 
-struct MapAccess<'a, In: 'a> {
-    de: &'a mut Deserializer<In>,
-    tag: Tag, // current tag
-}
+    // The typed methods below are what derived structs and collections call.
+    // Unlike a seed, a type can be deserialized again, so in lenient mode they
+    // skip values that fail in place and carry on.
 
-impl<'a, In: 'a> MapAccess<'a, In> {
-    pub fn new(de: &'a mut Deserializer<In>) -> Self {
-        Self { de, tag: Tag::End }
-    }
-}
+    fn next_value<V>(&mut self) -> Result<V>
+    where
+        V: de::Deserialize<'de>,
+    {
+        if self.de.lenient.is_none() {
+            return self.next_value_seed(PhantomData);
+        }
 
-impl<'de, 'a, In: Input<'de> + 'a> de::MapAccess<'de> for MapAccess<'a, In> {
-    type Error = Error;
+        let start = self.de.input.position();
+        let mark = self.de.skipped_mark();
+        let res = match self.value(PhantomData) {
+            Ok(v) => Ok(v),
+            // A value that can be missing, such as an Option, is skipped.
+            // Otherwise the failure passes to the containing value, which is
+            // skipped instead.
+            Err(e) => match V::deserialize(Absent) {
+                Ok(v) => self.de.skip_failed(start, self.tag, mark, e).map(|()| v),
+                Err(_) => Err(e),
+            },
+        };
+        self.de.leave_value();
+        res
+    }
 
-    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    fn next_entry<K, V>(&mut self) -> Result<Option<(K, V)>>
     where
-        K: de::DeserializeSeed<'de>,
+        K: de::Deserialize<'de>,
+        V: de::Deserialize<'de>,
     {
-        self.tag = self.de.input.consume_tag()?;
-        if self.tag == Tag::End {
-            return Ok(None);
+        if self.de.lenient.is_none() {
+            return self.next_entry_seed(PhantomData, PhantomData);
         }
 
-        seed.deserialize(MapKey { de: &mut *self.de }).map(Some)
+        while let Some(key) = self.next_key_seed(PhantomData)? {
+            let start = self.de.input.position();
+            let mark = self.de.skipped_mark();
+            let res = match self.value(PhantomData) {
+                Ok(v) => Ok(Some((key, v))),
+                Err(e) => self.de.skip_failed(start, self.tag, mark, e).map(|()| None),
+            };
+            self.de.leave_value();
+            if let Some(entry) = res? {
+                return Ok(Some(entry));
+            }
+        }
+        Ok(None)
     }
+}
 
-    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+impl<'de, 'a, In: Input<'de> + 'a> MapAccess<'a, In> {
+    /// Deserialize the value of the current entry.
+    fn value<V>(&mut self, seed: V) -> Result<V::Value>
     where
         V: de::DeserializeSeed<'de>,
     {
-        seed.deserialize(AnonymousValue {
-            tag: self.tag,
-            de: &mut *self.de,
-            last_hint: Hint::None,
-        })
+        match self.tag {
+            ReadTag::Std(tag) => seed.deserialize(AnonymousValue {
+                tag,
+                de: &mut *self.de,
+                last_hint: Hint::None,
+            }),
+            ReadTag::Custom(id) => self.de.consume_custom(id).and_then(|v| seed.deserialize(v)),
+        }
     }
 }
 
@@ -376,7 +1273,7 @@ fn arr_check(key: &str) -> Result<&str> {
         && (key == BYTE_ARRAY_TOKEN || key == INT_ARRAY_TOKEN || key == LONG_ARRAY_TOKEN)
     {
         Err(Error::bespoke(
-            "compound using special fastnbt array tokens".to_string(),
+            "compound using special fastnbt array tokens",
         ))
     } else {
         Ok(key)
@@ -393,7 +1290,13 @@ where
     where
         V: de::Visitor<'de>,
     {
-        match self.de.input.consume_str(&mut self.de.scratch)? {
+        if let Some(decoder) = self.de.opts.key_decoder.clone() {
+            let key = self.de.consume_decoded_key(&*decoder)?;
+            arr_check(&key)?;
+            return visitor.visit_string(key);
+        }
+
+        match self.de.consume_str()? {
             Reference::Borrowed(s) => visitor.visit_borrowed_str(arr_check(s)?),
             Reference::Copied(s) => visitor.visit_str(arr_check(s)?),
         }
@@ -437,20 +1340,31 @@ where
         self.last_hint = Hint::None;
 
         match self.tag {
-            Tag::End => Err(Error::bespoke("expected value, found end tag".into())),
+            Tag::End => Err(Error::bespoke("expected value, found end tag")),
             Tag::Byte => v.visit_i8(self.de.input.consume_byte()? as i8),
             Tag::Short => v.visit_i16(self.de.input.consume_i16()?),
             Tag::Int => v.visit_i32(self.de.input.consume_i32()?),
             Tag::Long => v.visit_i64(self.de.input.consume_i64()?),
             Tag::Float => v.visit_f32(self.de.input.consume_f32()?),
             Tag::Double => v.visit_f64(self.de.input.consume_f64()?),
-            Tag::String => match self.de.input.consume_str(&mut self.de.scratch)? {
+            Tag::String => match self.de.consume_str()? {
                 Reference::Borrowed(s) => v.visit_borrowed_str(s),
                 Reference::Copied(s) => v.visit_str(s),
             },
             Tag::List => {
-                let tag = self.de.input.consume_tag()?;
-                let remaining = self.de.input.consume_i32()? as usize;
+                let offset = self.de.input.position();
+                let tag = self.de.consume_tag()?;
+                let remaining = self.de.consume_len()?;
+
+                if let ReadTag::Std(tag) = tag {
+                    if remaining == 0 && tag != Tag::End && self.de.input.wants_warnings() {
+                        self.de.input.warn(Warning {
+                            kind: WarningKind::TypedEmptyList,
+                            message: format!("empty list with element tag {} rather than end", tag),
+                            offset,
+                        });
+                    }
+                }
 
                 // End values have no payload. An end tag on it's own is the payload
                 // of an empty compound. A logical interpretation is that this could
@@ -460,9 +1374,9 @@ where
                 //
                 // Some old chunks store empty lists as as 'list of end', so if the
                 // size is zero we let it slide.
-                if tag == Tag::End && remaining != 0 {
+                if matches!(tag, ReadTag::Std(Tag::End)) && remaining != 0 {
                     return Err(Error::bespoke(
-                        "unexpected list of type 'end', which is not supported".into(),
+                        "unexpected list of type 'end', which is not supported",
                     ));
                 }
 
@@ -473,32 +1387,46 @@ where
                     )));
                 }
 
-                v.visit_seq(ListAccess {
-                    de: self.de,
-                    tag,
-                    remaining,
+                self.de.nested(|de| {
+                    let mut access = ListAccess {
+                        de,
+                        tag,
+                        remaining,
+                        index: 0,
+                    };
+                    let value = v.visit_seq(&mut access)?;
+
+                    // A visitor can stop early, such as one for a tuple struct
+                    // given a list that is too long.
+                    if access.remaining > 0 {
+                        return Err(de::Error::invalid_length(
+                            access.index + access.remaining,
+                            &"fewer elements in list",
+                        ));
+                    }
+                    Ok(value)
                 })
             }
-            Tag::Compound => v.visit_map(MapAccess::new(self.de)),
+            Tag::Compound => self.de.nested(|de| v.visit_map(MapAccess::new(de))),
             Tag::ByteArray => {
                 if let Hint::Seq = last_hint {
                     return Err(Error::array_as_seq());
                 }
-                let len = self.de.input.consume_i32()? as usize;
+                let len = self.de.consume_len()?;
                 v.visit_map(ArrayWrapperAccess::bytes(self.de, len)?)
             }
             Tag::IntArray => {
                 if let Hint::Seq = last_hint {
                     return Err(Error::array_as_seq());
                 }
-                let len = self.de.input.consume_i32()? as usize;
+                let len = self.de.consume_len()?;
                 v.visit_map(ArrayWrapperAccess::ints(self.de, len)?)
             }
             Tag::LongArray => {
                 if let Hint::Seq = last_hint {
                     return Err(Error::array_as_seq());
                 }
-                let len = self.de.input.consume_i32()? as usize;
+                let len = self.de.consume_len()?;
                 v.visit_map(ArrayWrapperAccess::longs(self.de, len)?)
             }
         }
@@ -508,15 +1436,14 @@ where
     where
         V: de::Visitor<'de>,
     {
-        let consume_visit =
-            |de: &mut Deserializer<In>, len: usize, el_size| match de.input.consume_bytes(
+        let consume_visit = |de: &mut Deserializer<In>, len: usize, el_size| match de
+            .consume_bytes(
                 len.checked_mul(el_size)
-                    .ok_or_else(|| Error::bespoke("overflow deserializing bytes".to_string()))?,
-                &mut de.scratch,
+                    .ok_or_else(|| Error::bespoke("overflow deserializing bytes"))?,
             )? {
-                Reference::Borrowed(bs) => visitor.visit_borrowed_bytes(bs),
-                Reference::Copied(bs) => visitor.visit_bytes(bs),
-            };
+            Reference::Borrowed(bs) => visitor.visit_borrowed_bytes(bs),
+            Reference::Copied(bs) => visitor.visit_bytes(bs),
+        };
 
         match self.tag {
             Tag::String => {
@@ -525,7 +1452,7 @@ where
             }
             Tag::List => {
                 let tag = self.de.input.consume_tag()?;
-                let remaining = self.de.input.consume_i32()? as usize;
+                let remaining = self.de.consume_len()?;
 
                 match tag {
                     Tag::Byte => consume_visit(self.de, remaining, std::mem::size_of::<i8>()),
@@ -539,11 +1466,11 @@ where
                 }
             }
             Tag::ByteArray => {
-                let remaining = self.de.input.consume_i32()? as usize;
+                let remaining = self.de.consume_len()?;
                 consume_visit(self.de, remaining, std::mem::size_of::<i8>())
             }
             Tag::LongArray => {
-                let remaining = self.de.input.consume_i32()? as usize;
+                let remaining = self.de.consume_len()?;
                 consume_visit(self.de, remaining, std::mem::size_of::<i64>())
             }
             _ => Err(Error::bespoke(format!(
@@ -575,7 +1502,7 @@ where
         // value to 'fill' that hole in your type. This means a unit type can be
         // used to ensure the presense of a value in the NBT without actually
         // caring or deserializing its contents.
-        self.de.input.ignore_value(self.tag)?;
+        self.ignore()?;
         visitor.visit_unit()
     }
 
@@ -607,6 +1534,12 @@ where
     where
         V: de::Visitor<'de>,
     {
+        if self.tag == Tag::Compound {
+            return self
+                .de
+                .nested(|de| visitor.visit_enum(VariantAccess { de }));
+        }
+
         visitor.visit_enum(UnitVariantAccess {
             de: AnonymousValue {
                 tag: self.tag,
@@ -620,7 +1553,7 @@ where
     where
         V: de::Visitor<'de>,
     {
-        self.de.input.ignore_value(self.tag)?;
+        self.ignore()?;
         visitor.visit_unit()
     }
 
@@ -647,18 +1580,59 @@ where
         }
     }
 
-    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
+        if name == NESTED_NBT_TOKEN && self.tag == Tag::ByteArray {
+            let size = self.de.consume_len()?;
+            return self.de.nested_document(size, visitor);
+        }
+
+        if name == RAW_TOKEN {
+            // The tag has already been consumed, so put it back in front of
+            // the payload.
+            let mut raw = vec![self.tag as u8];
+            input::copy_value(
+                &mut self.de.input,
+                self.tag,
+                self.de.depth,
+                &mut raw,
+                &mut self.de.scratch,
+            )?;
+            Deserializer::<In>::spend(
+                &mut self.de.budget,
+                self.de.opts.max_total_bytes,
+                raw.len(),
+            )?;
+            return visitor.visit_byte_buf(raw);
+        }
+
         visitor.visit_newtype_struct(self)
     }
 }
 
+impl<'de, 'a, In> AnonymousValue<'a, In>
+where
+    In: Input<'de>,
+{
+    fn ignore(self) -> Result<()> {
+        if self.de.opts.tag_handler.is_some() {
+            // Custom tags might be nested in this value, which the input
+            // cannot skip by itself, so walk it with the deserializer instead.
+            de::Deserializer::deserialize_any(self, de::IgnoredAny)?;
+            Ok(())
+        } else {
+            self.de.input.ignore_value(self.tag, self.de.depth)
+        }
+    }
+}
+
 struct ListAccess<'a, In: 'a> {
     de: &'a mut Deserializer<In>,
-    tag: Tag, // current tag
+    tag: ReadTag, // element tag declared by the list, used for every element
     remaining: usize,
+    index: usize, // index of the next element
 }
 
 impl<'de, 'a, In: Input<'de> + 'a> de::SeqAccess<'de> for ListAccess<'a, In> {
@@ -668,20 +1642,109 @@ impl<'de, 'a, In: Input<'de> + 'a> de::SeqAccess<'de> for ListAccess<'a, In> {
     where
         T: de::DeserializeSeed<'de>,
     {
-        if self.remaining > 0 {
-            self.remaining -= 1;
-            seed.deserialize(AnonymousValue {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+
+        let index = self.index;
+        self.de.enter_value(|| index.to_string());
+        let res = self.element(seed);
+        self.de.leave_value();
+        res.map(Some)
+    }
+
+    // Collections call this rather than the seed method. In lenient mode,
+    // elements that fail are skipped in place and left out.
+    fn next_element<T>(&mut self) -> Result<Option<T>>
+    where
+        T: de::Deserialize<'de>,
+    {
+        if self.de.lenient.is_none() {
+            return self.next_element_seed(PhantomData);
+        }
+
+        while self.remaining > 0 {
+            let index = self.index;
+            let start = self.de.input.position();
+            let mark = self.de.skipped_mark();
+            self.de.enter_value(|| index.to_string());
+            let res = match self.element(PhantomData) {
+                Ok(v) => Ok(Some(v)),
+                Err(e) => self.de.skip_failed(start, self.tag, mark, e).map(|()| None),
+            };
+            self.de.leave_value();
+            if let Some(v) = res? {
+                return Ok(Some(v));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+impl<'de, 'a, In: Input<'de> + 'a> ListAccess<'a, In> {
+    /// Deserialize the next element, of which there must be at least one.
+    fn element<T>(&mut self, seed: T) -> Result<T::Value>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        self.remaining -= 1;
+        self.index += 1;
+        Deserializer::<In>::spend(
+            &mut self.de.budget,
+            self.de.opts.max_total_bytes,
+            ENTRY_SIZE,
+        )?;
+
+        let res = match self.tag {
+            ReadTag::Std(tag) => seed.deserialize(AnonymousValue {
                 de: &mut *self.de,
                 last_hint: Hint::None,
-                tag: self.tag,
-            })
-            .map(Some)
-        } else {
-            Ok(None)
+                tag,
+            }),
+            ReadTag::Custom(id) => self.de.consume_custom(id).and_then(|v| seed.deserialize(v)),
+        };
+
+        // A corrupt list can declare more elements than the input holds.
+        match res {
+            Err(e) if e == Error::unexpected_eof() => Err(Error::bespoke(format!(
+                "eof: list declared {} elements but input ended in element {}",
+                self.index + self.remaining,
+                self.index - 1
+            ))),
+            res => res,
         }
     }
 }
 
+/// Deserializer for a value that is not there, used in lenient mode to find
+/// whether a struct field can be missing.
+struct Absent;
+
+impl<'de> de::Deserializer<'de> for Absent {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        Err(Error::bespoke("value is missing"))
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_none()
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
 struct UnitVariantAccess<'a, In: 'a> {
     de: AnonymousValue<'a, In>,
 }
@@ -741,6 +1804,95 @@ impl<'de, 'a, In: Input<'de> + 'a> de::VariantAccess<'de> for UnitVariantAccess<
     }
 }
 
+/// Access to an enum variant stored as a compound with the variant name as its
+/// only key, and the variant's data as the value.
+struct VariantAccess<'a, In: 'a> {
+    de: &'a mut Deserializer<In>,
+}
+
+/// The value of a variant stored in a compound.
+struct VariantValue<'a, In: 'a> {
+    de: &'a mut Deserializer<In>,
+    tag: Tag,
+}
+
+impl<'de, 'a, In: Input<'de> + 'a> de::EnumAccess<'de> for VariantAccess<'a, In> {
+    type Error = Error;
+    type Variant = VariantValue<'a, In>;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant)>
+    where
+        V: serde::de::DeserializeSeed<'de>,
+    {
+        let tag = match self.de.consume_tag()? {
+            ReadTag::Std(Tag::End) => {
+                return Err(Error::bespoke(
+                    "expected enum variant, found empty compound",
+                ))
+            }
+            ReadTag::Std(tag) => tag,
+            ReadTag::Custom(id) => return Err(Error::invalid_tag(id)),
+        };
+        let variant = seed.deserialize(MapKey { de: &mut *self.de })?;
+        Ok((variant, VariantValue { de: self.de, tag }))
+    }
+}
+
+impl<'de, 'a, In: Input<'de> + 'a> VariantValue<'a, In> {
+    fn value(&mut self) -> AnonymousValue<'_, In> {
+        AnonymousValue {
+            tag: self.tag,
+            de: &mut *self.de,
+            last_hint: Hint::None,
+        }
+    }
+
+    /// Consume the end of the compound holding the variant.
+    fn end<T>(self, value: T) -> Result<T> {
+        match self.de.consume_tag()? {
+            ReadTag::Std(Tag::End) => Ok(value),
+            _ => Err(Error::bespoke(
+                "expected enum variant compound to have a single key",
+            )),
+        }
+    }
+}
+
+impl<'de, 'a, In: Input<'de> + 'a> de::VariantAccess<'de> for VariantValue<'a, In> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        Err(de::Error::invalid_type(
+            de::Unexpected::Map,
+            &"unit variant, stored as a string",
+        ))
+    }
+
+    fn newtype_variant_seed<T>(mut self, seed: T) -> Result<T::Value>
+    where
+        T: serde::de::DeserializeSeed<'de>,
+    {
+        let value = seed.deserialize(self.value())?;
+        self.end(value)
+    }
+
+    fn tuple_variant<V>(mut self, len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        let value = de::Deserializer::deserialize_tuple(self.value(), len, visitor)?;
+        self.end(value)
+    }
+
+    fn struct_variant<V>(mut self, fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        let value = de::Deserializer::deserialize_struct(self.value(), "", fields, visitor)?;
+        self.end(value)
+    }
+}
+
 enum State {
     Unread,
     Read,
@@ -759,7 +1911,7 @@ impl<'a, In: 'a> ArrayWrapperAccess<'a, In> {
             de,
             bytes_size: size
                 .checked_mul(1)
-                .ok_or_else(|| Error::bespoke("nbt array too large".to_string()))?,
+                .ok_or_else(|| Error::bespoke("nbt array too large"))?,
             token: BYTE_ARRAY_TOKEN,
             state: State::Unread,
         })
@@ -770,7 +1922,7 @@ impl<'a, In: 'a> ArrayWrapperAccess<'a, In> {
             de,
             bytes_size: size
                 .checked_mul(4)
-                .ok_or_else(|| Error::bespoke("nbt array too large".to_string()))?,
+                .ok_or_else(|| Error::bespoke("nbt array too large"))?,
             token: INT_ARRAY_TOKEN,
             state: State::Unread,
         })
@@ -781,7 +1933,7 @@ impl<'a, In: 'a> ArrayWrapperAccess<'a, In> {
             de,
             bytes_size: size
                 .checked_mul(8)
-                .ok_or_else(|| Error::bespoke("nbt array too large".to_string()))?,
+                .ok_or_else(|| Error::bespoke("nbt array too large"))?,
             token: LONG_ARRAY_TOKEN,
             state: State::Unread,
         })
@@ -808,10 +1960,7 @@ impl<'de, 'a, In: Input<'de> + 'a> de::MapAccess<'de> for ArrayWrapperAccess<'a,
     where
         V: de::DeserializeSeed<'de>,
     {
-        let data = self
-            .de
-            .input
-            .consume_bytes(self.bytes_size, &mut self.de.scratch)?;
+        let data = self.de.consume_bytes(self.bytes_size)?;
 
         match data {
             Reference::Borrowed(bs) => seed.deserialize(BorrowedBytesDeserializer::new(bs)),
@@ -819,3 +1968,63 @@ impl<'de, 'a, In: Input<'de> + 'a> de::MapAccess<'de> for ArrayWrapperAccess<'a,
         }
     }
 }
+
+/// Deserializer for the data of an NBT array whose length is not stored in the
+/// usual place directly before its elements.
+///
+/// This is an advanced escape hatch for nonstandard formats, for example ones
+/// that store an array's element count in a sibling field. A custom
+/// `Deserialize` implementation can read the count and the raw big-endian
+/// element data, then use this to produce any type that a normal NBT array can
+/// be deserialized into, such as [`IntArray`][`crate::IntArray`], the types in
+/// [`borrow`][`crate::borrow`], or [`Value`].
+///
+/// ```
+/// use fastnbt::{de::ArrayDeserializer, IntArray, Tag};
+/// use serde::Deserialize;
+///
+/// let data = [0, 0, 0, 1, 0, 0, 0, 2, 0xff, 0xff, 0xff, 0xff];
+/// let de = ArrayDeserializer::new(Tag::IntArray, 3, &data);
+/// let array = IntArray::deserialize(de)?;
+/// assert_eq!(*array, [1, 2, -1]);
+/// # Ok::<(), fastnbt::error::Error>(())
+/// ```
+pub struct ArrayDeserializer<'de> {
+    tag: Tag,
+    len: usize,
+    data: &'de [u8],
+}
+
+impl<'de> ArrayDeserializer<'de> {
+    /// Create a deserializer for an array of type `tag` with `len` elements,
+    /// stored in `data` in the same format as a normal NBT array. `tag` must
+    /// be one of the array tags, and `data` must hold at least `len` elements.
+    /// Any further data is ignored.
+    pub fn new(tag: Tag, len: usize, data: &'de [u8]) -> Self {
+        Self { tag, len, data }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for ArrayDeserializer<'de> {
+    type Error = Error;
+
+    forward_to_deserialize_any! {
+        bool u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string unit unit_struct seq tuple tuple_struct
+        identifier ignored_any bytes enum newtype_struct byte_buf option map struct
+    }
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        let mut de = Deserializer::from_bytes(self.data, DeOpts::new());
+        match self.tag {
+            Tag::ByteArray => visitor.visit_map(ArrayWrapperAccess::bytes(&mut de, self.len)?),
+            Tag::IntArray => visitor.visit_map(ArrayWrapperAccess::ints(&mut de, self.len)?),
+            Tag::LongArray => visitor.visit_map(ArrayWrapperAccess::longs(&mut de, self.len)?),
+            _ => Err(Error::bespoke(
+                "ArrayDeserializer requires an NBT array tag",
+            )),
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use std::io::Read;
+
+use crate::{
+    decode_int_array, decode_long_array,
+    error::{Error, Result},
+    input::{self, try_size, Input, MAX_DEPTH},
+    DeOpts, Tag,
+};
+
+/// Low-level reader of NBT data, for parsing NBT by hand where serde's data
+/// model doesn't fit. This is the counterpart of
+/// [`NbtWriter`][`crate::ser::NbtWriter`].
+///
+/// Each method reads the next part of the input, so they must be called in
+/// the order the parts appear. A named value is made of a tag from
+/// [`read_tag`][`NbtReader::read_tag`], a name from
+/// [`read_name`][`NbtReader::read_name`], then the payload from the method
+/// for that tag. Values in lists are just payloads.
+///
+/// ```
+/// use fastnbt::{de::NbtReader, Tag};
+///
+/// let data = fastnbt::to_bytes(&fastnbt::nbt!({"DataVersion": 3120}))?;
+/// let mut reader = NbtReader::new(data.as_slice());
+///
+/// assert_eq!(reader.read_tag()?, Tag::Compound);
+/// assert_eq!(reader.read_name()?, "");
+/// assert_eq!(reader.read_tag()?, Tag::Int);
+/// assert_eq!(reader.read_name()?, "DataVersion");
+/// assert_eq!(reader.read_int()?, 3120);
+/// assert_eq!(reader.read_tag()?, Tag::End);
+/// # Ok::<(), fastnbt::error::Error>(())
+/// ```
+pub struct NbtReader<R: Read> {
+    input: input::Reader<R>,
+    scratch: Vec<u8>,
+}
+
+impl<R: Read> NbtReader<R> {
+    /// Create a reader of the NBT data in `reader`.
+    pub fn new(reader: R) -> Self {
+        Self {
+            input: input::Reader::new(reader, &DeOpts::new()),
+            scratch: Vec::new(),
+        }
+    }
+
+    /// Return the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.input.reader.inner
+    }
+
+    /// Read the tag of the next value. Errors if the input has ended or the
+    /// byte is not a valid tag.
+    pub fn read_tag(&mut self) -> Result<Tag> {
+        self.input.consume_tag()
+    }
+
+    /// Read the name of a value, which follows its tag. Errors if the input
+    /// ends within the name or it is not valid CESU-8. Its length is unsigned
+    /// so cannot be negative.
+    pub fn read_name(&mut self) -> Result<String> {
+        self.read_string()
+    }
+
+    /// Read the payload of a [`Tag::Byte`]. Errors if the input ends first.
+    pub fn read_byte(&mut self) -> Result<i8> {
+        Ok(self.input.consume_byte()? as i8)
+    }
+
+    /// Read the payload of a [`Tag::Short`]. Errors if the input ends first.
+    pub fn read_short(&mut self) -> Result<i16> {
+        self.input.consume_i16()
+    }
+
+    /// Read the payload of a [`Tag::Int`]. Errors if the input ends first.
+    pub fn read_int(&mut self) -> Result<i32> {
+        self.input.consume_i32()
+    }
+
+    /// Read the payload of a [`Tag::Long`]. Errors if the input ends first.
+    pub fn read_long(&mut self) -> Result<i64> {
+        self.input.consume_i64()
+    }
+
+    /// Read the payload of a [`Tag::Float`]. Errors if the input ends first.
+    pub fn read_float(&mut self) -> Result<f32> {
+        self.input.consume_f32()
+    }
+
+    /// Read the payload of a [`Tag::Double`]. Errors if the input ends first.
+    pub fn read_double(&mut self) -> Result<f64> {
+        self.input.consume_f64()
+    }
+
+    /// Read the payload of a [`Tag::String`]. Errors if the input ends within
+    /// the string or it is not valid CESU-8. Its length is unsigned so cannot
+    /// be negative.
+    pub fn read_string(&mut self) -> Result<String> {
+        Ok(self
+            .input
+            .consume_str(&mut self.scratch)?
+            .as_ref()
+            .to_owned())
+    }
+
+    /// Read the payload of a [`Tag::ByteArray`]. Errors if its length is negative or
+    /// the input ends within the array.
+    pub fn read_byte_array(&mut self) -> Result<Vec<i8>> {
+        let len = self.read_array_len(1)?;
+        let data = self.input.consume_bytes(len, &mut self.scratch)?;
+        Ok(data.as_ref().iter().map(|&b| b as i8).collect())
+    }
+
+    /// Read the payload of a [`Tag::IntArray`]. Errors if its length is negative or
+    /// the input ends within the array.
+    pub fn read_int_array(&mut self) -> Result<Vec<i32>> {
+        let len = self.read_array_len(4)?;
+        let data = self.input.consume_bytes(len, &mut self.scratch)?;
+        Ok(decode_int_array(data.as_ref()))
+    }
+
+    /// Read the payload of a [`Tag::LongArray`]. Errors if its length is negative or
+    /// the input ends within the array.
+    pub fn read_long_array(&mut self) -> Result<Vec<i64>> {
+        let len = self.read_array_len(8)?;
+        let data = self.input.consume_bytes(len, &mut self.scratch)?;
+        Ok(decode_long_array(data.as_ref()))
+    }
+
+    /// Read the element tag and length of a list. The elements follow as
+    /// payloads without tags or names. Errors if the input ends within the
+    /// header, the element tag is invalid or the length is negative.
+    pub fn read_list_header(&mut self) -> Result<(Tag, usize)> {
+        let tag = self.read_tag()?;
+        let len = self.read_int()?;
+        let len = usize::try_from(len).map_err(|_| Error::invalid_size(len))?;
+        Ok((tag, len))
+    }
+
+    /// Skip the payload of a value with the given tag, including everything
+    /// inside it for compounds and lists.
+    pub fn skip_payload(&mut self, tag: Tag) -> Result<()> {
+        self.skip_nested(tag, 0)
+    }
+
+    fn skip_nested(&mut self, tag: Tag, depth: usize) -> Result<()> {
+        if matches!(tag, Tag::Compound | Tag::List) && depth >= MAX_DEPTH {
+            return Err(Error::nesting_too_deep());
+        }
+
+        match tag {
+            Tag::End => Err(Error::bespoke("End tag has no payload")),
+            Tag::Compound => loop {
+                let tag = self.read_tag()?;
+                if tag == Tag::End {
+                    return Ok(());
+                }
+                self.input.ignore_str()?;
+                self.skip_nested(tag, depth + 1)?;
+            },
+            Tag::List => {
+                let (element, len) = self.read_list_header()?;
+                if element == Tag::End && len != 0 {
+                    return Err(Error::bespoke("non-empty list with End element tag"));
+                }
+                for _ in 0..len {
+                    self.skip_nested(element, depth + 1)?;
+                }
+                Ok(())
+            }
+            _ => self.input.ignore_value(tag, depth),
+        }
+    }
+
+    /// Read an array length and return the size of the array in bytes.
+    fn read_array_len(&mut self, element_size: usize) -> Result<usize> {
+        let len = self.read_int()?;
+        try_size(len, element_size)
+    }
+}
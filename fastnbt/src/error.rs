@@ -1,9 +1,13 @@
 //! Contains the Error and Result type used by the deserializer.
-use std::fmt::Display;
+use std::{borrow::Cow, fmt::Display};
 
 /// Various errors that can occur during deserialization.
+///
+/// Errors with a fixed message, such as many of those for invalid NBT, do not
+/// allocate. Errors that serde creates, such as for a value of the wrong type
+/// or a missing field, are formatted and so are allocated.
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Error(String);
+pub struct Error(Cow<'static, str>);
 
 /// Convenience type for Result.
 pub type Result<T> = std::result::Result<T, Error>;
@@ -18,7 +22,7 @@ impl std::fmt::Display for Error {
 
 impl serde::de::Error for Error {
     fn custom<T: Display>(msg: T) -> Self {
-        Error(msg.to_string())
+        Error(Cow::Owned(msg.to_string()))
     }
 }
 
@@ -28,49 +32,65 @@ impl serde::ser::Error for Error {
     where
         T: Display,
     {
-        Error(msg.to_string())
+        Error(Cow::Owned(msg.to_string()))
     }
 }
 
 impl From<std::io::Error> for Error {
     fn from(e: std::io::Error) -> Self {
-        Error(format!("io error: {}", e))
+        // Running out of a reader is the same problem as running out of a
+        // slice, so report it the same way.
+        if e.kind() == std::io::ErrorKind::UnexpectedEof {
+            return Error::unexpected_eof();
+        }
+        // Our own errors, passed through a reader, are reported unchanged.
+        if let Some(e) = e.get_ref().and_then(|e| e.downcast_ref::<Error>()) {
+            return e.clone();
+        }
+        Error::bespoke(format!("io error: {}", e))
     }
 }
 
 impl Error {
     pub(crate) fn invalid_tag(tag: u8) -> Error {
-        Error(format!("invalid nbt tag value: {}", tag))
+        Error::bespoke(format!("invalid nbt tag value: {}", tag))
     }
 
     pub(crate) fn invalid_size(size: i32) -> Error {
-        Error(format!("invalid nbt list/array size: {}", size))
+        Error::bespoke(format!("invalid nbt list/array size: {}", size))
     }
 
     pub(crate) fn no_root_compound() -> Error {
-        Error("invalid nbt: no root compound".to_owned())
+        Error::bespoke("invalid nbt: no root compound")
     }
 
     pub(crate) fn nonunicode_string(data: &[u8]) -> Error {
-        Error(format!(
+        Error::bespoke(format!(
             "invalid nbt string: nonunicode: {}",
             String::from_utf8_lossy(data)
         ))
     }
 
     pub(crate) fn unexpected_eof() -> Error {
-        Error("eof: unexpectedly ran out of input".to_owned())
+        Error::bespoke("eof: unexpectedly ran out of input")
+    }
+
+    pub(crate) fn nesting_too_deep() -> Error {
+        Error::bespoke(format!(
+            "nesting exceeds the limit of {}",
+            crate::input::MAX_DEPTH
+        ))
     }
 
     pub(crate) fn array_as_seq() -> Error {
-        Error("expected NBT Array, found seq: use ByteArray, IntArray or LongArray types".into())
+        Error::bespoke("expected NBT Array, found seq: use ByteArray, IntArray or LongArray types")
     }
 
     pub(crate) fn array_as_other() -> Error {
-        Error("expected NBT Array: use ByteArray, IntArray or LongArray types".into())
+        Error::bespoke("expected NBT Array: use ByteArray, IntArray or LongArray types")
     }
 
-    pub(crate) fn bespoke(msg: String) -> Error {
-        Error(msg)
+    pub(crate) fn bespoke(msg: impl Into<Cow<'static, str>>) -> Error {
+        Error(msg.into())
     }
 }
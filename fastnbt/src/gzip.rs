@@ -0,0 +1,69 @@
+//! Deserialize GZip compressed NBT data, such as player and `level.dat` files.
+//!
+//! Some tools concatenate several GZip members into one file. [`from_gzip_reader`]
+//! only reads the first member and reports an error if more follow, rather than
+//! silently ignoring them. Use [`from_gzip_reader_all`] to read every member.
+//!
+//! This module requires the `gzip` feature, which is enabled by default.
+
+use std::io::{self, BufRead, BufReader, Read};
+
+use flate2::bufread::{GzDecoder, MultiGzDecoder};
+use serde::de::DeserializeOwned;
+
+use crate::{
+    de::Deserializer,
+    error::{Error, Result},
+    DeOpts,
+};
+
+/// Deserialize a `T` from a reader of GZip compressed NBT data.
+///
+/// This errors if the data contains more than one GZip member, see
+/// [`from_gzip_reader_all`].
+///
+/// ```no_run
+/// # use fastnbt::Value;
+/// let file = std::fs::File::open("level.dat")?;
+/// let level: Value = fastnbt::gzip::from_gzip_reader(file)?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn from_gzip_reader<R, T>(reader: R) -> Result<T>
+where
+    R: Read,
+    T: DeserializeOwned,
+{
+    let mut decoder = GzDecoder::new(BufReader::new(reader));
+    let mut de = Deserializer::from_reader(&mut decoder, DeOpts::new());
+    let t = T::deserialize(&mut de)?;
+
+    // Finish the member so that its trailer is consumed, then check whether
+    // anything follows it.
+    io::copy(&mut decoder, &mut io::sink())?;
+    if !decoder.into_inner().fill_buf()?.is_empty() {
+        return Err(Error::bespoke(
+            "data contains multiple gzip members, use from_gzip_reader_all",
+        ));
+    }
+
+    Ok(t)
+}
+
+/// Deserialize every NBT document in a reader of GZip compressed data that
+/// may contain several GZip members. The members are decompressed as one
+/// stream, and each NBT document in it is deserialized in turn.
+pub fn from_gzip_reader_all<R, T>(reader: R) -> Result<Vec<T>>
+where
+    R: Read,
+    T: DeserializeOwned,
+{
+    let mut decoded = BufReader::new(MultiGzDecoder::new(BufReader::new(reader)));
+    let mut values = Vec::new();
+
+    while !decoded.fill_buf()?.is_empty() {
+        let mut de = Deserializer::from_reader(&mut decoded, DeOpts::new());
+        values.push(T::deserialize(&mut de)?);
+    }
+
+    Ok(values)
+}
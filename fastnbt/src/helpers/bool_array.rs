@@ -0,0 +1,50 @@
+//! (De)serialize a `Vec<bool>` as a [`ByteArray`] of 0 and 1 values. NBT has
+//! no boolean array type, and this is a common convention for flag data.
+//!
+//! Without this helper a `Vec<bool>` is (de)serialized as an NBT list of
+//! `Byte`s instead.
+//!
+//! When deserializing, any non-zero byte is `true`.
+//!
+//! ```rust
+//! use fastnbt::ByteArray;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Flags {
+//!     #[serde(with = "fastnbt::helpers::bool_array")]
+//!     flags: Vec<bool>,
+//! }
+//!
+//! #[derive(Deserialize)]
+//! struct Raw {
+//!     flags: ByteArray,
+//! }
+//!
+//! let bytes = fastnbt::to_bytes(&Flags {
+//!     flags: vec![true, false, true],
+//! })
+//! .unwrap();
+//!
+//! let raw: Raw = fastnbt::from_bytes(&bytes).unwrap();
+//! assert_eq!(*raw.flags, [1, 0, 1]);
+//! ```
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::ByteArray;
+
+pub fn serialize<S>(data: &[bool], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    ByteArray::new(data.iter().map(|&b| b as i8).collect()).serialize(serializer)
+}
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<bool>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let data = ByteArray::deserialize(deserializer)?;
+    Ok(data.iter().map(|&b| b != 0).collect())
+}
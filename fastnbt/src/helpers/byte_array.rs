@@ -0,0 +1,84 @@
+//! (De)serialize a `Vec<u8>` as a [`ByteArray`][`crate::ByteArray`], reading
+//! and writing the bytes in bulk.
+//!
+//! By default serde treats a `Vec<u8>` like any other `Vec`, so it is
+//! (de)serialized as an NBT list of `Byte`s, one element at a time. With this
+//! helper the field uses the ByteArray tag instead, and the whole buffer is
+//! written in one go and filled with a single copy when deserializing. The
+//! bytes are stored unchanged, so values above 127 appear as negative bytes
+//! in the NBT.
+//!
+//! ```rust
+//! use fastnbt::ByteArray;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Blob {
+//!     #[serde(with = "fastnbt::helpers::byte_array")]
+//!     data: Vec<u8>,
+//! }
+//!
+//! #[derive(Deserialize)]
+//! struct Raw {
+//!     data: ByteArray,
+//! }
+//!
+//! let bytes = fastnbt::to_bytes(&Blob {
+//!     data: vec![1, 2, 255],
+//! })
+//! .unwrap();
+//!
+//! let raw: Raw = fastnbt::from_bytes(&bytes).unwrap();
+//! assert_eq!(*raw.data, [1, 2, -1]);
+//! ```
+
+use serde::{de::Visitor, Deserializer, Serialize, Serializer};
+use serde_bytes::{ByteBuf, Bytes};
+
+use crate::BYTE_ARRAY_TOKEN;
+
+pub fn serialize<S>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    #[derive(Serialize)]
+    struct Inner<'a> {
+        __fastnbt_byte_array: &'a Bytes,
+    }
+
+    Inner {
+        __fastnbt_byte_array: Bytes::new(data),
+    }
+    .serialize(serializer)
+}
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct InnerVisitor;
+    impl<'de> Visitor<'de> for InnerVisitor {
+        type Value = Vec<u8>;
+
+        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            formatter.write_str("byte array")
+        }
+
+        fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+        where
+            A: serde::de::MapAccess<'de>,
+        {
+            let token = map.next_key::<&str>()?.ok_or_else(|| {
+                serde::de::Error::custom("expected NBT byte array token, but got empty map")
+            })?;
+            let data = map.next_value::<ByteBuf>()?;
+
+            if token == BYTE_ARRAY_TOKEN {
+                Ok(data.into_vec())
+            } else {
+                Err(serde::de::Error::custom("expected NBT byte array token"))
+            }
+        }
+    }
+    deserializer.deserialize_map(InnerVisitor)
+}
@@ -0,0 +1,98 @@
+//! (De)serialize a `[u8; N]` as a [`ByteArray`][`crate::ByteArray`] of exactly
+//! `N` bytes, such as a raw UUID.
+//!
+//! By default serde treats a fixed size array as a tuple, so it is
+//! (de)serialized as an NBT list of `Byte`s, one element at a time. With this
+//! helper the field uses the ByteArray tag instead and is copied in one go,
+//! like [`byte_array`][`super::byte_array`] does for a `Vec<u8>`.
+//! Deserializing a ByteArray of any other length is an error.
+//!
+//! ```rust
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Entity {
+//!     #[serde(with = "fastnbt::helpers::fixed_byte_array")]
+//!     uuid: [u8; 16],
+//! }
+//!
+//! let bytes = fastnbt::to_bytes(&Entity { uuid: [7; 16] }).unwrap();
+//! let entity: Entity = fastnbt::from_bytes(&bytes).unwrap();
+//! assert_eq!(entity.uuid, [7; 16]);
+//! ```
+
+use std::fmt;
+
+use serde::{
+    de::{DeserializeSeed, Visitor},
+    Deserializer, Serializer,
+};
+
+use crate::BYTE_ARRAY_TOKEN;
+
+pub fn serialize<S, const N: usize>(data: &[u8; N], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    super::byte_array::serialize(data, serializer)
+}
+
+pub fn deserialize<'de, D, const N: usize>(deserializer: D) -> Result<[u8; N], D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct InnerVisitor<const N: usize>;
+    impl<'de, const N: usize> Visitor<'de> for InnerVisitor<N> {
+        type Value = [u8; N];
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            write!(formatter, "byte array of length {}", N)
+        }
+
+        fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+        where
+            A: serde::de::MapAccess<'de>,
+        {
+            let token = map.next_key::<&str>()?.ok_or_else(|| {
+                serde::de::Error::custom("expected NBT byte array token, but got empty map")
+            })?;
+
+            if token == BYTE_ARRAY_TOKEN {
+                map.next_value_seed(FixedBytes::<N>)
+            } else {
+                Err(serde::de::Error::custom("expected NBT byte array token"))
+            }
+        }
+    }
+    deserializer.deserialize_map(InnerVisitor::<N>)
+}
+
+/// Copies the bytes of the array straight into a `[u8; N]`, whether or not
+/// they can be borrowed.
+struct FixedBytes<const N: usize>;
+
+impl<'de, const N: usize> DeserializeSeed<'de> for FixedBytes<N> {
+    type Value = [u8; N];
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_bytes(self)
+    }
+}
+
+impl<'de, const N: usize> Visitor<'de> for FixedBytes<N> {
+    type Value = [u8; N];
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{} bytes", N)
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        v.try_into().map_err(|_| E::invalid_length(v.len(), &self))
+    }
+}
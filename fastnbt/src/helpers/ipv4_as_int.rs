@@ -0,0 +1,48 @@
+//! (De)serialize an [`Ipv4Addr`] as a single packed NBT `Int`.
+//!
+//! The octets are packed in big-endian (network) order, so the first octet is
+//! the most significant byte of the `Int`. For example `192.168.0.1` is stored
+//! as `0xC0A80001`, which as a signed `Int` is `-1062731775`.
+//!
+//! ```rust
+//! use std::net::Ipv4Addr;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Server {
+//!     #[serde(with = "fastnbt::helpers::ipv4_as_int")]
+//!     ip: Ipv4Addr,
+//! }
+//!
+//! #[derive(Deserialize)]
+//! struct Raw {
+//!     ip: i32,
+//! }
+//!
+//! let bytes = fastnbt::to_bytes(&Server {
+//!     ip: Ipv4Addr::new(192, 168, 0, 1),
+//! })
+//! .unwrap();
+//!
+//! let raw: Raw = fastnbt::from_bytes(&bytes).unwrap();
+//! assert_eq!(raw.ip as u32, 0xC0A80001);
+//! ```
+
+use std::net::Ipv4Addr;
+
+use serde::{Deserialize, Deserializer, Serializer};
+
+pub fn serialize<S>(addr: &Ipv4Addr, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_i32(u32::from(*addr) as i32)
+}
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Ipv4Addr, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let packed = i32::deserialize(deserializer)?;
+    Ok(Ipv4Addr::from(packed as u32))
+}
@@ -0,0 +1,21 @@
+//! Helpers for use with serde's `with` attribute, for (de)serializing Rust
+//! types that have no direct equivalent in NBT using common conventions.
+//!
+//! ```rust
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Flags {
+//!     #[serde(with = "fastnbt::helpers::bool_array")]
+//!     flags: Vec<bool>,
+//! }
+//! ```
+//!
+//! See each module for the exact NBT representation used.
+
+pub mod bool_array;
+pub mod byte_array;
+pub mod fixed_byte_array;
+pub mod ipv4_as_int;
+pub mod nested_nbt;
+pub mod one_or_many;
@@ -0,0 +1,150 @@
+//! (De)serialize a value as a whole NBT document stored in a
+//! [`ByteArray`][`crate::ByteArray`].
+//!
+//! Some data embeds one NBT document inside another, as the bytes of a
+//! ByteArray field. With this helper the field is the inner document's Rust
+//! type, and the outer and inner documents are (de)serialized in one go. The
+//! inner document is written uncompressed. When deserializing, an inner
+//! document that is GZip compressed is decompressed first if the `gzip`
+//! feature is enabled. The `gzip` submodule, which requires that feature,
+//! writes it compressed too.
+//!
+//! When deserializing with this crate, the limits set with
+//! [`DeOpts`][`crate::DeOpts`] for the outer document also apply to the
+//! inner one, with [`max_total_bytes`][`crate::DeOpts::max_total_bytes`]
+//! counting both together. A compressed inner document is read as it is
+//! decompressed, and may decompress to at most
+//! [`max_input_bytes`][`crate::DeOpts::max_input_bytes`], or 64 MiB if that is
+//! not set.
+//!
+//! ```rust
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize, PartialEq, Debug)]
+//! struct Inner {
+//!     name: String,
+//! }
+//!
+//! #[derive(Serialize, Deserialize, PartialEq, Debug)]
+//! struct Outer {
+//!     #[serde(with = "fastnbt::helpers::nested_nbt")]
+//!     inner: Inner,
+//! }
+//!
+//! let outer = Outer {
+//!     inner: Inner { name: "Steve".to_owned() },
+//! };
+//! let bytes = fastnbt::to_bytes(&outer).unwrap();
+//! assert_eq!(fastnbt::from_bytes::<Outer>(&bytes).unwrap(), outer);
+//! ```
+
+use std::marker::PhantomData;
+
+use serde::{
+    de::{DeserializeOwned, Visitor},
+    Deserializer, Serialize, Serializer,
+};
+
+use super::byte_array;
+
+/// Name of the newtype struct the inner document is requested as. This
+/// crate's deserializer recognises it, and passes a deserializer for the inner
+/// document to `visit_some`.
+pub(crate) const NESTED_NBT_TOKEN: &str = "__fastnbt_nested_nbt";
+
+/// The most a compressed inner document may decompress to, unless
+/// `max_input_bytes` is set.
+#[cfg(feature = "gzip")]
+pub(crate) const MAX_DECOMPRESSED_BYTES: usize = 64 << 20;
+
+pub fn serialize<S, T>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Serialize,
+{
+    let bytes = crate::to_bytes(value).map_err(serde::ser::Error::custom)?;
+    byte_array::serialize(&bytes, serializer)
+}
+
+pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned,
+{
+    deserializer.deserialize_newtype_struct(NESTED_NBT_TOKEN, NestedVisitor(PhantomData))
+}
+
+struct NestedVisitor<T>(PhantomData<T>);
+
+impl<'de, T: DeserializeOwned> Visitor<'de> for NestedVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("byte array holding an NBT document")
+    }
+
+    // This crate's deserializer passes the inner document.
+    fn visit_some<D>(self, deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer)
+    }
+
+    // Other deserializers pass themselves, so the inner document is
+    // deserialized without any options.
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let bytes = byte_array::deserialize(deserializer)?;
+        let mut de = crate::de::Deserializer::from_nested(&bytes, &crate::DeOpts::new());
+        T::deserialize(&mut de).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Like [`nested_nbt`][`super`], but the inner document is written GZip
+/// compressed.
+///
+/// This module requires the `gzip` feature, which is enabled by default.
+///
+/// ```rust
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Outer {
+///     #[serde(with = "fastnbt::helpers::nested_nbt::gzip")]
+///     inner: fastnbt::Value,
+/// }
+/// ```
+#[cfg(feature = "gzip")]
+pub mod gzip {
+    use std::io::Write;
+
+    use flate2::{write::GzEncoder, Compression};
+    use serde::{de::DeserializeOwned, Deserializer, Serialize, Serializer};
+
+    use super::byte_array;
+
+    pub fn serialize<S, T>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: Serialize,
+    {
+        let bytes = crate::to_bytes(value).map_err(serde::ser::Error::custom)?;
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder
+            .write_all(&bytes)
+            .map_err(serde::ser::Error::custom)?;
+        let compressed = encoder.finish().map_err(serde::ser::Error::custom)?;
+        byte_array::serialize(&compressed, serializer)
+    }
+
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: DeserializeOwned,
+    {
+        super::deserialize(deserializer)
+    }
+}
@@ -0,0 +1,114 @@
+//! (De)serialize a `Vec<T>` that may be stored as either a list or a single
+//! value.
+//!
+//! Some data stores a field as a single value when there is one, and as a
+//! list when there are several. With this helper both forms deserialize into
+//! a `Vec<T>`, a single value becoming a one-element `Vec`. The field is
+//! always serialized as a list.
+//!
+//! ```rust
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Drops {
+//!     #[serde(with = "fastnbt::helpers::one_or_many")]
+//!     items: Vec<String>,
+//! }
+//!
+//! let one = fastnbt::to_bytes(&fastnbt::nbt!({"items": "stick"})).unwrap();
+//! let many = fastnbt::to_bytes(&fastnbt::nbt!({"items": ["stick", "stone"]})).unwrap();
+//!
+//! let one: Drops = fastnbt::from_bytes(&one).unwrap();
+//! let many: Drops = fastnbt::from_bytes(&many).unwrap();
+//! assert_eq!(one.items, ["stick"]);
+//! assert_eq!(many.items, ["stick", "stone"]);
+//! ```
+
+use std::marker::PhantomData;
+
+use serde::{
+    de::{
+        value::{BorrowedStrDeserializer, MapAccessDeserializer},
+        IntoDeserializer, Visitor,
+    },
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+pub fn serialize<S, T>(data: &[T], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Serialize,
+{
+    data.serialize(serializer)
+}
+
+pub fn deserialize<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    deserializer.deserialize_any(OneOrManyVisitor(PhantomData))
+}
+
+struct OneOrManyVisitor<T>(PhantomData<T>);
+
+macro_rules! visit_one {
+    ($v:ident, $t:ty) => {
+        fn $v<E>(self, v: $t) -> Result<Self::Value, E>
+        where
+            E: serde::de::Error,
+        {
+            T::deserialize(IntoDeserializer::<E>::into_deserializer(v)).map(|v| vec![v])
+        }
+    };
+}
+
+impl<'de, T> Visitor<'de> for OneOrManyVisitor<T>
+where
+    T: Deserialize<'de>,
+{
+    type Value = Vec<T>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a list or a single value")
+    }
+
+    visit_one!(visit_bool, bool);
+    visit_one!(visit_i8, i8);
+    visit_one!(visit_i16, i16);
+    visit_one!(visit_i32, i32);
+    visit_one!(visit_i64, i64);
+    visit_one!(visit_u8, u8);
+    visit_one!(visit_u16, u16);
+    visit_one!(visit_u32, u32);
+    visit_one!(visit_u64, u64);
+    visit_one!(visit_f32, f32);
+    visit_one!(visit_f64, f64);
+    visit_one!(visit_str, &str);
+    visit_one!(visit_string, String);
+
+    fn visit_borrowed_str<E>(self, v: &'de str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        T::deserialize(BorrowedStrDeserializer::new(v)).map(|v| vec![v])
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: serde::de::SeqAccess<'de>,
+    {
+        let mut out = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+        while let Some(v) = seq.next_element()? {
+            out.push(v);
+        }
+        Ok(out)
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+    where
+        A: serde::de::MapAccess<'de>,
+    {
+        T::deserialize(MapAccessDeserializer::new(map)).map(|v| vec![v])
+    }
+}
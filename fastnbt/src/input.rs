@@ -2,9 +2,12 @@ use std::{borrow::Cow, io::Read, ops::Range};
 
 use byteorder::{BigEndian, ReadBytesExt};
 
+#[cfg(feature = "checksum")]
+use crate::de::Checksum;
 use crate::{
+    de::Warning,
     error::{Error, Result},
-    Tag,
+    DeOpts, Tag,
 };
 
 mod private {
@@ -13,14 +16,51 @@ mod private {
     pub trait Sealed {}
 }
 
-fn try_size(size: i32, multiplier: usize) -> Result<usize> {
+/// How deeply compounds and lists can be nested when parsing, as in
+/// Minecraft. This keeps deeply nested input from overflowing the stack.
+pub(crate) const MAX_DEPTH: usize = 512;
+
+pub(crate) fn try_size(size: i32, multiplier: usize) -> Result<usize> {
     let size: usize = size
         .try_into()
-        .map_err(|_| Error::bespoke("size was negative".to_string()))?;
+        .map_err(|_| Error::bespoke("size was negative"))?;
 
     size.checked_mul(multiplier)
-        .ok_or_else(|| Error::bespoke("size too large".to_string()))
+        .ok_or_else(|| Error::bespoke("size too large"))
+}
+/// Turn the CESU-8 bytes of a `Reference<[u8]>` into a `Reference<str>`.
+/// `borrowed` is the bytes if they were borrowed from the input, otherwise
+/// they are in `scratch`, which then holds the decoded string.
+fn decode_str<'de, 's>(
+    borrowed: Option<&'de [u8]>,
+    scratch: &'s mut Vec<u8>,
+) -> Result<Reference<'de, 's, str>> {
+    let str = match borrowed {
+        Some(bs) => cesu8::from_java_cesu8(bs).map_err(|_| Error::nonunicode_string(bs))?,
+        None => {
+            match cesu8::from_java_cesu8(scratch).map_err(|_| Error::nonunicode_string(scratch))? {
+                // scratch already holds the string, which is valid UTF-8.
+                Cow::Borrowed(_) => {
+                    return Ok(Reference::Copied(unsafe {
+                        std::str::from_utf8_unchecked(scratch)
+                    }))
+                }
+                Cow::Owned(s) => Cow::Owned(s),
+            }
+        }
+    };
+
+    Ok(match str {
+        Cow::Borrowed(str) => Reference::Borrowed(str),
+        Cow::Owned(str) => {
+            *scratch = str.into_bytes();
+            // we just converted scratch into the bytes of a string, so it
+            // definitely utf8.
+            Reference::Copied(unsafe { std::str::from_utf8_unchecked(scratch) })
+        }
+    })
 }
+
 pub enum Reference<'b, 'c, T>
 where
     T: ?Sized + 'static,
@@ -29,6 +69,15 @@ where
     Copied(&'c T),
 }
 
+impl<'b, 'c> AsRef<str> for Reference<'b, 'c, str> {
+    fn as_ref(&self) -> &str {
+        match self {
+            Reference::Borrowed(s) => s,
+            Reference::Copied(s) => s,
+        }
+    }
+}
+
 impl<'b, 'c> AsRef<[u8]> for Reference<'b, 'c, [u8]> {
     fn as_ref(&self) -> &[u8] {
         match self {
@@ -70,7 +119,40 @@ pub trait Input<'de>: private::Sealed {
     fn consume_f32(&mut self) -> Result<f32>;
     fn consume_f64(&mut self) -> Result<f64>;
 
-    fn ignore_value(&mut self, tag: Tag) -> Result<()> {
+    /// The number of bytes consumed so far.
+    #[doc(hidden)]
+    fn position(&self) -> usize;
+
+    /// Go back to an earlier [`position`][`Input::position`], returning
+    /// whether this input supports it.
+    #[doc(hidden)]
+    fn rewind(&mut self, _position: usize) -> bool {
+        false
+    }
+
+    /// Consume the checksum that follows the data consumed so far, erroring if
+    /// it is missing or does not match.
+    #[doc(hidden)]
+    #[cfg(feature = "checksum")]
+    fn verify_checksum(&mut self, checksum: Checksum) -> Result<()>;
+
+    /// Whether [`warn`][`Input::warn`] records warnings, so that the
+    /// deserializer can skip looking for them otherwise.
+    #[doc(hidden)]
+    fn wants_warnings(&self) -> bool {
+        false
+    }
+
+    #[doc(hidden)]
+    fn warn(&mut self, _warning: Warning) {}
+
+    /// Skip the payload of a value with the given tag, which is nested within
+    /// `depth` compounds and lists.
+    fn ignore_value(&mut self, tag: Tag, depth: usize) -> Result<()> {
+        if matches!(tag, Tag::Compound | Tag::List) && depth >= MAX_DEPTH {
+            return Err(Error::nesting_too_deep());
+        }
+
         match tag {
             Tag::Byte => {
                 self.consume_byte()?;
@@ -94,8 +176,8 @@ pub trait Input<'de>: private::Sealed {
                 self.ignore_str()?;
             }
             Tag::ByteArray => {
-                let size = self.consume_i32()? as usize;
-                self.ignore_bytes(size)?;
+                let size = self.consume_i32()?;
+                self.ignore_bytes(try_size(size, std::mem::size_of::<i8>())?)?;
             }
             Tag::IntArray => {
                 let size = self.consume_i32()?;
@@ -117,14 +199,34 @@ pub trait Input<'de>: private::Sealed {
 
                     // consume the name.
                     self.ignore_str()?;
-                    self.ignore_value(tag)?;
+                    self.ignore_value(tag, depth + 1)?;
                 }
             }
             Tag::List => {
                 let element_tag = self.consume_tag()?;
                 let size = self.consume_i32()?;
-                for _ in 0..size {
-                    self.ignore_value(element_tag)?;
+                if size < 0 {
+                    return Err(Error::invalid_size(size));
+                }
+                if element_tag == Tag::End && size != 0 {
+                    return Err(Error::bespoke(
+                        "unexpected list of type 'end', which is not supported",
+                    ));
+                }
+                let width = match element_tag {
+                    Tag::Byte => 1,
+                    Tag::Short => 2,
+                    Tag::Int | Tag::Float => 4,
+                    Tag::Long | Tag::Double => 8,
+                    _ => 0,
+                };
+                if width > 0 {
+                    // Elements of a fixed size can be skipped all at once.
+                    self.ignore_bytes(try_size(size, width)?)?;
+                } else {
+                    for _ in 0..size {
+                        self.ignore_value(element_tag, depth + 1)?;
+                    }
                 }
             }
             Tag::End => {
@@ -143,12 +245,92 @@ pub trait Input<'de>: private::Sealed {
     }
 }
 
-pub(crate) struct Slice<'de> {
-    pub data: &'de [u8],
+/// Consume the payload of a value with the given tag, appending the bytes it
+/// was read from to `out`. This walks the value like
+/// [`ignore_value`][`Input::ignore_value`].
+pub(crate) fn copy_value<'de, I: Input<'de>>(
+    input: &mut I,
+    tag: Tag,
+    depth: usize,
+    out: &mut Vec<u8>,
+    scratch: &mut Vec<u8>,
+) -> Result<()> {
+    if matches!(tag, Tag::Compound | Tag::List) && depth >= MAX_DEPTH {
+        return Err(Error::nesting_too_deep());
+    }
+
+    match tag {
+        Tag::Byte => out.push(input.consume_byte()?),
+        Tag::Short => out.extend(input.consume_i16()?.to_be_bytes()),
+        Tag::Int => out.extend(input.consume_i32()?.to_be_bytes()),
+        Tag::Long => out.extend(input.consume_i64()?.to_be_bytes()),
+        Tag::Float => out.extend(input.consume_f32()?.to_be_bytes()),
+        Tag::Double => out.extend(input.consume_f64()?.to_be_bytes()),
+        Tag::String => {
+            let len = input.consume_i16()?;
+            out.extend(len.to_be_bytes());
+            out.extend_from_slice(input.consume_bytes(len as u16 as usize, scratch)?.as_ref());
+        }
+        Tag::ByteArray | Tag::IntArray | Tag::LongArray => {
+            let width = match tag {
+                Tag::ByteArray => 1,
+                Tag::IntArray => 4,
+                _ => 8,
+            };
+            let size = input.consume_i32()?;
+            out.extend(size.to_be_bytes());
+            let bytes = input.consume_bytes(try_size(size, width)?, scratch)?;
+            out.extend_from_slice(bytes.as_ref());
+        }
+        Tag::Compound => loop {
+            let tag = input.consume_tag()?;
+            out.push(tag as u8);
+            if tag == Tag::End {
+                break;
+            }
+            copy_value(input, Tag::String, depth, out, scratch)?;
+            copy_value(input, tag, depth + 1, out, scratch)?;
+        },
+        Tag::List => {
+            let element_tag = input.consume_tag()?;
+            let size = input.consume_i32()?;
+            if size < 0 {
+                return Err(Error::invalid_size(size));
+            }
+            if element_tag == Tag::End && size != 0 {
+                return Err(Error::bespoke(
+                    "unexpected list of type 'end', which is not supported",
+                ));
+            }
+            out.push(element_tag as u8);
+            out.extend(size.to_be_bytes());
+            for _ in 0..size {
+                copy_value(input, element_tag, depth + 1, out, scratch)?;
+            }
+        }
+        Tag::End => return Err(Error::bespoke("expected value, found end tag")),
+    }
+
+    Ok(())
+}
+
+pub struct Slice<'de> {
+    pub(crate) data: &'de [u8],
+    /// The whole input, so that positions can be worked out.
+    whole: &'de [u8],
 }
 
 impl<'de> private::Sealed for Slice<'de> {}
 impl<'de> Slice<'de> {
+    pub(crate) fn new(data: &'de [u8]) -> Self {
+        Self { data, whole: data }
+    }
+
+    /// The position in the input of the next byte to be consumed.
+    pub(crate) fn position(&self) -> usize {
+        self.whole.len() - self.data.len()
+    }
+
     fn consume(&mut self, r: Range<usize>) -> Result<&'de [u8]> {
         if r.end <= self.data.len() {
             let ret = &self.data[r.start..r.end];
@@ -173,17 +355,7 @@ impl<'de> Input<'de> for Slice<'de> {
     fn consume_str<'s>(&'s mut self, scratch: &'s mut Vec<u8>) -> Result<Reference<'de, 's, str>> {
         let len = self.consume(0..2)?.read_u16::<BigEndian>()? as usize;
         let str = self.consume(0..len)?;
-        let str = cesu8::from_java_cesu8(str).map_err(|_| Error::nonunicode_string(str))?;
-
-        Ok(match str {
-            Cow::Borrowed(str) => Reference::Borrowed(str),
-            Cow::Owned(str) => {
-                *scratch = str.into_bytes();
-                // we just converted scratch into the bytes of a string, so it
-                // definitely utf8.
-                Reference::Copied(unsafe { std::str::from_utf8_unchecked(scratch) })
-            }
-        })
+        decode_str(Some(str), scratch)
     }
 
     fn consume_bytes<'s>(
@@ -224,10 +396,96 @@ impl<'de> Input<'de> for Slice<'de> {
         self.consume(0..size)?;
         Ok(())
     }
+
+    fn position(&self) -> usize {
+        Slice::position(self)
+    }
+
+    #[cfg(feature = "checksum")]
+    fn verify_checksum(&mut self, checksum: Checksum) -> Result<()> {
+        let mut hasher = checksum.hasher();
+        hasher.update(&self.whole[..self.position()]);
+        checksum.verify(hasher, self.data)?;
+        self.data = &self.data[checksum.size()..];
+        Ok(())
+    }
+
+    fn rewind(&mut self, position: usize) -> bool {
+        match self.whole.get(position..) {
+            Some(data) => {
+                self.data = data;
+                true
+            }
+            None => false,
+        }
+    }
 }
 
-pub(crate) struct Reader<R: Read> {
-    pub reader: R,
+pub struct Reader<R: Read> {
+    pub(crate) reader: Limit<R>,
+}
+
+impl<R: Read> Reader<R> {
+    /// Create an input that errors once more than
+    /// [`max_input_bytes`][`DeOpts::max_input_bytes`] have been read, and
+    /// that hashes what it reads if a
+    /// [`trailing_checksum`][`DeOpts::trailing_checksum`] is expected.
+    pub(crate) fn new(reader: R, opts: &DeOpts) -> Self {
+        Self {
+            reader: Limit {
+                inner: reader,
+                remaining: opts.max_input_bytes.map(|l| l as u64),
+                limit: opts.max_input_bytes,
+                consumed: 0,
+                #[cfg(feature = "checksum")]
+                hasher: opts.trailing_checksum.map(Checksum::hasher),
+            },
+        }
+    }
+}
+
+/// Reader that errors, rather than ending, once a limit is reached. This stops
+/// an untrusted stream from being read forever.
+pub(crate) struct Limit<R> {
+    pub(crate) inner: R,
+    remaining: Option<u64>,
+    limit: Option<usize>,
+    /// Bytes read so far.
+    consumed: usize,
+    /// Hash of the bytes read so far, when checking a trailing checksum.
+    #[cfg(feature = "checksum")]
+    hasher: Option<crc32fast::Hasher>,
+}
+
+impl<R: Read> Read for Limit<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = match self.remaining {
+            None => self.inner.read(buf)?,
+            Some(_) if buf.is_empty() => return Ok(0),
+            Some(0) => {
+                let err = Error::bespoke(format!(
+                    "input exceeded the maximum size of {} bytes",
+                    self.limit.unwrap_or_default()
+                ));
+                return Err(std::io::Error::other(err));
+            }
+            Some(remaining) => {
+                let len = buf
+                    .len()
+                    .min(usize::try_from(remaining).unwrap_or(usize::MAX));
+                let n = self.inner.read(&mut buf[..len])?;
+                self.remaining = Some(remaining - n as u64);
+                n
+            }
+        };
+
+        self.consumed += n;
+        #[cfg(feature = "checksum")]
+        if let Some(hasher) = &mut self.hasher {
+            hasher.update(&buf[..n]);
+        }
+        Ok(n)
+    }
 }
 
 impl<R: Read> private::Sealed for Reader<R> {}
@@ -239,8 +497,7 @@ impl<'de, R: Read> Input<'de> for Reader<R> {
 
     fn ignore_str(&mut self) -> Result<()> {
         let len = self.reader.read_u16::<BigEndian>()? as usize;
-        let mut buf = vec![0; len]; // TODO: try a scratch space to reduce allocs?
-        Ok(self.reader.read_exact(&mut buf)?)
+        self.ignore_bytes(len)
     }
 
     fn consume_str<'s>(&'s mut self, scratch: &'s mut Vec<u8>) -> Result<Reference<'de, 's, str>> {
@@ -248,18 +505,7 @@ impl<'de, R: Read> Input<'de> for Reader<R> {
         scratch.clear();
         scratch.resize(len, 0);
         self.reader.read_exact(scratch)?;
-
-        let str = cesu8::from_java_cesu8(scratch).map_err(|_| Error::nonunicode_string(scratch))?;
-
-        Ok(match str {
-            Cow::Borrowed(_) => {
-                Reference::Copied(unsafe { std::str::from_utf8_unchecked(scratch) })
-            }
-            Cow::Owned(s) => {
-                *scratch = s.into_bytes();
-                Reference::Copied(unsafe { std::str::from_utf8_unchecked(scratch) })
-            }
-        })
+        decode_str(None, scratch)
     }
 
     fn consume_bytes<'s>(
@@ -267,9 +513,12 @@ impl<'de, R: Read> Input<'de> for Reader<R> {
         n: usize,
         scratch: &'s mut Vec<u8>,
     ) -> Result<Reference<'de, 's, [u8]>> {
+        // Read through `take` so a corrupt length cannot force a huge allocation.
         scratch.clear();
-        scratch.resize(n, 0);
-        self.reader.read_exact(scratch.as_mut_slice())?;
+        (&mut self.reader).take(n as u64).read_to_end(scratch)?;
+        if scratch.len() != n {
+            return Err(Error::unexpected_eof());
+        }
 
         Ok(Reference::Copied(scratch.as_slice()))
     }
@@ -295,8 +544,213 @@ impl<'de, R: Read> Input<'de> for Reader<R> {
     }
 
     fn ignore_bytes(&mut self, size: usize) -> Result<()> {
-        let mut buf = vec![0; size]; // TODO: try a scratch space to reduce allocs?
-        self.reader.read_exact(&mut buf)?;
+        // Copying to a sink uses a stack buffer, so ignoring doesn't allocate.
+        let size = size as u64;
+        let copied = std::io::copy(&mut (&mut self.reader).take(size), &mut std::io::sink())?;
+        if copied == size {
+            Ok(())
+        } else {
+            Err(Error::unexpected_eof())
+        }
+    }
+
+    fn position(&self) -> usize {
+        self.reader.consumed
+    }
+
+    #[cfg(feature = "checksum")]
+    fn verify_checksum(&mut self, checksum: Checksum) -> Result<()> {
+        let hasher = self
+            .reader
+            .hasher
+            .take()
+            .ok_or_else(|| Error::bespoke("input was not read with a checksum"))?;
+
+        let mut trailer = Vec::with_capacity(checksum.size());
+        (&mut self.reader)
+            .take(checksum.size() as u64)
+            .read_to_end(&mut trailer)?;
+        checksum.verify(hasher, &trailer)
+    }
+}
+
+/// Input that passes each chunk of bytes consumed from `input` to `observer`.
+/// Values are reported as the raw bytes they were read from.
+pub struct Observed<In, F> {
+    pub(crate) input: In,
+    pub(crate) observer: F,
+    pub(crate) scratch: Vec<u8>,
+}
+
+impl<In, F> private::Sealed for Observed<In, F> {}
+
+impl<'de, In, F> Input<'de> for Observed<In, F>
+where
+    In: Input<'de>,
+    F: FnMut(&[u8]),
+{
+    fn consume_byte(&mut self) -> Result<u8> {
+        let b = self.input.consume_byte()?;
+        (self.observer)(&[b]);
+        Ok(b)
+    }
+
+    fn ignore_str(&mut self) -> Result<()> {
+        let len = self.consume_i16()? as u16 as usize;
+        self.ignore_bytes(len)
+    }
+
+    fn consume_str<'s>(&'s mut self, scratch: &'s mut Vec<u8>) -> Result<Reference<'de, 's, str>> {
+        let len = self.consume_i16()? as u16 as usize;
+        let raw = self.input.consume_bytes(len, scratch)?;
+        (self.observer)(raw.as_ref());
+
+        let borrowed = match raw {
+            Reference::Borrowed(bs) => Some(bs),
+            Reference::Copied(_) => None,
+        };
+        decode_str(borrowed, scratch)
+    }
+
+    fn consume_bytes<'s>(
+        &'s mut self,
+        n: usize,
+        scratch: &'s mut Vec<u8>,
+    ) -> Result<Reference<'de, 's, [u8]>> {
+        let bs = self.input.consume_bytes(n, scratch)?;
+        (self.observer)(bs.as_ref());
+        Ok(bs)
+    }
+
+    fn consume_i16(&mut self) -> Result<i16> {
+        let v = self.input.consume_i16()?;
+        (self.observer)(&v.to_be_bytes());
+        Ok(v)
+    }
+
+    fn consume_i32(&mut self) -> Result<i32> {
+        let v = self.input.consume_i32()?;
+        (self.observer)(&v.to_be_bytes());
+        Ok(v)
+    }
+
+    fn consume_i64(&mut self) -> Result<i64> {
+        let v = self.input.consume_i64()?;
+        (self.observer)(&v.to_be_bytes());
+        Ok(v)
+    }
+
+    fn consume_f32(&mut self) -> Result<f32> {
+        let v = self.input.consume_f32()?;
+        (self.observer)(&v.to_be_bytes());
+        Ok(v)
+    }
+
+    fn consume_f64(&mut self) -> Result<f64> {
+        let v = self.input.consume_f64()?;
+        (self.observer)(&v.to_be_bytes());
+        Ok(v)
+    }
+
+    fn ignore_bytes(&mut self, size: usize) -> Result<()> {
+        let bs = self.input.consume_bytes(size, &mut self.scratch)?;
+        (self.observer)(bs.as_ref());
         Ok(())
     }
+
+    fn position(&self) -> usize {
+        self.input.position()
+    }
+
+    #[cfg(feature = "checksum")]
+    fn verify_checksum(&mut self, checksum: Checksum) -> Result<()> {
+        self.input.verify_checksum(checksum)
+    }
+
+    fn wants_warnings(&self) -> bool {
+        self.input.wants_warnings()
+    }
+
+    fn warn(&mut self, warning: Warning) {
+        self.input.warn(warning)
+    }
+}
+
+/// Input that records the warnings the deserializer finds in `input` in
+/// `sink`.
+pub struct Warned<'w, In> {
+    pub(crate) input: In,
+    pub(crate) sink: &'w mut Vec<Warning>,
+}
+
+impl<In> private::Sealed for Warned<'_, In> {}
+
+impl<'de, In> Input<'de> for Warned<'_, In>
+where
+    In: Input<'de>,
+{
+    fn consume_byte(&mut self) -> Result<u8> {
+        self.input.consume_byte()
+    }
+
+    fn ignore_str(&mut self) -> Result<()> {
+        self.input.ignore_str()
+    }
+
+    fn ignore_bytes(&mut self, size: usize) -> Result<()> {
+        self.input.ignore_bytes(size)
+    }
+
+    fn consume_str<'s>(&'s mut self, scratch: &'s mut Vec<u8>) -> Result<Reference<'de, 's, str>> {
+        self.input.consume_str(scratch)
+    }
+
+    fn consume_bytes<'s>(
+        &'s mut self,
+        n: usize,
+        scratch: &'s mut Vec<u8>,
+    ) -> Result<Reference<'de, 's, [u8]>> {
+        self.input.consume_bytes(n, scratch)
+    }
+
+    fn consume_i16(&mut self) -> Result<i16> {
+        self.input.consume_i16()
+    }
+
+    fn consume_i32(&mut self) -> Result<i32> {
+        self.input.consume_i32()
+    }
+
+    fn consume_i64(&mut self) -> Result<i64> {
+        self.input.consume_i64()
+    }
+
+    fn consume_f32(&mut self) -> Result<f32> {
+        self.input.consume_f32()
+    }
+
+    fn consume_f64(&mut self) -> Result<f64> {
+        self.input.consume_f64()
+    }
+
+    fn ignore_value(&mut self, tag: Tag, depth: usize) -> Result<()> {
+        self.input.ignore_value(tag, depth)
+    }
+
+    fn position(&self) -> usize {
+        self.input.position()
+    }
+
+    #[cfg(feature = "checksum")]
+    fn verify_checksum(&mut self, checksum: Checksum) -> Result<()> {
+        self.input.verify_checksum(checksum)
+    }
+
+    fn wants_warnings(&self) -> bool {
+        true
+    }
+
+    fn warn(&mut self, warning: Warning) {
+        self.sink.push(warning);
+    }
 }
@@ -8,6 +8,13 @@
 //! * To easily create values, see the [`nbt`] macro.
 //! * For NBT array types see [`ByteArray`], [`IntArray`], and [`LongArray`].
 //! * For zero-copy NBT array types see [`borrow`].
+//! * For lists that keep their element tag when empty see [`NbtList`].
+//! * For serde `with` helpers for types with no direct NBT equivalent, see
+//!   [`helpers`].
+//! * For GZip compressed NBT, such as player data, see the `gzip` module,
+//!   which requires the `gzip` feature.
+//! * To work out the structure of unknown NBT data, see [`infer_schema`].
+//! * For reading and writing SNBT, the text form of NBT, see [`snbt`].
 //!
 //! Both this and related crates are under one [fastnbt Github
 //! repository](https://github.com/owengage/fastnbt).
@@ -54,6 +61,57 @@
 //! # }
 //! ```
 //!
+//! # Enums
+//!
+//! Enums are (de)serialized the same way as serde's default "externally
+//! tagged" representation, used by `serde_json` among others:
+//!
+//! * Unit variants are a `String` of the variant name.
+//! * Newtype variants are a compound with the variant name as its only key,
+//!   and the variant's value as the value.
+//! * Tuple variants are a compound with the variant name as its only key, and
+//!   a list of the fields as the value. As with any list, the fields must all
+//!   have the same NBT type. The exception is [`to_bytes`] and the other
+//!   serializers writing NBT directly, which write only the list, as they
+//!   always have. As the variant name is lost, that list cannot be read back
+//!   as the enum.
+//! * Struct variants are a compound with the variant name as its only key, and
+//!   a compound of the fields as the value.
+//!
+//! ```
+//! use fastnbt::nbt;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize, Debug, PartialEq)]
+//! enum Shape {
+//!     Empty,
+//!     Circle(f32),
+//!     Point(i32, i32),
+//!     Rect { w: i32, h: i32 },
+//! }
+//!
+//! let shapes = vec![
+//!     Shape::Empty,
+//!     Shape::Circle(1.5),
+//!     Shape::Point(1, 2),
+//!     Shape::Rect { w: 3, h: 4 },
+//! ];
+//!
+//! assert_eq!(
+//!     fastnbt::to_value(&shapes)?,
+//!     nbt!([
+//!         "Empty",
+//!         {"Circle": 1.5_f32},
+//!         {"Point": [1, 2]},
+//!         {"Rect": {"w": 3, "h": 4}},
+//!     ])
+//! );
+//! # Ok::<(), fastnbt::error::Error>(())
+//! ```
+//!
+//! Serde's other enum representations, chosen with attributes such as
+//! `#[serde(tag = "type")]`, work as they do with other formats.
+//!
 //! # Example: Player inventory
 //!
 //! This example demonstrates printing out a players inventory and ender chest
@@ -125,18 +183,32 @@ use ser::Serializer;
 use serde::{de as serde_de, Deserialize, Serialize};
 
 pub mod borrow;
+pub mod columnar;
 pub mod de;
 pub mod error;
+#[cfg(feature = "gzip")]
+pub mod gzip;
+pub mod helpers;
+pub mod schema;
 pub mod ser;
+pub mod snbt;
 pub mod stream;
+pub mod validate;
 pub mod value;
 
 mod arrays;
 mod input;
+mod list;
+mod raw;
 #[macro_use]
 mod macros;
 
 pub use arrays::*;
+pub use list::{ListElement, NbtList};
+pub use raw::Raw;
+pub use schema::{infer_schema, rust_type_for_tag};
+pub use snbt::{from_snbt, to_snbt_writer};
+pub use validate::validate_strict;
 pub use value::{from_value, to_value, Value};
 
 #[cfg(test)]
@@ -150,6 +222,7 @@ use std::{
     convert::TryFrom,
     fmt::Display,
     io::{Read, Write},
+    sync::Arc,
 };
 
 /// An NBT tag. This does not carry the value or the name of the data.
@@ -255,23 +328,84 @@ impl Display for Tag {
 
 /// Serialize some `T` into NBT data. See the [`ser`] module for more
 /// information.
-pub fn to_bytes<T: Serialize>(v: &T) -> Result<Vec<u8>> {
+pub fn to_bytes<T: Serialize + ?Sized>(v: &T) -> Result<Vec<u8>> {
     let mut result = vec![];
-    let mut serializer = Serializer {
-        writer: &mut result,
-    };
+    let mut serializer = Serializer::new(&mut result);
     v.serialize(&mut serializer)?;
     Ok(result)
 }
 
+/// Serialize some `T` into NBT data, in a buffer with room for `capacity`
+/// bytes reserved up front. See the [`ser`] module for more information.
+///
+/// [`to_bytes`] grows its buffer as it goes, copying the data written so far
+/// each time it runs out of room. Reserving the buffer first avoids this, which
+/// is noticeably faster for a large value whose size is already known, such as
+/// when writing many values of the same size. [`Value::serialized_size`] gives
+/// the exact size of a [`Value`], but it walks the whole value to work it out,
+/// which usually costs more than growing the buffer does.
+///
+/// ```
+/// # use fastnbt::nbt;
+/// let v = nbt!({"Biomes": [I; 1, 2, 3], "Status": "full"});
+/// let bytes = fastnbt::to_bytes_with_capacity(&v, v.serialized_size())?;
+/// assert_eq!(bytes, fastnbt::to_bytes(&v)?);
+/// # Ok::<(), fastnbt::error::Error>(())
+/// ```
+pub fn to_bytes_with_capacity<T: Serialize + ?Sized>(v: &T, capacity: usize) -> Result<Vec<u8>> {
+    let mut result = Vec::with_capacity(capacity);
+    to_writer(&mut result, v)?;
+    Ok(result)
+}
+
 /// Serialize some `T` into NBT data. See the [`ser`] module for more
 /// information.
-pub fn to_writer<T: Serialize, W: Write>(writer: W, v: &T) -> Result<()> {
-    let mut serializer = Serializer { writer };
+pub fn to_writer<T: Serialize + ?Sized, W: Write>(writer: W, v: &T) -> Result<()> {
+    let mut serializer = Serializer::new(writer);
     v.serialize(&mut serializer)?;
     Ok(())
 }
 
+/// Serialize some `T` into NBT data, prefixed with the length of that data as
+/// a big-endian `u32`. This is a common framing for NBT sent over a network.
+/// Read it back with [`from_length_prefixed_reader`].
+pub fn to_length_prefixed_bytes<T: Serialize + ?Sized>(v: &T) -> Result<Vec<u8>> {
+    let mut result = vec![0; 4];
+    to_writer(&mut result, v)?;
+
+    let len = u32::try_from(result.len() - 4)
+        .map_err(|_| Error::bespoke("NBT data too large for length prefix"))?;
+    result[..4].copy_from_slice(&len.to_be_bytes());
+    Ok(result)
+}
+
+/// Deserialize a `T` from a frame of NBT data prefixed with its length as a
+/// big-endian `u32`, as written by [`to_length_prefixed_bytes`]. Exactly one
+/// frame is read from `reader`, so this can be called repeatedly to read a
+/// series of frames. It is an error if the NBT data does not fill the frame.
+pub fn from_length_prefixed_reader<R, T>(mut reader: R) -> Result<T>
+where
+    R: Read,
+    T: serde_de::DeserializeOwned,
+{
+    let mut len = [0; 4];
+    reader.read_exact(&mut len)?;
+    let len = u32::from_be_bytes(len) as u64;
+
+    let mut frame = Vec::new();
+    reader.take(len).read_to_end(&mut frame)?;
+    if frame.len() as u64 != len {
+        return Err(Error::unexpected_eof());
+    }
+
+    let mut des = Deserializer::from_bytes(&frame, Default::default());
+    let t = T::deserialize(&mut des)?;
+    if !des.remaining().is_empty() {
+        return Err(Error::bespoke("trailing data in length prefixed frame"));
+    }
+    Ok(t)
+}
+
 /// Deserialize into a `T` from some NBT data. See the [`de`] module for more
 /// information.
 ///
@@ -301,6 +435,24 @@ where
 /// Deserialize into a `T` from some NBT data. See the [`de`] module for more
 /// information.
 ///
+/// The reader is not buffered: each tag, length and number is a separate
+/// small `read` call. For a [`File`][`std::fs::File`], a socket, or anything
+/// else where a read is a system call, wrap it in a
+/// [`BufReader`][`std::io::BufReader`] first. Decoders such as `GzDecoder`
+/// already read their input in blocks. The deserializer does not buffer
+/// internally because it would then read past the end of the NBT data, taking
+/// bytes from the reader that belong to whatever follows it.
+///
+/// ```no_run
+/// # use fastnbt::Value;
+/// # use std::{fs::File, io::BufReader};
+/// # fn main() -> fastnbt::error::Result<()> {
+/// let file = File::open("level.dat.nbt")?;
+/// let val: Value = fastnbt::from_reader(BufReader::new(file))?;
+/// # Ok(())
+/// # }
+/// ```
+///
 /// ```no_run
 /// # use fastnbt::Value;
 /// # use flate2::read::GzDecoder;
@@ -323,10 +475,63 @@ where
     serde_de::Deserialize::deserialize(&mut deserializer)
 }
 
+/// Entry point for fuzzing, for example with cargo-fuzz. This parses `data`
+/// in every way the crate supports and re-serializes anything that parses.
+///
+/// Parsing arbitrary bytes must never panic: invalid input is always reported
+/// as an error. This function panics only if that promise is broken, or if
+/// the different ways of parsing disagree, so that a fuzzer can find the
+/// input.
+#[cfg(any(feature = "fuzz", test))]
+pub fn fuzz_parse(data: &[u8]) {
+    let opts = DeOpts::new().max_seq_len(1000);
+
+    let from_slice: Result<Value> = from_bytes_with_opts(data, opts.clone());
+    let from_reader: Result<Value> = {
+        let mut des = Deserializer::from_reader(data, opts);
+        Value::deserialize(&mut des)
+    };
+    assert_eq!(
+        from_slice.is_ok(),
+        from_reader.is_ok(),
+        "slice and reader disagree: {:?} vs {:?}",
+        from_slice,
+        from_reader
+    );
+
+    let _ = infer_schema(data);
+    let _ = from_bytes_lenient::<Value>(data);
+    let mut parser = stream::Parser::new(data);
+    while parser.next().is_ok() {}
+
+    if let Ok(value) = from_slice {
+        let bytes = to_bytes(&value).expect("parsed value must serialize");
+        let reparsed: Value = from_bytes(&bytes).expect("serialized value must parse");
+        let _ = snbt::to_snbt_string(&reparsed);
+    }
+}
+
 /// Options for customizing deserialization.
+#[derive(Clone)]
 pub struct DeOpts {
     /// Maximum number of bytes a list or array can be.
     max_seq_len: usize,
+
+    /// Handler for tag IDs outside of the standard NBT range.
+    tag_handler: Option<Arc<dyn de::TagHandler>>,
+
+    /// Decoder for compound keys that are not Modified UTF-8.
+    key_decoder: Option<Arc<dyn de::KeyDecoder>>,
+
+    /// Checksum expected to follow the NBT data.
+    #[cfg(feature = "checksum")]
+    trailing_checksum: Option<de::Checksum>,
+
+    /// Maximum number of bytes of strings and arrays in total.
+    max_total_bytes: Option<usize>,
+
+    /// Maximum number of bytes to read from a reader.
+    max_input_bytes: Option<usize>,
 }
 
 impl DeOpts {
@@ -342,12 +547,89 @@ impl DeOpts {
         self.max_seq_len = value;
         self
     }
+
+    /// Set a handler for custom tag IDs, ie ones that are not part of the NBT
+    /// specification. Without a handler any unknown tag is an error. See
+    /// [`TagHandler`][`de::TagHandler`] for more information.
+    pub fn tag_handler(mut self, handler: impl de::TagHandler + 'static) -> Self {
+        self.tag_handler = Some(Arc::new(handler));
+        self
+    }
+
+    /// Set a decoder for the names of compound keys, for data written by tools
+    /// that encode them in something other than Modified UTF-8. Without a
+    /// decoder keys that are not Modified UTF-8 are an error. String values
+    /// are not affected. See [`KeyDecoder`][`de::KeyDecoder`] for more
+    /// information.
+    pub fn key_decoder(mut self, decoder: impl de::KeyDecoder + 'static) -> Self {
+        self.key_decoder = Some(Arc::new(decoder));
+        self
+    }
+
+    /// Expect a checksum of the given kind directly after the root compound,
+    /// and error if it is missing or does not match the data. Any data after
+    /// the checksum is ignored. By default no checksum is expected.
+    ///
+    /// The checksum is checked once the root value has been deserialized,
+    /// whether from a slice or a reader. Requires the `checksum` feature.
+    #[cfg(feature = "checksum")]
+    pub fn trailing_checksum(mut self, kind: de::Checksum) -> Self {
+        self.trailing_checksum = Some(kind);
+        self
+    }
+
+    /// Set the maximum number of bytes that deserialized data can take up in
+    /// total. All strings, including compound keys, and NBT arrays count as
+    /// their size in bytes, and every list element and compound entry counts
+    /// as a further 16 bytes. Deserialization errors once the limit is
+    /// exceeded. Unlike [`max_seq_len`][`DeOpts::max_seq_len`] this limits
+    /// data made of many small values as well as a few large ones, so memory
+    /// use grows with the limit however the data is structured. This is
+    /// useful for untrusted input. By default there is no limit.
+    ///
+    /// Values that are ignored, such as unknown fields, are not counted beyond
+    /// their keys.
+    pub fn max_total_bytes(mut self, value: usize) -> Self {
+        self.max_total_bytes = Some(value);
+        self
+    }
+
+    /// Set the maximum number of bytes to read when deserializing from a
+    /// reader, such as with [`Deserializer::from_reader`]. Deserialization
+    /// errors if it needs to read more than this, whatever sizes the data
+    /// claims, so an untrusted stream cannot keep it reading forever. By
+    /// default there is no limit.
+    ///
+    /// This has no effect when deserializing from a slice, which is already
+    /// bounded.
+    ///
+    /// ```
+    /// # use fastnbt::{de::Deserializer, DeOpts, Value};
+    /// # use serde::Deserialize;
+    /// // A compound whose first key claims to be 0x0a0a bytes long, and so on
+    /// // forever.
+    /// let endless = std::io::repeat(10);
+    ///
+    /// let mut de = Deserializer::from_reader(endless, DeOpts::new().max_input_bytes(1024));
+    /// let err = Value::deserialize(&mut de).unwrap_err();
+    /// assert_eq!(err.to_string(), "input exceeded the maximum size of 1024 bytes");
+    /// ```
+    pub fn max_input_bytes(mut self, value: usize) -> Self {
+        self.max_input_bytes = Some(value);
+        self
+    }
 }
 
 impl Default for DeOpts {
     fn default() -> Self {
         Self {
             max_seq_len: 10_000_000, // arbitrary high limit.
+            tag_handler: None,
+            key_decoder: None,
+            #[cfg(feature = "checksum")]
+            trailing_checksum: None,
+            max_total_bytes: None,
+            max_input_bytes: None,
         }
     }
 }
@@ -357,17 +639,125 @@ pub fn from_bytes_with_opts<'a, T>(input: &'a [u8], opts: DeOpts) -> Result<T>
 where
     T: serde_de::Deserialize<'a>,
 {
+    let mut des = Deserializer::from_bytes(input, opts);
+    deserialize_slice(input, &mut des)
+}
+
+/// Deserialize from a deserializer over `input`, applying the checks done by
+/// [`from_bytes_with_opts`].
+fn deserialize_slice<'a, T>(input: &'a [u8], des: &mut Deserializer<input::Slice<'a>>) -> Result<T>
+where
+    T: serde_de::Deserialize<'a>,
+{
+    check_not_gzip(input)?;
+
+    T::deserialize(des)
+}
+
+/// Deserialize a `T` from some NBT data, skipping any compound fields or list
+/// elements that fail to deserialize rather than failing entirely. This is
+/// useful for recovering what data is possible from corrupt or unexpected
+/// files.
+///
+/// When a value fails to deserialize, it is skipped in place using its tag and
+/// deserialization carries on, so the input is only read once. A skipped list
+/// element or map entry is left out, and a skipped struct field is treated as
+/// absent. If the field cannot be absent, for example because it is not an
+/// `Option`, its parent is skipped in turn. This returns an error if the root
+/// compound itself cannot be deserialized.
+///
+/// Alongside the value, this returns the path and error of every skipped
+/// value. Input that is invalid NBT, rather than NBT that doesn't fit `T`,
+/// usually cannot be skipped past.
+///
+/// ```
+/// use fastnbt::nbt;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Player {
+///     name: String,
+///     level: Option<i32>,
+/// }
+///
+/// let data = fastnbt::to_bytes(&nbt!({"name": "Steve", "level": "oops"}))?;
+/// let (player, skipped) = fastnbt::from_bytes_lenient::<Player>(&data)?;
+///
+/// assert_eq!(player.name, "Steve");
+/// assert_eq!(player.level, None);
+/// assert_eq!(skipped[0].path, "/level");
+/// # Ok::<(), fastnbt::error::Error>(())
+/// ```
+pub fn from_bytes_lenient<'a, T>(input: &'a [u8]) -> Result<(T, Vec<de::SkippedField>)>
+where
+    T: serde_de::Deserialize<'a>,
+{
+    check_not_gzip(input)?;
+
+    let mut des = Deserializer::from_bytes(input, Default::default());
+    des.set_lenient();
+    let t = T::deserialize(&mut des)?;
+    Ok((t, des.take_skipped()))
+}
+
+/// Deserialize a single entry of the root compound of some NBT data, without
+/// deserializing the rest. The entries before it are skipped over, and those
+/// after it are not read at all, so this is much faster than deserializing a
+/// whole struct or [`Value`] to read something like the `DataVersion` of a
+/// chunk. Returns `None` if there is no entry called `name`.
+///
+/// ```
+/// # use fastnbt::nbt;
+/// let data = fastnbt::to_bytes(&nbt!({"Level": {"xPos": 1}, "DataVersion": 3120}))?;
+///
+/// assert_eq!(fastnbt::extract_field::<i32>(&data, "DataVersion")?, Some(3120));
+/// assert_eq!(fastnbt::extract_field::<i32>(&data, "Missing")?, None);
+/// # Ok::<(), fastnbt::error::Error>(())
+/// ```
+pub fn extract_field<'a, T>(input: &'a [u8], name: &str) -> Result<Option<T>>
+where
+    T: serde_de::Deserialize<'a>,
+{
+    check_not_gzip(input)?;
+
+    let mut des = Deserializer::from_bytes(input, DeOpts::new());
+    des.enter_compound()?;
+    while let Some(key) = des.next_name()? {
+        if key == name {
+            return des.next_value().map(Some);
+        }
+        des.next_value_span()?;
+    }
+    Ok(None)
+}
+
+/// Deserialize some NBT data into an existing `T`. This allows `T` to reuse
+/// its existing allocations, for example when deserializing many values into
+/// the same pooled object.
+///
+/// This uses serde's `Deserialize::deserialize_in_place`, so `T` only reuses
+/// allocations if it implements that method. The default implementation
+/// simply replaces `place` with a newly deserialized value.
+pub fn from_bytes_in_place<'a, T>(input: &'a [u8], place: &mut T) -> Result<()>
+where
+    T: serde_de::Deserialize<'a>,
+{
+    check_not_gzip(input)?;
+
+    let mut des = Deserializer::from_bytes(input, Default::default());
+    T::deserialize_in_place(&mut des, place)
+}
+
+fn check_not_gzip(input: &[u8]) -> Result<()> {
     const GZIP_MAGIC_BYTES: [u8; 2] = [0x1f, 0x8b];
 
     // Provide freindly error for the common case of passing GZip data to
     // `from_bytes`. This would be invalid starting data for NBT anyway.
     if input.starts_with(&GZIP_MAGIC_BYTES) {
         return Err(Error::bespoke(
-            "from_bytes expects raw NBT, but input appears to be gzipped".to_string(),
+            "from_bytes expects raw NBT, but input appears to be gzipped",
         ));
     }
 
-    let mut des = Deserializer::from_bytes(input, opts);
-    let t = T::deserialize(&mut des)?;
-    Ok(t)
+    Ok(())
 }
@@ -0,0 +1,203 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    iter::FromIterator,
+    ops::{Deref, DerefMut},
+};
+
+use serde::{ser::SerializeTupleStruct, Deserialize, Serialize};
+
+use crate::{ByteArray, IntArray, LongArray, Tag};
+
+/// Names used to pass the element tag of an [`NbtList`] to the serializer,
+/// indexed by tag ID. Other serializers see an ordinary tuple struct.
+const LIST_TOKENS: [&str; 13] = [
+    "__fastnbt_list_0",
+    "__fastnbt_list_1",
+    "__fastnbt_list_2",
+    "__fastnbt_list_3",
+    "__fastnbt_list_4",
+    "__fastnbt_list_5",
+    "__fastnbt_list_6",
+    "__fastnbt_list_7",
+    "__fastnbt_list_8",
+    "__fastnbt_list_9",
+    "__fastnbt_list_10",
+    "__fastnbt_list_11",
+    "__fastnbt_list_12",
+];
+
+/// Get the element tag of an [`NbtList`] from the name it serializes with.
+pub(crate) fn list_token_tag(name: &str) -> Option<Tag> {
+    let id = LIST_TOKENS.iter().position(|t| *t == name)?;
+    Tag::try_from(id as u8).ok()
+}
+
+/// Types that are always serialized as the same NBT tag, and so can be the
+/// elements of an [`NbtList`].
+///
+/// Implement this for your own types that serialize as compounds:
+///
+/// ```
+/// # use fastnbt::{ListElement, Tag};
+/// #[derive(serde::Serialize)]
+/// struct Item {
+///     id: String,
+/// }
+///
+/// impl ListElement for Item {
+///     const TAG: Tag = Tag::Compound;
+/// }
+/// ```
+pub trait ListElement {
+    /// The tag this type serializes as.
+    const TAG: Tag;
+}
+
+macro_rules! list_element {
+    ($tag:ident: $($t:ty),*) => {
+        $(impl ListElement for $t {
+            const TAG: Tag = Tag::$tag;
+        })*
+    };
+}
+
+list_element!(Byte: bool, i8, u8);
+list_element!(Short: i16, u16);
+list_element!(Int: i32, u32, char);
+list_element!(Long: i64, u64);
+list_element!(Float: f32);
+list_element!(Double: f64);
+list_element!(String: String, str);
+list_element!(ByteArray: ByteArray);
+list_element!(IntArray: IntArray);
+list_element!(LongArray: LongArray);
+
+impl<T> ListElement for Vec<T> {
+    const TAG: Tag = Tag::List;
+}
+
+impl<T> ListElement for NbtList<T> {
+    const TAG: Tag = Tag::List;
+}
+
+impl<K, V, S> ListElement for HashMap<K, V, S> {
+    const TAG: Tag = Tag::Compound;
+}
+
+impl<K, V> ListElement for BTreeMap<K, V> {
+    const TAG: Tag = Tag::Compound;
+}
+
+impl<T: ListElement + ?Sized> ListElement for &T {
+    const TAG: Tag = T::TAG;
+}
+
+impl<T: ListElement + ?Sized> ListElement for Box<T> {
+    const TAG: Tag = T::TAG;
+}
+
+/// NBT List whose element type is known from its Rust type. This behaves
+/// like a `Vec<T>`, which it dereferences into, but an empty `NbtList`
+/// serializes with the element tag of `T` rather than the End tag an empty
+/// `Vec` has to use.
+///
+/// Some readers of NBT expect an empty list to have the element tag of the
+/// type it would contain.
+///
+/// ```
+/// # use fastnbt::{NbtList, Tag};
+/// #[derive(serde::Serialize, serde::Deserialize)]
+/// struct Player {
+///     scores: NbtList<i32>,
+/// }
+///
+/// let bytes = fastnbt::to_bytes(&Player {
+///     scores: NbtList::new(),
+/// })
+/// .unwrap();
+///
+/// // Compound "", then list "scores" with element tag Int and length 0.
+/// assert_eq!(bytes[3..15], [9, 0, 6, b's', b'c', b'o', b'r', b'e', b's', 3, 0, 0][..]);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct NbtList<T> {
+    data: Vec<T>,
+}
+
+impl<T> NbtList<T> {
+    /// Create a new empty list.
+    pub fn new() -> Self {
+        Self { data: Vec::new() }
+    }
+
+    /// Take the data out of the list.
+    pub fn into_inner(self) -> Vec<T> {
+        self.data
+    }
+}
+
+impl<T> Default for NbtList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: ListElement + Serialize> Serialize for NbtList<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut list =
+            serializer.serialize_tuple_struct(LIST_TOKENS[T::TAG as usize], self.data.len())?;
+        for element in &self.data {
+            list.serialize_field(element)?;
+        }
+        list.end()
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for NbtList<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        Vec::deserialize(deserializer).map(NbtList::from)
+    }
+}
+
+impl<T> From<Vec<T>> for NbtList<T> {
+    fn from(data: Vec<T>) -> Self {
+        Self { data }
+    }
+}
+
+impl<T> FromIterator<T> for NbtList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self {
+            data: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<T> Deref for NbtList<T> {
+    type Target = Vec<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.data
+    }
+}
+
+impl<T> DerefMut for NbtList<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.data
+    }
+}
+
+impl<T> IntoIterator for NbtList<T> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.data.into_iter()
+    }
+}
@@ -42,18 +42,29 @@
 /// ```
 ///
 /// Unlike SNBT, key/field names for compounds need quoted strings. `"key1"`
-/// above could not be simplified to just `key1`.
+/// above could not be simplified to just `key1`. As in `serde_json::json!`,
+/// any other key is an expression that converts into a `String`, such as a
+/// variable or `name.to_string()`.
 ///
-/// NBT Arrays are supported with
+/// The tag of each value follows from its Rust type, so use literal suffixes
+/// to choose it: `5i8` is a Byte, `5i16` a Short, `5` or `5i32` an Int, `5i64`
+/// a Long, `1.5f32` a Float and `1.5` or `1.5f64` a Double. Square brackets
+/// make a List, and NBT Arrays are supported with
 /// [SNBT](https://minecraft.fandom.com/wiki/NBT_format#SNBT_format) syntax:
 ///
 /// ```rust
-/// # use fastnbt::nbt;
-/// let _ = nbt!({
+/// # use fastnbt::{nbt, Value};
+/// let v = nbt!({
+///     "health": 20.0f32,
+///     "count": 5i8,
+///     "pos": [1.5, 64.0, -3.5],
 ///     "bytes": [B; 1, 2, 3],
 ///     "ints": [I; 1, 2, 3],
 ///     "longs": [L; 1, 2, 3],
 /// });
+/// assert!(matches!(v.pointer("/health"), Some(Value::Float(_))));
+/// assert!(matches!(v.pointer("/pos"), Some(Value::List(_))));
+/// assert!(matches!(v.pointer("/ints"), Some(Value::IntArray(_))));
 /// ```
 ///
 #[macro_export(local_inner_macros)]
@@ -0,0 +1,113 @@
+use std::fmt;
+
+use serde::{de::Visitor, Deserialize, Serialize};
+
+use crate::Tag;
+
+/// Name used to tell the serializer and deserializer that a value is [`Raw`].
+/// Other (de)serializers see an ordinary newtype struct of bytes.
+pub(crate) const RAW_TOKEN: &str = "__fastnbt_raw";
+
+/// The exact bytes of an NBT value, left undecoded. As a field of a struct,
+/// this captures the tag and payload of that entry as they are in the input,
+/// and serializing writes them back out unchanged. The name of the entry is
+/// not included, as it is written by the struct around it.
+///
+/// This is useful for passing through parts of the data that are not
+/// understood, or that must not change by even a byte, such as a value that
+/// something else has signed.
+///
+/// ```
+/// # use fastnbt::{nbt, Raw, Tag};
+/// # use serde::{Deserialize, Serialize};
+/// #[derive(Serialize, Deserialize)]
+/// struct Entity {
+///     id: String,
+///     data: Raw,
+/// }
+///
+/// let bytes = fastnbt::to_bytes(&nbt!({"id": "cow", "data": {"Age": 3}}))?;
+/// let entity: Entity = fastnbt::from_bytes(&bytes)?;
+/// assert_eq!(entity.data.tag(), Tag::Compound);
+/// # Ok::<(), fastnbt::error::Error>(())
+/// ```
+///
+/// This only works with fastnbt's own serializer and deserializer. Others,
+/// including those for [`Value`][`crate::Value`], see it as a newtype struct of
+/// bytes. Values of custom tags (see
+/// [`DeOpts::tag_handler`][`crate::DeOpts::tag_handler`]) cannot be captured.
+/// In a list, every `Raw` element must have the same tag, which is written
+/// once for the whole list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Raw {
+    data: Vec<u8>,
+}
+
+impl Raw {
+    /// The tag of the value.
+    pub fn tag(&self) -> Tag {
+        // The data is only ever captured from a value, so starts with a
+        // valid tag.
+        Tag::try_from(self.data[0]).unwrap()
+    }
+
+    /// The tag and payload of the value, exactly as they were in the input.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// The payload of the value, without its tag.
+    pub fn payload(&self) -> &[u8] {
+        &self.data[1..]
+    }
+
+    /// Take the tag and payload out of this value.
+    pub fn into_inner(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+impl Serialize for Raw {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_newtype_struct(RAW_TOKEN, serde_bytes::Bytes::new(&self.data))
+    }
+}
+
+impl<'de> Deserialize<'de> for Raw {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct RawVisitor;
+
+        impl<'de> Visitor<'de> for RawVisitor {
+            type Value = Raw;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("raw NBT value")
+            }
+
+            fn visit_byte_buf<E>(self, data: Vec<u8>) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                match data.first().map(|&id| Tag::try_from(id)) {
+                    Some(Ok(_)) => Ok(Raw { data }),
+                    _ => Err(E::custom("raw NBT value does not start with a valid tag")),
+                }
+            }
+
+            fn visit_bytes<E>(self, data: &[u8]) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                self.visit_byte_buf(data.to_vec())
+            }
+        }
+
+        deserializer.deserialize_newtype_struct(RAW_TOKEN, RawVisitor)
+    }
+}
@@ -0,0 +1,279 @@
+//! Infer the structure of NBT data without knowing it ahead of time.
+//!
+//! This is useful for reverse-engineering unknown NBT files, for example to
+//! work out what Rust types to deserialize them into. See [`infer_schema`].
+
+use std::collections::BTreeMap;
+
+use crate::{
+    error::{Error, Result},
+    input::MAX_DEPTH,
+    stream::{self, Parser},
+    Tag,
+};
+
+/// Description of the shape of an NBT value.
+///
+/// The elements of a list are described by a single node, the result of
+/// merging the shapes of every element: compounds contain the union of the
+/// keys of all elements, and lengths are the longest seen.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SchemaNode {
+    Byte,
+    Short,
+    Int,
+    Long,
+    Float,
+    Double,
+    String,
+    ByteArray {
+        len: usize,
+    },
+    IntArray {
+        len: usize,
+    },
+    LongArray {
+        len: usize,
+    },
+    List {
+        /// Shape of the elements. This is `None` for an empty list.
+        element: Option<Box<SchemaNode>>,
+        len: usize,
+    },
+    Compound(BTreeMap<String, SchemaNode>),
+}
+
+impl SchemaNode {
+    /// The NBT tag of values of this shape.
+    pub fn tag(&self) -> Tag {
+        match self {
+            SchemaNode::Byte => Tag::Byte,
+            SchemaNode::Short => Tag::Short,
+            SchemaNode::Int => Tag::Int,
+            SchemaNode::Long => Tag::Long,
+            SchemaNode::Float => Tag::Float,
+            SchemaNode::Double => Tag::Double,
+            SchemaNode::String => Tag::String,
+            SchemaNode::ByteArray { .. } => Tag::ByteArray,
+            SchemaNode::IntArray { .. } => Tag::IntArray,
+            SchemaNode::LongArray { .. } => Tag::LongArray,
+            SchemaNode::List { .. } => Tag::List,
+            SchemaNode::Compound(_) => Tag::Compound,
+        }
+    }
+
+    /// The Rust type to deserialize values of this shape into, as it would be
+    /// written in Rust source. This is like [`rust_type_for_tag`], but lists
+    /// have the type of their elements, such as `Vec<i32>`. Compounds, which
+    /// would need a struct of their own, are still a map of
+    /// [`Value`][`crate::Value`].
+    ///
+    /// ```
+    /// use fastnbt::{nbt, to_bytes, infer_schema, schema::SchemaNode};
+    ///
+    /// let data = to_bytes(&nbt!({"pos": [[1.0, 2.0]]}))?;
+    /// let fields = match infer_schema(&data)? {
+    ///     SchemaNode::Compound(fields) => fields,
+    ///     _ => unreachable!(),
+    /// };
+    /// assert_eq!(fields["pos"].rust_type(), "Vec<Vec<f64>>");
+    /// # Ok::<(), fastnbt::error::Error>(())
+    /// ```
+    pub fn rust_type(&self) -> String {
+        match self {
+            SchemaNode::List {
+                element: Some(element),
+                ..
+            } => format!("Vec<{}>", element.rust_type()),
+            _ => rust_type_for_tag(self.tag()).to_string(),
+        }
+    }
+
+    /// Merge the shape of another element of the same list into this one.
+    fn merge(&mut self, other: SchemaNode) {
+        match (self, other) {
+            (SchemaNode::Compound(fields), SchemaNode::Compound(other)) => {
+                for (key, node) in other {
+                    match fields.get_mut(&key) {
+                        Some(existing) => existing.merge(node),
+                        None => {
+                            fields.insert(key, node);
+                        }
+                    }
+                }
+            }
+            (
+                SchemaNode::List { element, len },
+                SchemaNode::List {
+                    element: other_element,
+                    len: other_len,
+                },
+            ) => {
+                *len = (*len).max(other_len);
+                match (element, other_element) {
+                    (Some(element), Some(other)) => element.merge(*other),
+                    (element @ None, other) => *element = other,
+                    (Some(_), None) => {}
+                }
+            }
+            (SchemaNode::ByteArray { len }, SchemaNode::ByteArray { len: other })
+            | (SchemaNode::IntArray { len }, SchemaNode::IntArray { len: other })
+            | (SchemaNode::LongArray { len }, SchemaNode::LongArray { len: other }) => {
+                *len = (*len).max(other);
+            }
+            // Elements of a list all have the same tag, so anything else
+            // already has the same shape.
+            _ => {}
+        }
+    }
+}
+
+/// Walk some NBT data and describe its structure: the tag of every value, the
+/// keys of every compound, and the lengths of lists and arrays. The root
+/// compound is returned as a [`SchemaNode::Compound`].
+///
+/// This uses the [`stream`] parser, so the data is never fully deserialized.
+///
+/// ```
+/// use fastnbt::{nbt, schema::SchemaNode};
+///
+/// let data = fastnbt::to_bytes(&nbt!({
+///     "name": "Steve",
+///     "pos": [1.0, 2.0, 3.0],
+/// }))?;
+///
+/// let schema = fastnbt::infer_schema(&data)?;
+/// let fields = match schema {
+///     SchemaNode::Compound(fields) => fields,
+///     _ => unreachable!(),
+/// };
+///
+/// assert_eq!(fields["name"], SchemaNode::String);
+/// assert_eq!(
+///     fields["pos"],
+///     SchemaNode::List {
+///         element: Some(Box::new(SchemaNode::Double)),
+///         len: 3,
+///     }
+/// );
+/// # Ok::<(), fastnbt::error::Error>(())
+/// ```
+pub fn infer_schema(data: &[u8]) -> Result<SchemaNode> {
+    let mut parser = Parser::new(data);
+
+    match parser.next().map_err(from_stream)? {
+        stream::Value::Compound(_) => Ok(SchemaNode::Compound(compound(&mut parser, 1)?)),
+        _ => Err(Error::no_root_compound()),
+    }
+}
+
+/// The idiomatic Rust type to deserialize a value with the given tag into, as
+/// it would be written in Rust source. This is useful for tools that generate
+/// Rust definitions for NBT data.
+///
+/// The tag alone does not say what a list or compound contains, so these map
+/// to types that can hold anything, built on [`Value`][`crate::Value`]. Use
+/// [`SchemaNode::rust_type`] to get a more precise type for an inferred
+/// schema. The End tag, which never has a value, maps to `()`.
+///
+/// ```
+/// use fastnbt::{rust_type_for_tag, Tag};
+///
+/// assert_eq!(rust_type_for_tag(Tag::Int), "i32");
+/// assert_eq!(rust_type_for_tag(Tag::IntArray), "fastnbt::IntArray");
+/// ```
+pub fn rust_type_for_tag(tag: Tag) -> &'static str {
+    match tag {
+        Tag::End => "()",
+        Tag::Byte => "i8",
+        Tag::Short => "i16",
+        Tag::Int => "i32",
+        Tag::Long => "i64",
+        Tag::Float => "f32",
+        Tag::Double => "f64",
+        Tag::ByteArray => "fastnbt::ByteArray",
+        Tag::String => "String",
+        Tag::List => "Vec<fastnbt::Value>",
+        Tag::Compound => "std::collections::HashMap<String, fastnbt::Value>",
+        Tag::IntArray => "fastnbt::IntArray",
+        Tag::LongArray => "fastnbt::LongArray",
+    }
+}
+
+fn from_stream(e: stream::Error) -> Error {
+    Error::bespoke(e.to_string())
+}
+
+/// Infer the fields of a compound, nested within `depth` compounds and lists
+/// including itself.
+fn compound(parser: &mut Parser<&[u8]>, depth: usize) -> Result<BTreeMap<String, SchemaNode>> {
+    let mut fields = BTreeMap::new();
+
+    loop {
+        let value = parser.next().map_err(from_stream)?;
+        let name = match &value {
+            stream::Value::CompoundEnd => return Ok(fields),
+            stream::Value::Byte(name, _)
+            | stream::Value::Short(name, _)
+            | stream::Value::Int(name, _)
+            | stream::Value::Long(name, _)
+            | stream::Value::Float(name, _)
+            | stream::Value::Double(name, _)
+            | stream::Value::ByteArray(name, _)
+            | stream::Value::String(name, _)
+            | stream::Value::List(name, _, _)
+            | stream::Value::Compound(name)
+            | stream::Value::IntArray(name, _)
+            | stream::Value::LongArray(name, _) => name.clone().unwrap_or_default(),
+            stream::Value::ListEnd => return Err(Error::bespoke("unexpected end of list")),
+        };
+
+        let node = node(parser, value, depth)?;
+        fields.insert(name, node);
+    }
+}
+
+fn node(parser: &mut Parser<&[u8]>, value: stream::Value, depth: usize) -> Result<SchemaNode> {
+    if matches!(value, stream::Value::Compound(_) | stream::Value::List(..)) && depth >= MAX_DEPTH {
+        return Err(Error::nesting_too_deep());
+    }
+
+    Ok(match value {
+        stream::Value::Byte(..) => SchemaNode::Byte,
+        stream::Value::Short(..) => SchemaNode::Short,
+        stream::Value::Int(..) => SchemaNode::Int,
+        stream::Value::Long(..) => SchemaNode::Long,
+        stream::Value::Float(..) => SchemaNode::Float,
+        stream::Value::Double(..) => SchemaNode::Double,
+        stream::Value::String(..) => SchemaNode::String,
+        stream::Value::ByteArray(_, v) => SchemaNode::ByteArray { len: v.len() },
+        stream::Value::IntArray(_, v) => SchemaNode::IntArray { len: v.len() },
+        stream::Value::LongArray(_, v) => SchemaNode::LongArray { len: v.len() },
+        stream::Value::Compound(_) => SchemaNode::Compound(compound(parser, depth + 1)?),
+        stream::Value::List(_, _, len) => {
+            let len = usize::try_from(len).map_err(|_| Error::invalid_size(len))?;
+            let mut element: Option<SchemaNode> = None;
+
+            loop {
+                let value = parser.next().map_err(from_stream)?;
+                if let stream::Value::ListEnd = value {
+                    break;
+                }
+
+                let el = node(parser, value, depth + 1)?;
+                match &mut element {
+                    Some(element) => element.merge(el),
+                    None => element = Some(el),
+                }
+            }
+
+            SchemaNode::List {
+                element: element.map(Box::new),
+                len,
+            }
+        }
+        stream::Value::CompoundEnd | stream::Value::ListEnd => {
+            return Err(Error::bespoke("unexpected end of compound or list"))
+        }
+    })
+}
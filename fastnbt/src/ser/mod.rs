@@ -8,15 +8,30 @@
 //!
 //! The examples directory contains some examples.
 //!
+//! # Sets
+//!
+//! Sets such as `HashSet` and `BTreeSet` are serialized as lists, in the order
+//! they iterate in. For a `HashSet` that order is arbitrary, so serializing the
+//! same set twice can give different bytes. Use a `BTreeSet` if the output
+//! needs to be deterministic, for example to compare or hash it.
+//!
 //! # 128 bit integers and UUIDs
 //!
 //! UUIDs tend to be stored in NBT using 4-long IntArrays. When serializing
 //! `i128` or `u128`, an IntArray of length 4 will be produced. This is stored
 //! as big endian i.e. the most significant bit (and int) is first.
 //!
+//! # Writing NBT without serde
+//!
+//! To choose the tag of every value yourself, for example to reproduce an
+//! existing file byte-for-byte, use [`NbtWriter`].
 mod array_serializer;
 mod name_serializer;
 mod serializer;
 mod write_nbt;
+mod writer;
 
 pub use serializer::*;
+pub use writer::NbtWriter;
+
+pub(crate) use write_nbt::WriteNbt;
@@ -2,12 +2,14 @@ use std::io::Write;
 
 use byteorder::{BigEndian, WriteBytesExt};
 use serde::{
-    ser::{self, Impossible, SerializeTuple},
+    ser::{self, SerializeTuple},
     Serialize,
 };
 
 use crate::{
     error::{Error, Result},
+    list::list_token_tag,
+    raw::RAW_TOKEN,
     Tag, BYTE_ARRAY_TOKEN, INT_ARRAY_TOKEN, LONG_ARRAY_TOKEN,
 };
 
@@ -23,6 +25,43 @@ enum DelayedHeader {
 
 pub struct Serializer<W: Write> {
     pub(crate) writer: W,
+    /// Spare buffers for the names of compound entries, so that serializing
+    /// many entries does not need an allocation for each name.
+    names: Vec<Vec<u8>>,
+}
+
+impl<W: Write> Serializer<W> {
+    pub(crate) fn new(writer: W) -> Self {
+        Self {
+            writer,
+            names: Vec::new(),
+        }
+    }
+
+    fn write_header(&mut self, header: DelayedHeader, actual_tag: Tag) -> Result<()> {
+        let writer = &mut self.writer;
+        match header {
+            DelayedHeader::Root => {
+                if actual_tag != Tag::Compound {
+                    // TODO: Test case for this.
+                    return Err(Error::no_root_compound());
+                }
+                writer.write_tag(Tag::Compound)?;
+                writer.write_size_prefixed_str("")?;
+            }
+            DelayedHeader::MapEntry { outer_name } => {
+                writer.write_tag(actual_tag)?;
+                writer.write_u16::<BigEndian>(outer_name.len() as u16)?;
+                writer.write_all(&outer_name)?;
+                self.names.push(outer_name);
+            }
+            DelayedHeader::List { len } => {
+                writer.write_tag(actual_tag)?;
+                writer.write_len(len)?;
+            }
+        };
+        Ok(())
+    }
 }
 
 macro_rules! no_root {
@@ -41,7 +80,7 @@ impl<'a, W: 'a + Write> serde::ser::Serializer for &'a mut Serializer<W> {
     type SerializeTupleVariant = SerializerTuple<'a, W>;
     type SerializeMap = SerializerMap<'a, W>;
     type SerializeStruct = SerializerMap<'a, W>;
-    type SerializeStructVariant = Impossible<(), Error>;
+    type SerializeStructVariant = SerializerStructVariant<'a, W>;
 
     no_root!(serialize_bool, bool);
     no_root!(serialize_i8, i8);
@@ -164,29 +203,6 @@ pub struct SerializerMap<'a, W: Write> {
     // first: bool,
 }
 
-fn write_header(writer: &mut impl Write, header: DelayedHeader, actual_tag: Tag) -> Result<()> {
-    match header {
-        DelayedHeader::Root => {
-            if actual_tag != Tag::Compound {
-                // TODO: Test case for this.
-                return Err(Error::no_root_compound());
-            }
-            writer.write_tag(Tag::Compound)?;
-            writer.write_size_prefixed_str("")?;
-        }
-        DelayedHeader::MapEntry { ref outer_name } => {
-            writer.write_tag(actual_tag)?;
-            writer.write_u16::<BigEndian>(outer_name.len() as u16)?;
-            writer.write_all(outer_name)?;
-        }
-        DelayedHeader::List { len } => {
-            writer.write_tag(actual_tag)?;
-            writer.write_len(len)?;
-        }
-    };
-    Ok(())
-}
-
 impl<'a, W: Write> serde::ser::SerializeMap for SerializerMap<'a, W> {
     type Ok = ();
 
@@ -197,7 +213,8 @@ impl<'a, W: Write> serde::ser::SerializeMap for SerializerMap<'a, W> {
         T: serde::Serialize,
     {
         // Get the name ahead of time.
-        let mut name = Vec::new();
+        let mut name = self.ser.names.pop().unwrap_or_default();
+        name.clear();
         key.serialize(&mut NameSerializer { name: &mut name })?;
         self.key = Some(name);
         Ok(())
@@ -207,47 +224,34 @@ impl<'a, W: Write> serde::ser::SerializeMap for SerializerMap<'a, W> {
     where
         T: serde::Serialize,
     {
-        let name = self.key.take().ok_or_else(|| {
-            Error::bespoke("serialize_value called before serialize_key".to_string())
-        })?;
-
-        let outer_tag = match std::str::from_utf8(&name) {
-            Ok(BYTE_ARRAY_TOKEN) => Tag::ByteArray,
-            Ok(INT_ARRAY_TOKEN) => Tag::IntArray,
-            Ok(LONG_ARRAY_TOKEN) => Tag::LongArray,
-            _ => Tag::Compound,
+        let name = self
+            .key
+            .take()
+            .ok_or_else(|| Error::bespoke("serialize_value called before serialize_key"))?;
+
+        let array_tag = match name.as_slice() {
+            n if n == BYTE_ARRAY_TOKEN.as_bytes() => Some(Tag::ByteArray),
+            n if n == INT_ARRAY_TOKEN.as_bytes() => Some(Tag::IntArray),
+            n if n == LONG_ARRAY_TOKEN.as_bytes() => Some(Tag::LongArray),
+            _ => None,
         };
 
         if let Some(header) = self.header.take() {
-            write_header(&mut self.ser.writer, header, outer_tag)?;
+            self.ser
+                .write_header(header, array_tag.unwrap_or(Tag::Compound))?;
         }
 
-        match std::str::from_utf8(&name) {
-            Ok(BYTE_ARRAY_TOKEN) => {
+        match array_tag {
+            Some(tag) => {
                 self.trailer = None;
-                value.serialize(ArraySerializer {
-                    ser: self.ser,
-                    tag: Tag::ByteArray,
-                })
+                self.ser.names.push(name);
+                value.serialize(ArraySerializer { ser: self.ser, tag })
             }
-            Ok(INT_ARRAY_TOKEN) => {
-                self.trailer = None;
-                value.serialize(ArraySerializer {
-                    ser: self.ser,
-                    tag: Tag::IntArray,
-                })
-            }
-            Ok(LONG_ARRAY_TOKEN) => {
-                self.trailer = None;
-                value.serialize(ArraySerializer {
-                    ser: self.ser,
-                    tag: Tag::LongArray,
-                })
-            }
-            _ => value.serialize(&mut Delayed {
+            None => value.serialize(&mut Delayed {
                 ser: &mut *self.ser,
                 header: Some(DelayedHeader::MapEntry { outer_name: name }),
                 is_list: false,
+                raw: false,
             }),
         }
     }
@@ -258,7 +262,7 @@ impl<'a, W: Write> serde::ser::SerializeMap for SerializerMap<'a, W> {
                 // if we still have a header, that means that we haven't seen a
                 // single key, so it must be an empty compound, we need to write
                 // the bytes we have delayed then close off the compound.
-                write_header(&mut self.ser.writer, header, Tag::Compound)?;
+                self.ser.write_header(header, Tag::Compound)?;
             }
             self.ser.writer.write_tag(tag)?;
         }
@@ -318,6 +322,7 @@ impl<'a, W: 'a + Write> serde::ser::SerializeTuple for SerializerTuple<'a, W> {
             ser: self.ser,
             header: self.first.then_some(DelayedHeader::List { len: self.len }),
             is_list: true,
+            raw: false,
         })?;
         self.first = false;
         Ok(())
@@ -362,16 +367,48 @@ impl<'a, W: 'a + Write> serde::ser::SerializeTupleVariant for SerializerTuple<'a
     }
 }
 
+/// Serializer for a struct variant, written as a compound with the variant
+/// name as its only key and the fields as a nested compound.
+pub struct SerializerStructVariant<'a, W: Write> {
+    map: SerializerMap<'a, W>,
+}
+
+impl<'a, W: 'a + Write> serde::ser::SerializeStructVariant for SerializerStructVariant<'a, W> {
+    type Ok = ();
+
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        ser::SerializeMap::serialize_entry(&mut self.map, key, value)
+    }
+
+    fn end(mut self) -> Result<()> {
+        // Write the header of the fields' compound if there were no fields.
+        if let Some(header) = self.map.header.take() {
+            self.map.ser.write_header(header, Tag::Compound)?;
+        }
+
+        // End of the fields, then of the compound holding the variant.
+        self.map.ser.writer.write_tag(Tag::End)?;
+        self.map.ser.writer.write_tag(Tag::End)
+    }
+}
+
 struct Delayed<'a, W: Write + 'a> {
     ser: &'a mut Serializer<W>,
     header: Option<DelayedHeader>,
     is_list: bool,
+    /// Whether the bytes being serialized are a [`Raw`][`crate::Raw`] value.
+    raw: bool,
 }
 
 impl<'a, W: Write + 'a> Delayed<'a, W> {
     fn write_header(&mut self, tag: Tag) -> Result<()> {
         if let Some(header) = self.header.take() {
-            write_header(&mut self.ser.writer, header, tag)?;
+            self.ser.write_header(header, tag)?;
         }
         Ok(())
     }
@@ -386,11 +423,12 @@ impl<'a, W: 'a + Write> serde::ser::Serializer for &'a mut Delayed<'a, W> {
     type SerializeTupleVariant = SerializerTuple<'a, W>;
     type SerializeMap = SerializerMap<'a, W>;
     type SerializeStruct = SerializerMap<'a, W>;
-    type SerializeStructVariant = Impossible<(), Error>;
+    type SerializeStructVariant = SerializerStructVariant<'a, W>;
 
     fn serialize_bool(self, v: bool) -> Result<()> {
         self.write_header(Tag::Byte)?;
-        self.ser.serialize_bool(v)
+        self.ser.writer.write_i8(v as i8)?;
+        Ok(())
     }
 
     fn serialize_i8(self, v: i8) -> Result<()> {
@@ -480,6 +518,16 @@ impl<'a, W: 'a + Write> serde::ser::Serializer for &'a mut Delayed<'a, W> {
     }
 
     fn serialize_bytes(self, v: &[u8]) -> Result<()> {
+        if self.raw {
+            // The first byte is the tag, which is part of the header.
+            let (&id, payload) = v
+                .split_first()
+                .ok_or_else(|| Error::bespoke("raw value has no tag"))?;
+            self.write_header(Tag::try_from(id).map_err(|_| Error::invalid_tag(id))?)?;
+            self.ser.writer.write_all(payload)?;
+            return Ok(());
+        }
+
         self.write_header(Tag::List)?;
         self.ser.writer.write_tag(Tag::Byte)?;
         self.ser.writer.write_len(v.len())?;
@@ -492,7 +540,7 @@ impl<'a, W: 'a + Write> serde::ser::Serializer for &'a mut Delayed<'a, W> {
         // us to delay writing the length of the list until we're finished
         // serializing the actua list. Users can filter a list with None values.
         if self.is_list {
-            Err(Error::bespoke("cannot serialize None in list".to_string()))
+            Err(Error::bespoke("cannot serialize None in list"))
         } else {
             Ok(())
         }
@@ -506,7 +554,7 @@ impl<'a, W: 'a + Write> serde::ser::Serializer for &'a mut Delayed<'a, W> {
     }
 
     fn serialize_unit(self) -> Result<()> {
-        Err(Error::bespoke("cannot serialize unit: ()".to_string()))
+        Err(Error::bespoke("cannot serialize unit: ()"))
     }
 
     fn serialize_unit_struct(self, name: &'static str) -> Result<()> {
@@ -525,10 +573,11 @@ impl<'a, W: 'a + Write> serde::ser::Serializer for &'a mut Delayed<'a, W> {
         self.ser.writer.write_size_prefixed_str(variant)
     }
 
-    fn serialize_newtype_struct<T: ?Sized>(self, _name: &'static str, value: &T) -> Result<()>
+    fn serialize_newtype_struct<T: ?Sized>(self, name: &'static str, value: &T) -> Result<()>
     where
         T: Serialize,
     {
+        self.raw = name == RAW_TOKEN;
         value.serialize(self)
     }
 
@@ -536,20 +585,19 @@ impl<'a, W: 'a + Write> serde::ser::Serializer for &'a mut Delayed<'a, W> {
         self,
         _name: &'static str,
         _variant_index: u32,
-        _variant: &'static str,
-        _value: &T,
+        variant: &'static str,
+        value: &T,
     ) -> Result<()>
     where
         T: Serialize,
     {
-        Err(Error::bespoke(
-            "cannot serialize newtype variant, please open fastnbt issue".to_string(),
-        ))
+        let mut map = self.serialize_map(Some(1))?;
+        ser::SerializeMap::serialize_entry(&mut map, variant, value)?;
+        ser::SerializeMap::end(map)
     }
 
     fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
-        let len =
-            len.ok_or_else(|| Error::bespoke("sequences must have a known length".to_string()))?;
+        let len = len.ok_or_else(|| Error::bespoke("sequences must have a known length"))?;
 
         self.serialize_tuple(len)
     }
@@ -579,10 +627,24 @@ impl<'a, W: 'a + Write> serde::ser::Serializer for &'a mut Delayed<'a, W> {
 
     fn serialize_tuple_struct(
         self,
-        _name: &'static str,
+        name: &'static str,
         len: usize,
     ) -> Result<Self::SerializeTupleStruct> {
-        self.serialize_tuple(len)
+        match list_token_tag(name) {
+            // An empty NbtList knows its element tag, so use it rather than End.
+            Some(tag) if len == 0 => {
+                self.write_header(Tag::List)?;
+                self.ser.writer.write_tag(tag)?;
+                self.ser.writer.write_len(0)?;
+
+                Ok(SerializerTuple {
+                    ser: self.ser,
+                    first: true,
+                    len,
+                })
+            }
+            _ => self.serialize_tuple(len),
+        }
     }
 
     fn serialize_tuple_variant(
@@ -592,6 +654,7 @@ impl<'a, W: 'a + Write> serde::ser::Serializer for &'a mut Delayed<'a, W> {
         _variant: &'static str,
         len: usize,
     ) -> Result<Self::SerializeTupleVariant> {
+        // Written as a list of the fields, without the variant name.
         self.serialize_seq(Some(len))
     }
 
@@ -612,11 +675,20 @@ impl<'a, W: 'a + Write> serde::ser::Serializer for &'a mut Delayed<'a, W> {
         self,
         _name: &'static str,
         _variant_index: u32,
-        _variant: &'static str,
+        variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeStructVariant> {
-        Err(Error::bespoke(
-            "cannot serialize struct variant, please open fastnbt issue".to_string(),
-        ))
+        self.write_header(Tag::Compound)?;
+
+        Ok(SerializerStructVariant {
+            map: SerializerMap {
+                ser: self.ser,
+                key: None,
+                header: Some(DelayedHeader::MapEntry {
+                    outer_name: variant.as_bytes().to_vec(),
+                }),
+                trailer: Some(Tag::End),
+            },
+        })
     }
 }
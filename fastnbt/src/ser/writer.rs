@@ -0,0 +1,240 @@
+use std::io::Write;
+
+use byteorder::{BigEndian, WriteBytesExt};
+
+use crate::{
+    error::{Error, Result},
+    Tag,
+};
+
+use super::write_nbt::WriteNbt;
+
+enum Layer {
+    Compound,
+    List { element: Tag, remaining: usize },
+}
+
+/// Low-level writer of NBT data, where the tag of every value is chosen
+/// explicitly rather than inferred from Rust types by serde. This gives full
+/// control over the output, for tools that must reproduce files exactly.
+///
+/// Values are written in the order the methods are called. Every method takes
+/// the name of the value, which is only used inside compounds; values inside a
+/// list have no name so it is ignored there. The writer checks that the
+/// structure is valid NBT as it goes, for example that list elements have the
+/// list's element tag and that lists get the number of elements they declared.
+///
+/// ```
+/// use fastnbt::{ser::NbtWriter, Tag};
+///
+/// let mut writer = NbtWriter::new(Vec::new());
+/// writer.start_compound("")?;
+/// writer.write_int("DataVersion", 3120)?;
+/// writer.start_list("Pos", Tag::Double, 3)?;
+/// writer.write_double("", 1.5)?;
+/// writer.write_double("", 64.0)?;
+/// writer.write_double("", -3.5)?;
+/// writer.end_list()?;
+/// writer.end_compound()?;
+/// let bytes = writer.finish()?;
+/// # let _ = bytes;
+/// # Ok::<(), fastnbt::error::Error>(())
+/// ```
+pub struct NbtWriter<W: Write> {
+    writer: W,
+    layers: Vec<Layer>,
+    root_done: bool,
+}
+
+impl<W: Write> NbtWriter<W> {
+    /// Create a writer that writes NBT data to `writer`. The first value
+    /// written must be the root compound.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            layers: Vec::new(),
+            root_done: false,
+        }
+    }
+
+    /// Write the tag and name of a value, if any are needed in the current
+    /// position.
+    fn header(&mut self, tag: Tag, name: &str) -> Result<()> {
+        match self.layers.last_mut() {
+            Some(Layer::Compound) => {
+                self.writer.write_tag(tag)?;
+                self.writer.write_size_prefixed_str(name)
+            }
+            Some(Layer::List { element, remaining }) => {
+                if *element != tag {
+                    return Err(Error::bespoke(format!(
+                        "cannot write {:?} to a list of {:?}",
+                        tag, element
+                    )));
+                }
+                if *remaining == 0 {
+                    return Err(Error::bespoke("list already has all its elements"));
+                }
+                *remaining -= 1;
+                Ok(())
+            }
+            None if self.root_done => Err(Error::bespoke("root compound already finished")),
+            None if tag != Tag::Compound => Err(Error::no_root_compound()),
+            None => {
+                self.writer.write_tag(tag)?;
+                self.writer.write_size_prefixed_str(name)
+            }
+        }
+    }
+
+    /// Write a [`Tag::Byte`]. Inside a list `name` is ignored, and this errors
+    /// unless the list's element tag is [`Tag::Byte`].
+    pub fn write_byte(&mut self, name: &str, value: i8) -> Result<()> {
+        self.header(Tag::Byte, name)?;
+        Ok(self.writer.write_i8(value)?)
+    }
+
+    /// Write a [`Tag::Short`]. Inside a list `name` is ignored, and this errors
+    /// unless the list's element tag is [`Tag::Short`].
+    pub fn write_short(&mut self, name: &str, value: i16) -> Result<()> {
+        self.header(Tag::Short, name)?;
+        Ok(self.writer.write_i16::<BigEndian>(value)?)
+    }
+
+    /// Write a [`Tag::Int`]. Inside a list `name` is ignored, and this errors
+    /// unless the list's element tag is [`Tag::Int`].
+    pub fn write_int(&mut self, name: &str, value: i32) -> Result<()> {
+        self.header(Tag::Int, name)?;
+        Ok(self.writer.write_i32::<BigEndian>(value)?)
+    }
+
+    /// Write a [`Tag::Long`]. Inside a list `name` is ignored, and this errors
+    /// unless the list's element tag is [`Tag::Long`].
+    pub fn write_long(&mut self, name: &str, value: i64) -> Result<()> {
+        self.header(Tag::Long, name)?;
+        Ok(self.writer.write_i64::<BigEndian>(value)?)
+    }
+
+    /// Write a [`Tag::Float`]. Inside a list `name` is ignored, and this errors
+    /// unless the list's element tag is [`Tag::Float`].
+    pub fn write_float(&mut self, name: &str, value: f32) -> Result<()> {
+        self.header(Tag::Float, name)?;
+        Ok(self.writer.write_f32::<BigEndian>(value)?)
+    }
+
+    /// Write a [`Tag::Double`]. Inside a list `name` is ignored, and this
+    /// errors unless the list's element tag is [`Tag::Double`].
+    pub fn write_double(&mut self, name: &str, value: f64) -> Result<()> {
+        self.header(Tag::Double, name)?;
+        Ok(self.writer.write_f64::<BigEndian>(value)?)
+    }
+
+    /// Write a [`Tag::String`]. Inside a list `name` is ignored, and this
+    /// errors unless the list's element tag is [`Tag::String`].
+    pub fn write_string(&mut self, name: &str, value: &str) -> Result<()> {
+        self.header(Tag::String, name)?;
+        self.writer.write_size_prefixed_str(value)
+    }
+
+    /// Write a [`Tag::ByteArray`]. Inside a list `name` is ignored, and this
+    /// errors unless the list's element tag is [`Tag::ByteArray`].
+    pub fn write_byte_array(&mut self, name: &str, value: &[i8]) -> Result<()> {
+        self.header(Tag::ByteArray, name)?;
+        self.writer.write_len(value.len())?;
+        for &v in value {
+            self.writer.write_i8(v)?;
+        }
+        Ok(())
+    }
+
+    /// Write a [`Tag::IntArray`]. Inside a list `name` is ignored, and this
+    /// errors unless the list's element tag is [`Tag::IntArray`].
+    pub fn write_int_array(&mut self, name: &str, value: &[i32]) -> Result<()> {
+        self.header(Tag::IntArray, name)?;
+        self.writer.write_len(value.len())?;
+        for &v in value {
+            self.writer.write_i32::<BigEndian>(v)?;
+        }
+        Ok(())
+    }
+
+    /// Write a [`Tag::LongArray`]. Inside a list `name` is ignored, and this
+    /// errors unless the list's element tag is [`Tag::LongArray`].
+    pub fn write_long_array(&mut self, name: &str, value: &[i64]) -> Result<()> {
+        self.header(Tag::LongArray, name)?;
+        self.writer.write_len(value.len())?;
+        for &v in value {
+            self.writer.write_i64::<BigEndian>(v)?;
+        }
+        Ok(())
+    }
+
+    /// Start a compound. Values written after this are part of the compound
+    /// until [`end_compound`][`NbtWriter::end_compound`] is called.
+    pub fn start_compound(&mut self, name: &str) -> Result<()> {
+        self.header(Tag::Compound, name)?;
+        self.layers.push(Layer::Compound);
+        Ok(())
+    }
+
+    /// End the current compound.
+    pub fn end_compound(&mut self) -> Result<()> {
+        match self.layers.last() {
+            Some(Layer::Compound) => {
+                self.layers.pop();
+                self.root_done = self.layers.is_empty();
+                self.writer.write_tag(Tag::End)
+            }
+            _ => Err(Error::bespoke("end_compound called outside of a compound")),
+        }
+    }
+
+    /// Start a list of `len` elements with the tag `element`. Exactly `len`
+    /// values must be written before calling
+    /// [`end_list`][`NbtWriter::end_list`]. An empty list can have the
+    /// element tag [`Tag::End`], as written by Minecraft for some empty lists.
+    pub fn start_list(&mut self, name: &str, element: Tag, len: usize) -> Result<()> {
+        if element == Tag::End && len != 0 {
+            return Err(Error::bespoke("only empty lists can have the End tag"));
+        }
+
+        self.header(Tag::List, name)?;
+        self.writer.write_tag(element)?;
+        self.writer.write_len(len)?;
+        self.layers.push(Layer::List {
+            element,
+            remaining: len,
+        });
+        Ok(())
+    }
+
+    /// End the current list.
+    pub fn end_list(&mut self) -> Result<()> {
+        match self.layers.last() {
+            Some(Layer::List { remaining: 0, .. }) => {
+                self.layers.pop();
+                Ok(())
+            }
+            Some(Layer::List { remaining, .. }) => Err(Error::bespoke(format!(
+                "list ended with {} elements still to write",
+                remaining
+            ))),
+            _ => Err(Error::bespoke("end_list called outside of a list")),
+        }
+    }
+
+    /// The underlying writer, for taking what has been written so far.
+    pub(crate) fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// Finish writing, returning the underlying writer. This errors if the
+    /// root compound has not been completed.
+    pub fn finish(self) -> Result<W> {
+        if self.root_done {
+            Ok(self.writer)
+        } else {
+            Err(Error::bespoke("root compound not finished"))
+        }
+    }
+}
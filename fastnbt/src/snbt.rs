@@ -0,0 +1,849 @@
+//! Convert between [`Value`] and SNBT, the text form of NBT used by Minecraft
+//! commands and data packs.
+//!
+//! ```
+//! use fastnbt::nbt;
+//!
+//! let value = nbt!({"id": "minecraft:stone", "Count": 1_i8});
+//!
+//! let snbt = fastnbt::snbt::to_snbt_string(&value);
+//! assert_eq!(snbt, r#"{Count:1b,id:"minecraft:stone"}"#);
+//! assert_eq!(fastnbt::from_snbt(&snbt)?, value);
+//! # Ok::<(), fastnbt::error::Error>(())
+//! ```
+//!
+//! For displaying large values to people, where exact output is not needed,
+//! see [`Value::to_pretty_string_with`].
+//!
+//! # Comments
+//!
+//! NBT has no comments, but SNBT files edited by hand sometimes do. `//` line
+//! comments are accepted anywhere whitespace is, except inside quoted strings.
+//! [`from_snbt`] discards them, while [`from_snbt_with_comments`] keeps them in
+//! a [`SnbtComments`] table so that [`to_snbt_string_with_comments`] can write
+//! them back out.
+//!
+//! ```
+//! use fastnbt::snbt::{from_snbt_with_comments, to_snbt_string_with_comments, SnbtOpts};
+//!
+//! let input = "{\n  // The block to place.\n  id: \"minecraft:stone\"\n}";
+//! let (value, comments) = from_snbt_with_comments(input)?;
+//! assert_eq!(comments.leading("/id"), Some(" The block to place."));
+//!
+//! let output = to_snbt_string_with_comments(&value, &comments, SnbtOpts::new().pretty(true));
+//! assert_eq!(output, input);
+//! # Ok::<(), fastnbt::error::Error>(())
+//! ```
+
+use std::{collections::HashMap, io::Write};
+
+use crate::{
+    error::{Error, Result},
+    input::MAX_DEPTH,
+    ByteArray, IntArray, LongArray, Value,
+};
+
+/// Options for writing SNBT. This object follows a builder pattern.
+///
+/// By default the output is compact, with no whitespace.
+#[derive(Debug, Clone, Default)]
+pub struct SnbtOpts {
+    pretty: bool,
+}
+
+impl SnbtOpts {
+    /// Create new options.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Set whether to put each compound entry and list element on its own
+    /// line, indented by two spaces per level. NBT arrays are always written
+    /// on a single line.
+    pub fn pretty(mut self, pretty: bool) -> Self {
+        self.pretty = pretty;
+        self
+    }
+}
+
+/// Comments kept from SNBT by [`from_snbt_with_comments`], to be written back
+/// out by [`to_snbt_writer_with_comments`].
+///
+/// Each comment is attached to a place in the value, given by a path in the
+/// syntax of [`Value::pointer`]:
+///
+/// * A leading comment comes directly before a value, or before the key of a
+///   compound entry. Comments between a key and its value are leading
+///   comments of the value too.
+/// * A trailing comment comes after the last entry of a compound or list,
+///   just before its closing bracket. This is also where the comments in an
+///   empty compound or list go.
+/// * The footer comes after the root value, at the end of the input.
+///
+/// The text of a comment is everything after the `//`, with consecutive
+/// comment lines joined by `\n`. Comments are not supported inside NBT
+/// arrays, as their elements have no paths.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SnbtComments {
+    leading: HashMap<String, String>,
+    trailing: HashMap<String, String>,
+    footer: Option<String>,
+}
+
+impl SnbtComments {
+    /// Create an empty set of comments.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Whether there are no comments at all.
+    pub fn is_empty(&self) -> bool {
+        self.leading.is_empty() && self.trailing.is_empty() && self.footer.is_none()
+    }
+
+    /// The comment before the value at `path`.
+    pub fn leading(&self, path: &str) -> Option<&str> {
+        self.leading.get(path).map(String::as_str)
+    }
+
+    /// Set the comment before the value at `path`.
+    pub fn set_leading(&mut self, path: impl Into<String>, comment: impl Into<String>) {
+        self.leading.insert(path.into(), comment.into());
+    }
+
+    /// The comment before the closing bracket of the compound or list at
+    /// `path`.
+    pub fn trailing(&self, path: &str) -> Option<&str> {
+        self.trailing.get(path).map(String::as_str)
+    }
+
+    /// Set the comment before the closing bracket of the compound or list at
+    /// `path`.
+    pub fn set_trailing(&mut self, path: impl Into<String>, comment: impl Into<String>) {
+        self.trailing.insert(path.into(), comment.into());
+    }
+
+    /// The comment after the root value.
+    pub fn footer(&self) -> Option<&str> {
+        self.footer.as_deref()
+    }
+
+    /// Set the comment after the root value.
+    pub fn set_footer(&mut self, comment: impl Into<String>) {
+        self.footer = Some(comment.into());
+    }
+}
+
+/// Write `value` as compact SNBT to `writer`.
+///
+/// Floats are written in the shortest form that parses back to exactly the
+/// same value, always with an `f` or `d` suffix so that whole numbers are not
+/// read as integers. Minecraft has no syntax for floats that are not finite,
+/// so these are written as Java writes them, for example `NaNf` and
+/// `-Infinityd`. [`from_snbt`] reads these back, but Minecraft reads them as
+/// strings.
+///
+/// The SNBT is written in many small pieces, so `writer` should usually be
+/// buffered, for example with a [`BufWriter`][`std::io::BufWriter`].
+/// Compound keys are written in sorted order so that the output is
+/// deterministic.
+pub fn to_snbt_writer<W: Write>(writer: W, value: &Value) -> Result<()> {
+    to_snbt_writer_with_opts(writer, value, SnbtOpts::new())
+}
+
+/// Similar to [`to_snbt_writer`] but with options.
+///
+/// ```
+/// # use fastnbt::{nbt, snbt::{to_snbt_writer_with_opts, SnbtOpts}};
+/// let value = nbt!({"Pos": [1.5, 64.0], "Data": [I; 1, 2]});
+///
+/// let mut out = Vec::new();
+/// to_snbt_writer_with_opts(&mut out, &value, SnbtOpts::new().pretty(true))?;
+///
+/// assert_eq!(
+///     String::from_utf8(out).unwrap(),
+///     "{\n  Data: [I; 1, 2],\n  Pos: [\n    1.5d,\n    64d\n  ]\n}"
+/// );
+/// # Ok::<(), fastnbt::error::Error>(())
+/// ```
+pub fn to_snbt_writer_with_opts<W: Write>(
+    mut writer: W,
+    value: &Value,
+    opts: SnbtOpts,
+) -> Result<()> {
+    SnbtWriter {
+        out: &mut writer,
+        opts: &opts,
+        comments: None,
+        path: String::new(),
+    }
+    .value(value, 0)
+}
+
+/// Similar to [`to_snbt_writer_with_opts`], but also writes `comments` in the
+/// places they are attached to. See [`SnbtComments`].
+///
+/// Comments run to the end of the line, so they are always followed by a new
+/// line, even if the output is otherwise compact.
+pub fn to_snbt_writer_with_comments<W: Write>(
+    mut writer: W,
+    value: &Value,
+    comments: &SnbtComments,
+    opts: SnbtOpts,
+) -> Result<()> {
+    let mut w = SnbtWriter {
+        out: &mut writer,
+        opts: &opts,
+        comments: Some(comments),
+        path: String::new(),
+    };
+    if let Some(comment) = comments.leading("") {
+        w.comment(comment, 0)?;
+    }
+    w.value(value, 0)?;
+    if let Some(footer) = comments.footer() {
+        for line in footer.split('\n') {
+            write!(w.out, "\n//{}", line)?;
+        }
+    }
+    Ok(())
+}
+
+/// Format `value` as compact SNBT.
+pub fn to_snbt_string(value: &Value) -> String {
+    to_snbt_string_with_opts(value, SnbtOpts::new())
+}
+
+/// Similar to [`to_snbt_string`] but with options.
+pub fn to_snbt_string_with_opts(value: &Value, opts: SnbtOpts) -> String {
+    let mut out = Vec::new();
+    to_snbt_writer_with_opts(&mut out, value, opts).expect("writing to a Vec cannot fail");
+    String::from_utf8(out).expect("SNBT is valid UTF-8")
+}
+
+/// Similar to [`to_snbt_writer_with_comments`], but returns a `String`.
+pub fn to_snbt_string_with_comments(
+    value: &Value,
+    comments: &SnbtComments,
+    opts: SnbtOpts,
+) -> String {
+    let mut out = Vec::new();
+    to_snbt_writer_with_comments(&mut out, value, comments, opts)
+        .expect("writing to a Vec cannot fail");
+    String::from_utf8(out).expect("SNBT is valid UTF-8")
+}
+
+struct SnbtWriter<'a, W> {
+    out: W,
+    opts: &'a SnbtOpts,
+    comments: Option<&'a SnbtComments>,
+    /// Path to the value being written, only kept up to date when writing
+    /// comments.
+    path: String,
+}
+
+impl<'a, W: Write> SnbtWriter<'a, W> {
+    fn value(&mut self, value: &Value, depth: usize) -> Result<()> {
+        match value {
+            Value::Byte(v) => write!(self.out, "{}b", v)?,
+            Value::Short(v) => write!(self.out, "{}s", v)?,
+            Value::Int(v) => write!(self.out, "{}", v)?,
+            Value::Long(v) => write!(self.out, "{}L", v)?,
+            Value::Float(v) => self.float(v.to_string(), 'f')?,
+            Value::Double(v) => self.float(v.to_string(), 'd')?,
+            Value::String(v) => self.string(v)?,
+            Value::ByteArray(v) => self.array("B", v.iter().map(|v| format!("{}b", v)))?,
+            Value::IntArray(v) => self.array("I", v.iter())?,
+            Value::LongArray(v) => self.array("L", v.iter().map(|v| format!("{}L", v)))?,
+            Value::List(v) => self.list(v, depth)?,
+            Value::Compound(v) => self.compound(v, depth)?,
+        }
+        Ok(())
+    }
+
+    /// Write a float, given `formatted` by its `Display` implementation. This
+    /// is the shortest form that parses back to exactly the same value, except
+    /// for infinities, which are written as Java writes them.
+    fn float(&mut self, formatted: String, suffix: char) -> Result<()> {
+        let formatted = match formatted.as_str() {
+            "inf" => "Infinity",
+            "-inf" => "-Infinity",
+            other => other,
+        };
+        Ok(write!(self.out, "{}{}", formatted, suffix)?)
+    }
+
+    fn array<T: std::fmt::Display>(
+        &mut self,
+        prefix: &str,
+        elements: impl Iterator<Item = T>,
+    ) -> Result<()> {
+        write!(self.out, "[{};", prefix)?;
+        for (i, el) in elements.enumerate() {
+            match (i, self.opts.pretty) {
+                (0, true) => write!(self.out, " {}", el)?,
+                (0, false) => write!(self.out, "{}", el)?,
+                (_, true) => write!(self.out, ", {}", el)?,
+                (_, false) => write!(self.out, ",{}", el)?,
+            }
+        }
+        Ok(self.out.write_all(b"]")?)
+    }
+
+    fn list(&mut self, list: &[Value], depth: usize) -> Result<()> {
+        let trailing = self.comments.and_then(|c| c.trailing(&self.path));
+        if list.is_empty() && trailing.is_none() {
+            return Ok(self.out.write_all(b"[]")?);
+        }
+
+        self.out.write_all(b"[")?;
+        for (i, el) in list.iter().enumerate() {
+            self.separator(i, depth + 1)?;
+            let len = self.path.len();
+            if self.comments.is_some() {
+                self.path.push_str(&format!("/{}", i));
+            }
+            self.leading_comment(depth + 1)?;
+            self.value(el, depth + 1)?;
+            self.path.truncate(len);
+        }
+        if let Some(comment) = trailing {
+            self.trailing_comment(comment, depth + 1)?;
+        }
+        self.close(depth)?;
+        Ok(self.out.write_all(b"]")?)
+    }
+
+    fn compound(&mut self, map: &HashMap<String, Value>, depth: usize) -> Result<()> {
+        let trailing = self.comments.and_then(|c| c.trailing(&self.path));
+        if map.is_empty() && trailing.is_none() {
+            return Ok(self.out.write_all(b"{}")?);
+        }
+
+        let mut entries: Vec<_> = map.iter().collect();
+        entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+
+        self.out.write_all(b"{")?;
+        for (i, (key, value)) in entries.into_iter().enumerate() {
+            self.separator(i, depth + 1)?;
+            let len = self.path.len();
+            if self.comments.is_some() {
+                push_path_key(&mut self.path, key);
+            }
+            self.leading_comment(depth + 1)?;
+            self.key(key)?;
+            self.out
+                .write_all(if self.opts.pretty { b": " } else { b":" })?;
+            self.value(value, depth + 1)?;
+            self.path.truncate(len);
+        }
+        if let Some(comment) = trailing {
+            self.trailing_comment(comment, depth + 1)?;
+        }
+        self.close(depth)?;
+        Ok(self.out.write_all(b"}")?)
+    }
+
+    /// Write the leading comment of the value at the current path, if any.
+    fn leading_comment(&mut self, depth: usize) -> Result<()> {
+        match self.comments.and_then(|c| c.leading(&self.path)) {
+            Some(comment) => self.comment(comment, depth),
+            None => Ok(()),
+        }
+    }
+
+    /// Write a comment on lines of its own, leaving the output indented for
+    /// whatever follows it.
+    fn comment(&mut self, comment: &str, depth: usize) -> Result<()> {
+        for line in comment.split('\n') {
+            writeln!(self.out, "//{}", line)?;
+            if self.opts.pretty {
+                self.indent(depth)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write a comment after the last element of a compound or list.
+    fn trailing_comment(&mut self, comment: &str, depth: usize) -> Result<()> {
+        for line in comment.split('\n') {
+            if self.opts.pretty {
+                self.out.write_all(b"\n")?;
+                self.indent(depth)?;
+                write!(self.out, "//{}", line)?;
+            } else {
+                writeln!(self.out, "//{}", line)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write what comes before the `i`th entry of a compound or list.
+    fn separator(&mut self, i: usize, depth: usize) -> Result<()> {
+        if i != 0 {
+            self.out.write_all(b",")?;
+        }
+        if self.opts.pretty {
+            self.out.write_all(b"\n")?;
+            self.indent(depth)?;
+        }
+        Ok(())
+    }
+
+    /// Write what comes before the closing bracket of a non-empty compound or
+    /// list.
+    fn close(&mut self, depth: usize) -> Result<()> {
+        if self.opts.pretty {
+            self.out.write_all(b"\n")?;
+            self.indent(depth)?;
+        }
+        Ok(())
+    }
+
+    fn indent(&mut self, depth: usize) -> Result<()> {
+        for _ in 0..depth {
+            self.out.write_all(b"  ")?;
+        }
+        Ok(())
+    }
+
+    fn key(&mut self, key: &str) -> Result<()> {
+        if !key.is_empty() && key.bytes().all(is_unquoted_char) {
+            Ok(self.out.write_all(key.as_bytes())?)
+        } else {
+            self.string(key)
+        }
+    }
+
+    fn string(&mut self, s: &str) -> Result<()> {
+        self.out.write_all(b"\"")?;
+        for part in s.split_inclusive(['"', '\\']) {
+            match part.as_bytes().last() {
+                Some(c @ (b'"' | b'\\')) => {
+                    self.out.write_all(&part.as_bytes()[..part.len() - 1])?;
+                    self.out.write_all(&[b'\\', *c])?;
+                }
+                _ => self.out.write_all(part.as_bytes())?,
+            }
+        }
+        Ok(self.out.write_all(b"\"")?)
+    }
+}
+
+/// Add a compound key to a path of the form used by [`Value::pointer`].
+fn push_path_key(path: &mut String, key: &str) {
+    path.push('/');
+    path.push_str(&key.replace('~', "~0").replace('/', "~1"));
+}
+
+/// Characters allowed in unquoted keys and strings.
+fn is_unquoted_char(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, b'_' | b'-' | b'.' | b'+')
+}
+
+/// Parse SNBT into a [`Value`].
+///
+/// This accepts the syntax written by Minecraft and by [`to_snbt_writer`]:
+/// quoted or unquoted keys and strings, numbers with optional type suffixes,
+/// `true` and `false` as bytes, lists, and `[B; ...]`, `[I; ...]` and
+/// `[L; ...]` arrays. As in Minecraft, an unquoted value that is not a valid
+/// number, such as an integer that is out of range, is a string. Floats that
+/// are not finite are accepted in the form [`to_snbt_writer`] writes them:
+/// `NaN`, `Infinity` or `-Infinity` followed by an `f` or `d` suffix.
+///
+/// ```
+/// # use fastnbt::nbt;
+/// let value = fastnbt::from_snbt("{Count: 3b, Pos: [1.5, 2d], Motion: [I; 1, 2]}")?;
+/// assert_eq!(
+///     value,
+///     nbt!({"Count": 3_i8, "Pos": [1.5, 2.0], "Motion": [I; 1, 2]})
+/// );
+/// # Ok::<(), fastnbt::error::Error>(())
+/// ```
+///
+/// `//` comments are skipped. To keep them, use [`from_snbt_with_comments`].
+/// Compounds, lists and arrays can be nested at most 512 deep, as in
+/// Minecraft.
+pub fn from_snbt(input: &str) -> Result<Value> {
+    Parser::new(input, false).parse()
+}
+
+/// Similar to [`from_snbt`], but also returns the comments in the input. See
+/// [`SnbtComments`] for where each comment is attached.
+pub fn from_snbt_with_comments(input: &str) -> Result<(Value, SnbtComments)> {
+    let mut parser = Parser::new(input, true);
+    let value = parser.parse()?;
+    Ok((value, parser.comments.unwrap_or_default()))
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+    /// Comments found so far, if they are being kept.
+    comments: Option<SnbtComments>,
+    /// Comments not yet attached to anything.
+    pending: Option<String>,
+    /// Path to the value being parsed, only kept up to date when keeping
+    /// comments.
+    path: String,
+    /// Number of compounds and lists the value being parsed is within.
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str, keep_comments: bool) -> Self {
+        Self {
+            input,
+            pos: 0,
+            comments: keep_comments.then(SnbtComments::new),
+            pending: None,
+            path: String::new(),
+            depth: 0,
+        }
+    }
+
+    fn parse(&mut self) -> Result<Value> {
+        let value = self.value()?;
+        self.skip_whitespace();
+        if self.pos != self.input.len() {
+            return Err(self.error("unexpected trailing characters"));
+        }
+        if let (Some(comments), Some(footer)) = (&mut self.comments, self.pending.take()) {
+            comments.footer = Some(footer);
+        }
+        Ok(value)
+    }
+
+    fn error(&self, msg: &str) -> Error {
+        Error::bespoke(format!("invalid snbt: {} at position {}", msg, self.pos))
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.as_bytes().get(self.pos).copied()
+    }
+
+    /// Skip whitespace and comments, keeping the comments as pending if
+    /// comments are being kept.
+    fn skip_whitespace(&mut self) {
+        loop {
+            while self.peek().is_some_and(|c| c.is_ascii_whitespace()) {
+                self.pos += 1;
+            }
+            if !self.input[self.pos..].starts_with("//") {
+                return;
+            }
+
+            let rest = &self.input[self.pos + 2..];
+            let line = &rest[..rest.find('\n').unwrap_or(rest.len())];
+            self.pos += 2 + line.len();
+            if self.comments.is_some() {
+                let line = line.strip_suffix('\r').unwrap_or(line);
+                match &mut self.pending {
+                    Some(pending) => {
+                        pending.push('\n');
+                        pending.push_str(line);
+                    }
+                    None => self.pending = Some(line.to_owned()),
+                }
+            }
+        }
+    }
+
+    /// Attach any pending comments to the current path.
+    fn attach_pending(&mut self, trailing: bool) {
+        if let (Some(comments), Some(comment)) = (&mut self.comments, self.pending.take()) {
+            let map = if trailing {
+                &mut comments.trailing
+            } else {
+                &mut comments.leading
+            };
+            match map.get_mut(&self.path) {
+                Some(existing) => {
+                    existing.push('\n');
+                    existing.push_str(&comment);
+                }
+                None => {
+                    map.insert(self.path.clone(), comment);
+                }
+            }
+        }
+    }
+
+    /// Skip whitespace then consume `c`, returning whether it was there.
+    fn eat(&mut self, c: u8) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: u8) -> Result<()> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", c as char)))
+        }
+    }
+
+    fn value(&mut self) -> Result<Value> {
+        self.skip_whitespace();
+        self.attach_pending(false);
+        match self.peek() {
+            Some(b'{') => self.nested(Self::compound),
+            Some(b'[') => self.nested(Self::list_or_array),
+            Some(b'"' | b'\'') => Ok(Value::String(self.quoted()?)),
+            Some(c) if is_unquoted_char(c) => Ok(unquoted_value(self.unquoted())),
+            Some(_) => Err(self.error("unexpected character")),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    /// Parse a compound, list or array with `f`, erroring if it is nested too
+    /// deeply.
+    fn nested(&mut self, f: fn(&mut Self) -> Result<Value>) -> Result<Value> {
+        if self.depth >= MAX_DEPTH {
+            return Err(self.error(&format!("nesting exceeds the limit of {}", MAX_DEPTH)));
+        }
+        self.depth += 1;
+        let value = f(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn compound(&mut self) -> Result<Value> {
+        self.expect(b'{')?;
+        let mut map = HashMap::new();
+        if self.eat(b'}') {
+            self.attach_pending(true);
+            return Ok(Value::Compound(map));
+        }
+
+        loop {
+            let key = self.key()?;
+            self.expect(b':')?;
+            let len = self.path.len();
+            if self.comments.is_some() {
+                push_path_key(&mut self.path, &key);
+            }
+            let value = self.value()?;
+            self.path.truncate(len);
+            map.insert(key, value);
+
+            if self.eat(b'}') {
+                self.attach_pending(true);
+                return Ok(Value::Compound(map));
+            }
+            self.expect(b',')?;
+        }
+    }
+
+    fn key(&mut self) -> Result<String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'"' | b'\'') => self.quoted(),
+            Some(c) if is_unquoted_char(c) => Ok(self.unquoted().to_owned()),
+            _ => Err(self.error("expected key")),
+        }
+    }
+
+    fn list_or_array(&mut self) -> Result<Value> {
+        self.expect(b'[')?;
+
+        let bytes = self.input.as_bytes();
+        if let (Some(&kind @ (b'B' | b'I' | b'L')), Some(b';')) =
+            (bytes.get(self.pos), bytes.get(self.pos + 1))
+        {
+            self.pos += 2;
+            return self.array(kind);
+        }
+
+        let mut list = Vec::new();
+        if self.eat(b']') {
+            self.attach_pending(true);
+            return Ok(Value::List(list));
+        }
+
+        loop {
+            self.skip_whitespace();
+            let start = self.pos;
+            let len = self.path.len();
+            if self.comments.is_some() {
+                self.path.push_str(&format!("/{}", list.len()));
+            }
+            let value = self.value()?;
+            self.path.truncate(len);
+            if list
+                .first()
+                .is_some_and(|first: &Value| first.tag() != value.tag())
+            {
+                self.pos = start;
+                return Err(self.error("list elements must all be the same type"));
+            }
+            list.push(value);
+
+            if self.eat(b']') {
+                self.attach_pending(true);
+                return Ok(Value::List(list));
+            }
+            self.expect(b',')?;
+        }
+    }
+
+    /// Parse the elements of an NBT array, after the `[B;` or similar.
+    fn array(&mut self, kind: u8) -> Result<Value> {
+        let mut elements = Vec::new();
+        if !self.eat(b']') {
+            loop {
+                self.skip_whitespace();
+                if self.pending.is_some() {
+                    return Err(self.error("comments are not supported inside arrays"));
+                }
+                let start = self.pos;
+                let el = match self.value()? {
+                    Value::Byte(v) => v.into(),
+                    Value::Short(v) => v.into(),
+                    Value::Int(v) => v.into(),
+                    Value::Long(v) => v,
+                    _ => {
+                        self.pos = start;
+                        return Err(self.error("expected integer array element"));
+                    }
+                };
+                elements.push((start, el));
+
+                if self.eat(b']') {
+                    break;
+                }
+                self.expect(b',')?;
+            }
+        }
+        if self.pending.is_some() {
+            return Err(self.error("comments are not supported inside arrays"));
+        }
+
+        let out_of_range = |(start, _)| {
+            let mut parser = Parser::new(self.input, false);
+            parser.pos = start;
+            parser.error("array element out of range")
+        };
+
+        Ok(match kind {
+            b'B' => Value::ByteArray(ByteArray::new(
+                elements
+                    .into_iter()
+                    .map(|(start, v)| i8::try_from(v).map_err(|_| out_of_range((start, v))))
+                    .collect::<Result<_>>()?,
+            )),
+            b'I' => Value::IntArray(IntArray::new(
+                elements
+                    .into_iter()
+                    .map(|(start, v)| i32::try_from(v).map_err(|_| out_of_range((start, v))))
+                    .collect::<Result<_>>()?,
+            )),
+            _ => Value::LongArray(LongArray::new(
+                elements.into_iter().map(|(_, v)| v).collect(),
+            )),
+        })
+    }
+
+    fn unquoted(&mut self) -> &'a str {
+        let start = self.pos;
+        while self.peek().is_some_and(is_unquoted_char) {
+            self.pos += 1;
+        }
+        &self.input[start..self.pos]
+    }
+
+    fn quoted(&mut self) -> Result<String> {
+        let quote = self.peek().expect("called on a quote");
+        self.pos += 1;
+
+        let mut out = String::new();
+        loop {
+            let rest = &self.input[self.pos..];
+            let end = match rest.find([quote as char, '\\']) {
+                Some(end) => end,
+                None => return Err(self.error("unterminated string")),
+            };
+            out.push_str(&rest[..end]);
+            self.pos += end;
+
+            if self.peek() == Some(quote) {
+                self.pos += 1;
+                return Ok(out);
+            }
+
+            // An escape.
+            match self.input.as_bytes().get(self.pos + 1) {
+                Some(&c @ (b'\\' | b'"' | b'\'')) => {
+                    out.push(c as char);
+                    self.pos += 2;
+                }
+                _ => return Err(self.error("invalid escape")),
+            }
+        }
+    }
+}
+
+/// Interpret an unquoted value: a number, a boolean, or otherwise a string.
+fn unquoted_value(s: &str) -> Value {
+    let (body, suffix) = s.split_at(s.len() - 1);
+    let suffix = suffix.as_bytes()[0].to_ascii_lowercase();
+
+    let parsed = match suffix {
+        b'b' if is_integer(body) => body.parse().ok().map(Value::Byte),
+        b's' if is_integer(body) => body.parse().ok().map(Value::Short),
+        b'l' if is_integer(body) => body.parse().ok().map(Value::Long),
+        b'f' if is_decimal(body) => body.parse().ok().map(Value::Float),
+        b'd' if is_decimal(body) => body.parse().ok().map(Value::Double),
+        b'f' => special_float(body).map(|v| Value::Float(v as f32)),
+        b'd' => special_float(body).map(Value::Double),
+        _ if is_integer(s) => s.parse().ok().map(Value::Int),
+        // Doubles without a suffix need a decimal point.
+        _ if s.contains('.') && is_decimal(s) => s.parse().ok().map(Value::Double),
+        _ => match s {
+            "true" => Some(Value::Byte(1)),
+            "false" => Some(Value::Byte(0)),
+            _ => None,
+        },
+    };
+
+    parsed.unwrap_or_else(|| Value::String(s.to_owned()))
+}
+
+/// Interpret the floats that have no decimal form, as written by Java.
+fn special_float(s: &str) -> Option<f64> {
+    match s {
+        "NaN" => Some(f64::NAN),
+        "Infinity" | "+Infinity" => Some(f64::INFINITY),
+        "-Infinity" => Some(f64::NEG_INFINITY),
+        _ => None,
+    }
+}
+
+/// Whether `s` is an optionally signed sequence of digits.
+fn is_integer(s: &str) -> bool {
+    let digits = s.strip_prefix(['-', '+']).unwrap_or(s);
+    !digits.is_empty() && digits.bytes().all(|c| c.is_ascii_digit())
+}
+
+/// Whether `s` is a decimal number, with an optional sign, fractional part and
+/// exponent.
+fn is_decimal(s: &str) -> bool {
+    let s = s.strip_prefix(['-', '+']).unwrap_or(s);
+    let (mantissa, exponent) = match s.find(['e', 'E']) {
+        Some(i) => (&s[..i], Some(&s[i + 1..])),
+        None => (s, None),
+    };
+
+    let (whole, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let digits = |s: &str| s.bytes().all(|c| c.is_ascii_digit());
+
+    (!whole.is_empty() || !frac.is_empty())
+        && digits(whole)
+        && digits(frac)
+        && exponent.is_none_or(is_integer)
+}
@@ -314,30 +314,49 @@ impl<R: Read> Parser<R> {
                 let element_tag = self.reader.read_u8()?;
                 let element_tag = u8_to_tag(element_tag)?;
                 let size = self.reader.read_i32::<BigEndian>()?;
+                if size < 0 {
+                    return Err(Error::bespoke("negative list length"));
+                }
+                if element_tag == Tag::End && size > 0 {
+                    return Err(Error::bespoke("list of type end with non-zero length"));
+                }
                 self.layers.push(Layer::List(element_tag, size));
                 Ok(Value::List(name, element_tag, size))
             }
             Tag::String => Ok(Value::String(name, self.read_size_prefixed_string()?)),
             Tag::ByteArray => {
                 let size = self.reader.read_i32::<BigEndian>()?;
-                let mut buf = vec![0u8; size as usize];
-                self.reader.read_exact(&mut buf[..])?;
+                if size < 0 {
+                    return Err(Error::bespoke("negative array length"));
+                }
+                // Read through `take` so a corrupt length cannot force a huge allocation.
+                let mut buf = Vec::new();
+                (&mut self.reader).take(size as u64).read_to_end(&mut buf)?;
+                if buf.len() != size as usize {
+                    return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+                }
                 Ok(Value::ByteArray(name, vec_u8_into_i8(buf)))
             }
             Tag::IntArray => {
                 let size = self.reader.read_i32::<BigEndian>()?;
-                let mut buf = vec![0i32; size as usize];
-                for i in 0..size {
-                    buf[i as usize] = self.reader.read_i32::<BigEndian>()?;
+                if size < 0 {
+                    return Err(Error::bespoke("negative array length"));
+                }
+                let mut buf = Vec::with_capacity((size as usize).min(4096));
+                for _ in 0..size {
+                    buf.push(self.reader.read_i32::<BigEndian>()?);
                 }
 
                 Ok(Value::IntArray(name, buf))
             }
             Tag::LongArray => {
                 let size = self.reader.read_i32::<BigEndian>()?;
-                let mut buf = vec![0i64; size as usize];
-                for i in 0..size {
-                    buf[i as usize] = self.reader.read_i64::<BigEndian>()?;
+                if size < 0 {
+                    return Err(Error::bespoke("negative array length"));
+                }
+                let mut buf = Vec::with_capacity((size as usize).min(4096));
+                for _ in 0..size {
+                    buf.push(self.reader.read_i64::<BigEndian>()?);
                 }
 
                 Ok(Value::LongArray(name, buf))
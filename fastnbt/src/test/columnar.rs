@@ -0,0 +1,129 @@
+use crate::{
+    columnar::{decode, encode, ColumnarBlob},
+    Value,
+};
+
+fn chunk(i: i32) -> Value {
+    nbt!({
+        "xPos": i,
+        "zPos": -i,
+        "Status": format!("status {}", i),
+        "Heightmap": [L; i as i64, 2],
+        "Light": [B; 1, 2, 3],
+        "Sections": [
+            {"Y": 0_i8, "Biomes": [I; i]},
+            {"Y": 1_i8, "Biomes": [I; 1, 2, i]},
+        ],
+        "Entities": [],
+        "LastUpdate": i as i64 * 20,
+        "Inhabited": 0.5_f32,
+        "Scale": 1.0,
+        "Flags": 3_i16,
+        "a/b": "escaped",
+    })
+}
+
+#[test]
+fn columnar_round_trip() {
+    let chunks: Vec<_> = (0..50).map(chunk).collect();
+    let blob = encode(&chunks).unwrap();
+    assert_eq!(decode(&blob).unwrap(), chunks);
+
+    // Stored and loaded again.
+    let blob = ColumnarBlob::from_bytes(blob.into_bytes());
+    assert_eq!(decode(&blob).unwrap(), chunks);
+}
+
+#[test]
+fn columnar_groups_values_by_column() {
+    let blob = encode(&[nbt!({"a": 1, "b": 2}), nbt!({"a": 3, "b": 4})]).unwrap();
+
+    #[rustfmt::skip]
+    let expected = [
+        0, 0, 0, 2, // count
+        10, 0, 0, 0, 2, // compound of two entries
+        0, 1, b'a', 3,
+        0, 1, b'b', 3,
+        0, 0, 0, 8, 0, 0, 0, 1, 0, 0, 0, 3, // column of a
+        0, 0, 0, 8, 0, 0, 0, 2, 0, 0, 0, 4, // column of b
+    ];
+    assert_eq!(blob.as_bytes(), expected);
+}
+
+#[test]
+fn columnar_empty() {
+    let blob = encode(&[]).unwrap();
+    assert_eq!(blob.as_bytes(), [0, 0, 0, 0]);
+    assert!(decode(&blob).unwrap().is_empty());
+}
+
+#[test]
+fn columnar_rejects_different_structures() {
+    let err = |values: &[Value]| encode(values).unwrap_err().to_string();
+
+    assert_eq!(
+        err(&[chunk(1), chunk(2), nbt!({"xPos": 1})]),
+        "value 2 does not have the same structure as the first, at ''"
+    );
+
+    let mut missing = chunk(3);
+    if let Value::Compound(map) = &mut missing {
+        map.remove("zPos");
+        map.insert("yPos".to_string(), Value::Int(0));
+    }
+    assert_eq!(
+        err(&[chunk(1), missing]),
+        "value 1 does not have the same structure as the first, at '/zPos'"
+    );
+
+    let mut retagged = chunk(4);
+    *retagged.pointer_mut("/Sections/1/Y").unwrap() = Value::Int(1);
+    assert_eq!(
+        err(&[chunk(1), retagged]),
+        "value 1 does not have the same structure as the first, at '/Sections/1/Y'"
+    );
+
+    let mut escaped = chunk(5);
+    *escaped.pointer_mut("/a~1b").unwrap() = Value::Byte(1);
+    assert_eq!(
+        err(&[chunk(1), escaped]),
+        "value 1 does not have the same structure as the first, at '/a~1b'"
+    );
+}
+
+#[test]
+fn columnar_rejects_corrupt_blobs() {
+    let bytes = encode(&[chunk(1), chunk(2)]).unwrap().into_bytes();
+
+    for len in 0..bytes.len() {
+        let truncated = ColumnarBlob::from_bytes(bytes[..len].to_vec());
+        assert!(decode(&truncated).is_err(), "truncated to {}", len);
+    }
+
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert!(decode(&ColumnarBlob::from_bytes(trailing)).is_err());
+
+    // One value too few for the data in the columns.
+    let mut fewer = bytes;
+    fewer[3] = 1;
+    assert!(decode(&ColumnarBlob::from_bytes(fewer)).is_err());
+}
+
+#[test]
+fn columnar_values_without_leaves() {
+    // One such value round trips.
+    let empty = [nbt!({"a": {}, "b": []})];
+    assert_eq!(decode(&encode(&empty).unwrap()).unwrap(), empty);
+
+    // More than one has nothing in the columns to bound the count.
+    assert!(encode(&[nbt!({}), nbt!({})]).is_err());
+
+    // 0x10000000 empty compounds in 9 bytes.
+    let blob = ColumnarBlob::from_bytes(vec![0x10, 0, 0, 0, 10, 0, 0, 0, 0]);
+    let err = decode(&blob).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "columnar data has more than one value with no numbers, strings or arrays"
+    );
+}
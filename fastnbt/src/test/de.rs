@@ -4,10 +4,12 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     borrow,
+    de::{ArrayDeserializer, BulkDeserializer, Deserializer, NbtReader, WarningKind},
     error::{Error, Result},
-    from_bytes, from_bytes_with_opts, from_reader, nbt,
-    test::builder::Builder,
-    to_bytes, ByteArray, DeOpts, IntArray, LongArray, Tag, Value,
+    from_bytes, from_bytes_in_place, from_bytes_with_opts, from_length_prefixed_reader,
+    from_reader, nbt,
+    test::{builder::Builder, OneByteReader},
+    to_bytes, to_length_prefixed_bytes, ByteArray, DeOpts, IntArray, LongArray, Tag, Value,
 };
 
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
@@ -1489,6 +1491,82 @@ fn ints_to_bool() {
     )
 }
 
+#[test]
+fn field_aliases() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct BlockEntity {
+        id: String,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Chunk {
+        #[serde(rename = "xPos", alias = "XPos")]
+        x_pos: i32,
+        #[serde(alias = "TileEntities")]
+        block_entities: Vec<BlockEntity>,
+    }
+
+    let chunk = |x: &str, entities: &str| {
+        Builder::new()
+            .start_compound("")
+            .int(x, 1)
+            .start_list(entities, Tag::Compound, 1)
+            .string("id", "chest")
+            .end_anon_compound()
+            .end_compound()
+            .build()
+    };
+    let expected = Chunk {
+        x_pos: 1,
+        block_entities: vec![BlockEntity { id: "chest".into() }],
+    };
+
+    let new = chunk("xPos", "block_entities");
+    let old = chunk("XPos", "TileEntities");
+    assert_eq!(from_all::<Chunk>(&new), expected);
+    assert_eq!(from_all::<Chunk>(&old), expected);
+
+    // Keys are read differently in lenient mode and with warnings on.
+    let (v, skipped) = crate::from_bytes_lenient::<Chunk>(&old).unwrap();
+    assert_eq!(v, expected);
+    assert!(skipped.is_empty());
+
+    let mut warnings = Vec::new();
+    let mut de = Deserializer::from_bytes(&old, DeOpts::new()).with_warnings(&mut warnings);
+    assert_eq!(Chunk::deserialize(&mut de).unwrap(), expected);
+    assert!(warnings.is_empty());
+}
+
+#[test]
+fn ints_to_float() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct V {
+        a: f64,
+        b: f32,
+        c: f64,
+    }
+
+    let payload = Builder::new()
+        .start_compound("")
+        .int("a", 5)
+        .byte("b", -3)
+        .long("c", i64::MAX)
+        .end_compound()
+        .build();
+
+    let expected = V {
+        a: 5.0,
+        b: -3.0,
+        c: i64::MAX as f64,
+    };
+    assert_eq!(from_all::<V>(&payload), expected);
+
+    // Nothing needs skipping in lenient mode either.
+    let (v, skipped) = crate::from_bytes_lenient::<V>(&payload).unwrap();
+    assert_eq!(v, expected);
+    assert!(skipped.is_empty());
+}
+
 #[test]
 fn direct_to_non_compound() {
     // Ensure that only compounds can be deserialized, not raw values inside
@@ -1656,3 +1734,1807 @@ fn negative_seq_lens() {
 
     assert!(from_bytes::<Value>(&payload).is_err());
 }
+
+fn custom_int_handler() -> DeOpts {
+    use byteorder::{BigEndian, ReadBytesExt};
+
+    DeOpts::new().tag_handler(|id: u8, reader: &mut dyn std::io::Read| match id {
+        100 => Ok(Value::Int(reader.read_i32::<BigEndian>()?)),
+        _ => Err(Error::invalid_tag(id)),
+    })
+}
+
+#[test]
+fn custom_tag_with_handler() {
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct V {
+        custom: i32,
+        after: i8,
+    }
+
+    let payload = Builder::new()
+        .start_compound("")
+        .raw_bytes(&[100])
+        .name("custom")
+        .int_payload(42)
+        .byte("after", 1)
+        .end_compound()
+        .build();
+
+    let v: V = from_bytes_with_opts(&payload, custom_int_handler()).unwrap();
+    assert_eq!(
+        v,
+        V {
+            custom: 42,
+            after: 1
+        }
+    );
+
+    let v: Value = from_bytes_with_opts(&payload, custom_int_handler()).unwrap();
+    assert_eq!(v, nbt!({"custom": 42, "after": 1i8}));
+}
+
+#[test]
+fn custom_tag_handler_errors() {
+    let payload = Builder::new()
+        .start_compound("")
+        .raw_bytes(&[100])
+        .name("custom")
+        .int_payload(42)
+        .end_compound()
+        .build();
+    let payload_start = payload.len() - 5;
+
+    // Running out of input within the payload.
+    let err = from_bytes_with_opts::<Value>(&payload[..payload_start + 2], custom_int_handler())
+        .unwrap_err();
+    assert_eq!(err, Error::unexpected_eof());
+
+    // Other errors from the input reach the handler's caller unchanged.
+    let opts = custom_int_handler().max_input_bytes(payload_start + 2);
+    let mut de = Deserializer::from_reader(payload.as_slice(), opts);
+    let err = Value::deserialize(&mut de).unwrap_err();
+    assert!(err.to_string().contains("maximum size"), "{}", err);
+}
+
+#[test]
+fn custom_tag_in_list() {
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct V {
+        list: Vec<i32>,
+    }
+
+    let payload = Builder::new()
+        .start_compound("")
+        .tag(Tag::List)
+        .name("list")
+        .raw_bytes(&[100])
+        .int_payload(2)
+        .int_payload(1)
+        .int_payload(2)
+        .end_compound()
+        .build();
+
+    let v: V = from_bytes_with_opts(&payload, custom_int_handler()).unwrap();
+    assert_eq!(v.list, [1, 2]);
+}
+
+#[test]
+fn custom_tag_ignored() {
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct V {
+        after: i8,
+    }
+
+    let payload = Builder::new()
+        .start_compound("")
+        .start_compound("inner")
+        .raw_bytes(&[100])
+        .name("custom")
+        .int_payload(42)
+        .end_compound()
+        .byte("after", 1)
+        .end_compound()
+        .build();
+
+    let v: V = from_bytes_with_opts(&payload, custom_int_handler()).unwrap();
+    assert_eq!(v, V { after: 1 });
+}
+
+#[test]
+fn custom_tag_without_handler() {
+    let payload = Builder::new()
+        .start_compound("")
+        .raw_bytes(&[100])
+        .name("custom")
+        .int_payload(42)
+        .end_compound()
+        .build();
+
+    assert!(from_bytes::<Value>(&payload).is_err());
+}
+
+#[test]
+fn in_place_reuses_allocation() {
+    use serde::de::{DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor};
+
+    #[derive(Debug, PartialEq)]
+    struct Pooled {
+        data: Vec<i32>,
+    }
+
+    struct DataSeed<'a>(&'a mut Vec<i32>);
+
+    impl<'de, 'a> DeserializeSeed<'de> for DataSeed<'a> {
+        type Value = ();
+
+        fn deserialize<D>(self, deserializer: D) -> std::result::Result<(), D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            deserializer.deserialize_seq(self)
+        }
+    }
+
+    impl<'de, 'a> Visitor<'de> for DataSeed<'a> {
+        type Value = ();
+
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            f.write_str("list of int")
+        }
+
+        fn visit_seq<A>(self, mut seq: A) -> std::result::Result<(), A::Error>
+        where
+            A: SeqAccess<'de>,
+        {
+            self.0.clear();
+            while let Some(el) = seq.next_element()? {
+                self.0.push(el);
+            }
+            Ok(())
+        }
+    }
+
+    struct PooledVisitor<'a>(&'a mut Pooled);
+
+    impl<'de, 'a> Visitor<'de> for PooledVisitor<'a> {
+        type Value = ();
+
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            f.write_str("compound")
+        }
+
+        fn visit_map<A>(self, mut map: A) -> std::result::Result<(), A::Error>
+        where
+            A: MapAccess<'de>,
+        {
+            while let Some(key) = map.next_key::<String>()? {
+                if key == "data" {
+                    map.next_value_seed(DataSeed(&mut self.0.data))?;
+                } else {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+            Ok(())
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Pooled {
+        fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            let mut place = Pooled { data: Vec::new() };
+            Self::deserialize_in_place(deserializer, &mut place)?;
+            Ok(place)
+        }
+
+        fn deserialize_in_place<D>(
+            deserializer: D,
+            place: &mut Self,
+        ) -> std::result::Result<(), D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            deserializer.deserialize_map(PooledVisitor(place))
+        }
+    }
+
+    let payload = Builder::new()
+        .start_compound("")
+        .start_list("data", Tag::Int, 3)
+        .int_payload(1)
+        .int_payload(2)
+        .int_payload(3)
+        .byte("other", 1)
+        .end_compound()
+        .build();
+
+    let mut pooled = Pooled {
+        data: Vec::with_capacity(64),
+    };
+    let ptr = pooled.data.as_ptr();
+
+    from_bytes_in_place(&payload, &mut pooled).unwrap();
+    assert_eq!(pooled.data, [1, 2, 3]);
+    assert_eq!(pooled.data.as_ptr(), ptr);
+    assert_eq!(pooled.data.capacity(), 64);
+
+    from_bytes_in_place(&payload, &mut pooled).unwrap();
+    assert_eq!(pooled.data, [1, 2, 3]);
+    assert_eq!(pooled.data.as_ptr(), ptr);
+}
+
+#[test]
+fn in_place_default_replaces() {
+    let payload = Builder::new()
+        .start_compound("")
+        .int("val", 7)
+        .end_compound()
+        .build();
+
+    let mut v = Single { val: 0 };
+    from_bytes_in_place(&payload, &mut v).unwrap();
+    assert_eq!(v, Single { val: 7 });
+}
+
+#[test]
+#[cfg(feature = "checksum")]
+fn trailing_crc32() {
+    use crate::de::Checksum;
+
+    let mut payload = Builder::new()
+        .start_compound("")
+        .int("val", 7)
+        .end_compound()
+        .build();
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(&payload);
+    let crc = hasher.finalize();
+    let opts = DeOpts::new().trailing_checksum(Checksum::Crc32);
+
+    let missing: Result<Single<i32>> = from_bytes_with_opts(&payload, opts.clone());
+    assert!(missing.is_err());
+
+    payload.extend_from_slice(&crc.to_be_bytes());
+    let v: Single<i32> = from_bytes_with_opts(&payload, opts.clone()).unwrap();
+    assert_eq!(v, Single { val: 7 });
+
+    let last = payload.len() - 1;
+    payload[last] ^= 1;
+    let invalid: Result<Single<i32>> = from_bytes_with_opts(&payload, opts);
+    assert!(invalid.is_err());
+
+    // Without the option the trailing data is ignored as usual.
+    let v: Single<i32> = from_bytes(&payload).unwrap();
+    assert_eq!(v, Single { val: 7 });
+}
+
+#[test]
+#[cfg(feature = "checksum")]
+fn trailing_crc32_every_deserializer() {
+    use crate::de::Checksum;
+
+    let mut payload = Builder::new()
+        .start_compound("")
+        .int("val", 7)
+        .end_compound()
+        .build();
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(&payload);
+    let valid = hasher.finalize().to_be_bytes();
+    let opts = DeOpts::new().trailing_checksum(Checksum::Crc32);
+
+    type Parse = fn(&[u8], DeOpts) -> Result<Single<i32>>;
+    let parsers: [(&str, Parse); 4] = [
+        ("slice", |data, opts| {
+            Single::deserialize(&mut Deserializer::from_bytes(data, opts))
+        }),
+        ("reader", |data, opts| {
+            Single::deserialize(&mut Deserializer::from_reader(data, opts))
+        }),
+        ("bulk", |data, opts| BulkDeserializer::new(opts).parse(data)),
+        ("bulk reader", |data, opts| {
+            BulkDeserializer::new(opts).parse_reader(data)
+        }),
+    ];
+
+    let len = payload.len();
+    for (name, parse) in parsers {
+        payload.truncate(len);
+        assert!(parse(&payload, opts.clone()).is_err(), "{}", name);
+
+        payload.extend_from_slice(&valid);
+        payload.push(0xff); // ignored
+        assert_eq!(parse(&payload, opts.clone()).unwrap().val, 7, "{}", name);
+
+        payload[len] ^= 1;
+        assert!(parse(&payload, opts.clone()).is_err(), "{}", name);
+    }
+}
+
+#[test]
+fn bulk_deserializer() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct V {
+        name: String,
+        data: IntArray,
+    }
+
+    let docs: Vec<_> = ["a", "bb", "ccc\u{1F600}"]
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            Builder::new()
+                .start_compound("")
+                .string("name", name)
+                .int_array("data", &[i as i32; 3])
+                .end_compound()
+                .build()
+        })
+        .collect();
+
+    let mut bulk = BulkDeserializer::new(DeOpts::new());
+    for doc in &docs {
+        let expected: V = from_bytes(doc).unwrap();
+        let v: V = bulk.parse(doc).unwrap();
+        assert_eq!(expected, v);
+
+        let v: V = bulk.parse_reader(doc.as_slice()).unwrap();
+        assert_eq!(expected, v);
+    }
+
+    // A failed parse should not affect later ones.
+    assert!(bulk.parse::<V>(&docs[0][..5]).is_err());
+    assert_eq!(
+        bulk.parse::<V>(&docs[1]).unwrap(),
+        from_bytes::<V>(&docs[1]).unwrap()
+    );
+}
+
+#[test]
+fn array_deserializer_external_len() {
+    let data: Vec<u8> = [1i64, -2, 3].iter().flat_map(|l| l.to_be_bytes()).collect();
+
+    let owned = LongArray::deserialize(ArrayDeserializer::new(Tag::LongArray, 2, &data)).unwrap();
+    assert_eq!(*owned, [1, -2]);
+
+    let borrowed =
+        borrow::LongArray::deserialize(ArrayDeserializer::new(Tag::LongArray, 3, &data)).unwrap();
+    assert!(borrowed.iter().eq([1, -2, 3]));
+
+    let value = Value::deserialize(ArrayDeserializer::new(Tag::IntArray, 2, &data)).unwrap();
+    assert_eq!(value, Value::IntArray(IntArray::new(vec![0, 1])));
+
+    assert!(LongArray::deserialize(ArrayDeserializer::new(Tag::LongArray, 4, &data)).is_err());
+    assert!(Value::deserialize(ArrayDeserializer::new(Tag::List, 1, &data)).is_err());
+}
+
+#[test]
+fn option_vec_absent_empty_and_full() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct V {
+        list: Option<Vec<i32>>,
+    }
+
+    let absent = Builder::new().start_compound("").end_compound().build();
+    let v: V = from_all(&absent);
+    assert_eq!(v.list, None);
+
+    for element_tag in [Tag::End, Tag::Int] {
+        let empty = Builder::new()
+            .start_compound("")
+            .start_list("list", element_tag, 0)
+            .end_compound()
+            .build();
+        let v: V = from_all(&empty);
+        assert_eq!(v.list, Some(vec![]));
+    }
+
+    let full = Builder::new()
+        .start_compound("")
+        .start_list("list", Tag::Int, 2)
+        .int_payload(1)
+        .int_payload(2)
+        .end_compound()
+        .build();
+    let v: V = from_all(&full);
+    assert_eq!(v.list, Some(vec![1, 2]));
+}
+
+#[test]
+fn ignored_fields_from_reader() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct V {
+        b: i32,
+    }
+
+    let payload = Builder::new()
+        .start_compound("")
+        .string("a", "ignored string")
+        .int_array("c", &[1, 2, 3])
+        .int("b", 7)
+        .byte_array("d", &[1, 2])
+        .end_compound()
+        .build();
+
+    let v: V = from_reader(payload.as_slice()).unwrap();
+    assert_eq!(v, V { b: 7 });
+
+    // Truncated in the middle of an ignored array.
+    let truncated = &payload[..payload.len() - 3];
+    assert!(from_reader::<_, V>(truncated).is_err());
+}
+
+#[test]
+fn from_reader_stops_at_end_of_data() {
+    let first = Builder::new()
+        .start_compound("")
+        .string("a", "first")
+        .end_compound()
+        .build();
+    let mut stream = first.clone();
+    stream.extend_from_slice(b"rest of the stream");
+
+    let mut reader = stream.as_slice();
+    let v: Value = from_reader(&mut reader).unwrap();
+    assert_eq!(v, nbt!({"a": "first"}));
+    assert_eq!(reader, b"rest of the stream");
+}
+
+#[test]
+fn nbt_reader_manual_parse() {
+    let payload = Builder::new()
+        .start_compound("root")
+        .byte("b", 1)
+        .short("s", 2)
+        .string("str", "hello")
+        .start_compound("skipped")
+        .start_list("l", Tag::Compound, 1)
+        .int("x", 1)
+        .end_compound()
+        .end_compound()
+        .start_list("list", Tag::Long, 2)
+        .long_payload(3)
+        .long_payload(-4)
+        .byte_array("ba", &[1, -1])
+        .int_array("ia", &[5, 6])
+        .long_array("la", &[7])
+        .float("f", 1.5)
+        .double("d", 2.5)
+        .end_compound()
+        .build();
+
+    let mut r = NbtReader::new(payload.as_slice());
+
+    assert_eq!(r.read_tag().unwrap(), Tag::Compound);
+    assert_eq!(r.read_name().unwrap(), "root");
+
+    assert_eq!(r.read_tag().unwrap(), Tag::Byte);
+    assert_eq!(r.read_name().unwrap(), "b");
+    assert_eq!(r.read_byte().unwrap(), 1);
+
+    assert_eq!(r.read_tag().unwrap(), Tag::Short);
+    assert_eq!(r.read_name().unwrap(), "s");
+    assert_eq!(r.read_short().unwrap(), 2);
+
+    assert_eq!(r.read_tag().unwrap(), Tag::String);
+    assert_eq!(r.read_name().unwrap(), "str");
+    assert_eq!(r.read_string().unwrap(), "hello");
+
+    assert_eq!(r.read_tag().unwrap(), Tag::Compound);
+    assert_eq!(r.read_name().unwrap(), "skipped");
+    r.skip_payload(Tag::Compound).unwrap();
+
+    assert_eq!(r.read_tag().unwrap(), Tag::List);
+    assert_eq!(r.read_name().unwrap(), "list");
+    assert_eq!(r.read_list_header().unwrap(), (Tag::Long, 2));
+    assert_eq!(r.read_long().unwrap(), 3);
+    assert_eq!(r.read_long().unwrap(), -4);
+
+    assert_eq!(r.read_tag().unwrap(), Tag::ByteArray);
+    assert_eq!(r.read_name().unwrap(), "ba");
+    assert_eq!(r.read_byte_array().unwrap(), [1, -1]);
+
+    assert_eq!(r.read_tag().unwrap(), Tag::IntArray);
+    assert_eq!(r.read_name().unwrap(), "ia");
+    assert_eq!(r.read_int_array().unwrap(), [5, 6]);
+
+    assert_eq!(r.read_tag().unwrap(), Tag::LongArray);
+    assert_eq!(r.read_name().unwrap(), "la");
+    assert_eq!(r.read_long_array().unwrap(), [7]);
+
+    assert_eq!(r.read_tag().unwrap(), Tag::Float);
+    assert_eq!(r.read_name().unwrap(), "f");
+    assert_eq!(r.read_float().unwrap(), 1.5);
+
+    assert_eq!(r.read_tag().unwrap(), Tag::Double);
+    assert_eq!(r.read_name().unwrap(), "d");
+    assert_eq!(r.read_double().unwrap(), 2.5);
+
+    assert_eq!(r.read_tag().unwrap(), Tag::End);
+    assert!(r.read_tag().is_err());
+}
+
+#[test]
+fn nbt_reader_rejects_end_list() {
+    let payload = Builder::new().start_anon_list(Tag::End, 1).build();
+    let mut r = NbtReader::new(payload.as_slice());
+    assert!(r.skip_payload(Tag::List).is_err());
+}
+
+#[test]
+fn length_prefixed_frames() {
+    let mut data = to_length_prefixed_bytes(&Single { val: 1 }).unwrap();
+    let second = to_length_prefixed_bytes(&Single { val: "two" }).unwrap();
+    assert_eq!(
+        u32::from_be_bytes(second[..4].try_into().unwrap()) as usize,
+        second.len() - 4
+    );
+    data.extend_from_slice(&second);
+
+    let mut reader = data.as_slice();
+    let first: Single<i32> = from_length_prefixed_reader(&mut reader).unwrap();
+    let second: Single<String> = from_length_prefixed_reader(&mut reader).unwrap();
+    assert_eq!(first, Single { val: 1 });
+    assert_eq!(second.val, "two");
+    assert!(reader.is_empty());
+
+    // Frame longer than the NBT inside it.
+    let mut padded = to_length_prefixed_bytes(&Single { val: 1 }).unwrap();
+    padded.push(0);
+    let len = (padded.len() - 4) as u32;
+    padded[..4].copy_from_slice(&len.to_be_bytes());
+    assert!(from_length_prefixed_reader::<_, Single<i32>>(padded.as_slice()).is_err());
+
+    // Truncated frame.
+    let data = to_length_prefixed_bytes(&Single { val: 1 }).unwrap();
+    let truncated = &data[..data.len() - 1];
+    assert!(from_length_prefixed_reader::<_, Single<i32>>(truncated).is_err());
+}
+
+#[test]
+fn lenient_skips_bad_fields() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Item {
+        id: String,
+        count: Option<i8>,
+    }
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct V {
+        name: String,
+        level: Option<i32>,
+        items: Vec<Item>,
+    }
+
+    let payload = Builder::new()
+        .start_compound("")
+        .string("name", "Steve")
+        .string("level", "not an int")
+        .start_list("items", Tag::Compound, 3)
+        .string("id", "stone")
+        .byte("count", 1)
+        .end_compound()
+        // Missing required field, so the whole element is skipped.
+        .byte("count", 2)
+        .end_compound()
+        .string("id", "dirt")
+        .start_compound("count")
+        .end_compound()
+        .end_compound()
+        .end_compound()
+        .build();
+
+    assert!(from_bytes::<V>(&payload).is_err());
+
+    let (v, skipped) = crate::from_bytes_lenient::<V>(&payload).unwrap();
+    assert_eq!(
+        v,
+        V {
+            name: "Steve".to_owned(),
+            level: None,
+            items: vec![
+                Item {
+                    id: "stone".to_owned(),
+                    count: Some(1)
+                },
+                Item {
+                    id: "dirt".to_owned(),
+                    count: None
+                },
+            ]
+        }
+    );
+
+    let mut paths: Vec<_> = skipped.iter().map(|s| s.path.as_str()).collect();
+    paths.sort_unstable();
+    assert_eq!(paths, ["/items/1", "/items/2/count", "/level"]);
+}
+
+#[test]
+fn lenient_root_failure() {
+    #[derive(Deserialize, Debug)]
+    struct V {
+        _required: i32,
+    }
+
+    let payload = Builder::new()
+        .start_compound("")
+        .string("_required", "oops")
+        .end_compound()
+        .build();
+
+    assert!(crate::from_bytes_lenient::<V>(&payload).is_err());
+}
+
+#[test]
+fn lenient_skips_many_values_in_one_pass() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct V {
+        ids: Vec<i32>,
+        counts: HashMap<String, i32>,
+    }
+
+    // Skipping is done in place, so this is quick despite every value failing.
+    let n = 100_000;
+    let payload = to_bytes(&Value::compound_from([
+        (
+            "ids",
+            Value::List((0..n).map(|i| Value::String(i.to_string())).collect()),
+        ),
+        (
+            "counts",
+            Value::compound_from([("good", Value::Int(1)), ("bad", Value::String("x".into()))]),
+        ),
+    ]))
+    .unwrap();
+
+    let (v, skipped) = crate::from_bytes_lenient::<V>(&payload).unwrap();
+    assert!(v.ids.is_empty());
+    assert_eq!(v.counts, HashMap::from([("good".to_owned(), 1)]));
+
+    // Compound order is not preserved by Value, so the two fields may come
+    // either way round.
+    let (ids, others): (Vec<_>, Vec<_>) = skipped.iter().partition(|s| s.path.starts_with("/ids/"));
+    assert_eq!(ids.len(), n);
+    assert!(ids
+        .iter()
+        .enumerate()
+        .all(|(i, s)| s.path == format!("/ids/{}", i)));
+    assert_eq!(others.len(), 1);
+    assert_eq!(others[0].path, "/counts/bad");
+}
+
+#[test]
+fn renamed_coordinate_fields_are_case_sensitive() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Pos {
+        #[serde(rename = "X")]
+        x: i32,
+        #[serde(rename = "Y")]
+        y: i32,
+        #[serde(rename = "Z")]
+        z: i32,
+    }
+
+    let payload = Builder::new()
+        .start_compound("")
+        .int("X", 1)
+        .int("Y", 2)
+        .int("Z", 3)
+        .end_compound()
+        .build();
+
+    let pos: Pos = from_bytes(&payload).unwrap();
+    assert_eq!(pos, Pos { x: 1, y: 2, z: 3 });
+
+    // Keys are matched exactly, so lowercase keys are unknown fields and the
+    // renamed fields are missing.
+    let payload = Builder::new()
+        .start_compound("")
+        .int("x", 1)
+        .int("y", 2)
+        .int("z", 3)
+        .end_compound()
+        .build();
+
+    assert!(from_bytes::<Pos>(&payload).is_err());
+}
+
+#[test]
+fn decode_array_payloads() {
+    let ints = [0, 1, -1, i32::MIN, i32::MAX];
+    let payload = Builder::new().int_array_payload(&ints).build();
+    assert_eq!(crate::decode_int_array(&payload), ints);
+
+    let longs = [0, 1, -1, i64::MIN, i64::MAX];
+    let payload = Builder::new().long_array_payload(&longs).build();
+    assert_eq!(crate::decode_long_array(&payload), longs);
+
+    // Partial trailing elements are ignored.
+    let payload = Builder::new()
+        .int_array_payload(&[7])
+        .raw_bytes(&[1, 2])
+        .build();
+    assert_eq!(crate::decode_int_array(&payload), [7]);
+    assert!(crate::decode_long_array(&payload).is_empty());
+}
+
+#[test]
+fn max_total_bytes() {
+    #[derive(Deserialize, Debug)]
+    struct V {
+        _a: String,
+        _b: String,
+        _c: LongArray,
+    }
+
+    let payload = Builder::new()
+        .start_compound("")
+        .string("_a", &"a".repeat(100))
+        .string("_b", &"b".repeat(100))
+        .long_array("_c", &[1; 10])
+        .end_compound()
+        .build();
+
+    // Keys and values: 2 + 100 + 2 + 100 + 2 + 80, plus 16 per entry.
+    let within = DeOpts::new().max_total_bytes(334);
+    assert!(from_bytes_with_opts::<V>(&payload, within.clone()).is_ok());
+    let mut de = Deserializer::from_reader(&payload[..], within);
+    assert!(V::deserialize(&mut de).is_ok());
+
+    let over = DeOpts::new().max_total_bytes(333);
+    let err = from_bytes_with_opts::<V>(&payload, over.clone()).unwrap_err();
+    assert!(err.to_string().contains("maximum total size"));
+    let mut de = Deserializer::from_reader(&payload[..], over);
+    assert!(V::deserialize(&mut de).is_err());
+}
+
+#[test]
+fn max_total_bytes_counts_small_values() {
+    // A megabyte of input that deserializes into many small values, which
+    // take up far more memory than the input.
+    let n = 1 << 20;
+    let mut builder = Builder::new()
+        .start_compound("")
+        .start_list("bytes", Tag::Byte, n);
+    for _ in 0..n {
+        builder = builder.byte_payload(0);
+    }
+    let payload = builder.end_compound().build();
+
+    let opts = DeOpts::new().max_total_bytes(1 << 20);
+    let err = from_bytes_with_opts::<Value>(&payload, opts.clone()).unwrap_err();
+    assert!(err.to_string().contains("maximum total size"));
+    let mut de = Deserializer::from_reader(&payload[..], opts);
+    assert!(Value::deserialize(&mut de).is_err());
+
+    let opts = DeOpts::new().max_total_bytes((n as usize + 1) * 16 + 5);
+    assert!(from_bytes_with_opts::<Value>(&payload, opts).is_ok());
+}
+
+#[test]
+fn std_sequence_collections() {
+    use std::collections::{LinkedList, VecDeque};
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct V {
+        deque: VecDeque<i32>,
+        linked: LinkedList<String>,
+    }
+
+    let payload = Builder::new()
+        .start_compound("")
+        .start_list("deque", Tag::Int, 3)
+        .int_payload(1)
+        .int_payload(2)
+        .int_payload(3)
+        .start_list("linked", Tag::String, 2)
+        .string_payload("a")
+        .string_payload("b")
+        .end_compound()
+        .build();
+
+    let v: V = from_bytes(&payload).unwrap();
+    assert_eq!(v.deque, [1, 2, 3]);
+    assert_eq!(v.linked.iter().collect::<Vec<_>>(), ["a", "b"]);
+    assert_eq!(to_bytes(&v).unwrap(), payload);
+
+    let negative = Builder::new()
+        .start_compound("")
+        .start_list("deque", Tag::Int, -1)
+        .end_compound()
+        .build();
+    assert!(from_bytes::<V>(&negative).is_err());
+}
+
+#[test]
+fn list_count_larger_than_input() {
+    #[derive(Deserialize, Debug)]
+    struct V {
+        _list: Vec<i32>,
+    }
+
+    let payload = Builder::new()
+        .start_compound("")
+        .start_list("_list", Tag::Int, 5)
+        .int_payload(1)
+        .int_payload(2)
+        .build();
+
+    let err = from_bytes::<V>(&payload).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "eof: list declared 5 elements but input ended in element 2"
+    );
+    assert_eq!(err, from_reader::<_, V>(&payload[..]).unwrap_err());
+
+    // Ignoring the list must not read past the input either.
+    #[derive(Deserialize, Debug)]
+    struct Empty {}
+
+    assert!(from_bytes::<Empty>(&payload).is_err());
+    assert!(from_reader::<_, Empty>(&payload[..]).is_err());
+}
+
+#[test]
+fn list_count_smaller_than_elements() {
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct V {
+        list: Vec<i8>,
+        after: i8,
+    }
+
+    // The declared count is respected, so the extra element is read as the
+    // start of the next field and fails as an invalid tag.
+    let payload = Builder::new()
+        .start_compound("")
+        .start_list("list", Tag::Byte, 1)
+        .byte_payload(1)
+        .byte_payload(99)
+        .byte("after", 2)
+        .end_compound()
+        .build();
+
+    assert!(from_bytes::<V>(&payload).is_err());
+}
+
+#[test]
+fn ignored_corrupt_lists_error() {
+    #[derive(Deserialize, Debug)]
+    struct Empty {}
+
+    let end_list = Builder::new()
+        .start_compound("")
+        .start_list("ignored", Tag::End, 3)
+        .end_compound()
+        .build();
+    assert!(from_bytes::<Empty>(&end_list).is_err());
+    assert!(from_reader::<_, Empty>(&end_list[..]).is_err());
+
+    let negative = Builder::new()
+        .start_compound("")
+        .start_list("ignored", Tag::Int, -1)
+        .end_compound()
+        .build();
+    assert!(from_bytes::<Empty>(&negative).is_err());
+}
+
+#[test]
+fn byte_as_bool_or_i8() {
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct AsBool {
+        off: bool,
+        on: bool,
+        two: bool,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct AsByte {
+        off: i8,
+        on: i8,
+        two: i8,
+    }
+
+    let payload = Builder::new()
+        .start_compound("")
+        .byte("off", 0)
+        .byte("on", 1)
+        .byte("two", 2)
+        .end_compound()
+        .build();
+
+    let expected_bool = AsBool {
+        off: false,
+        on: true,
+        two: true,
+    };
+    let expected_byte = AsByte {
+        off: 0,
+        on: 1,
+        two: 2,
+    };
+
+    assert_eq!(expected_bool, from_bytes(&payload).unwrap());
+    assert_eq!(expected_byte, from_bytes(&payload).unwrap());
+
+    let value: Value = from_bytes(&payload).unwrap();
+    assert_eq!(expected_bool, crate::from_value(&value).unwrap());
+    assert_eq!(expected_byte, crate::from_value(&value).unwrap());
+
+    // Serializing a bool writes the same bytes back.
+    #[derive(Serialize)]
+    struct Out {
+        off: bool,
+        on: bool,
+    }
+    let expected = Builder::new()
+        .start_compound("")
+        .byte("off", 0)
+        .byte("on", 1)
+        .end_compound()
+        .build();
+    assert_eq!(
+        expected,
+        to_bytes(&Out {
+            off: false,
+            on: true
+        })
+        .unwrap()
+    );
+}
+
+#[test]
+fn struct_fields_in_any_order() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Inner {
+        x: i32,
+        y: String,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct V {
+        first: i8,
+        second: Vec<i32>,
+        third: Inner,
+        fourth: String,
+    }
+
+    // Keys are in the reverse of declaration order, at both levels.
+    let payload = Builder::new()
+        .start_compound("")
+        .string("fourth", "last")
+        .start_compound("third")
+        .string("y", "why")
+        .int("x", 7)
+        .end_compound()
+        .start_list("second", Tag::Int, 2)
+        .int_payload(1)
+        .int_payload(2)
+        .byte("first", 1)
+        .end_compound()
+        .build();
+
+    let expected = V {
+        first: 1,
+        second: vec![1, 2],
+        third: Inner {
+            x: 7,
+            y: "why".to_string(),
+        },
+        fourth: "last".to_string(),
+    };
+
+    assert_eq!(expected, from_all(payload.as_slice()));
+
+    let value: Value = from_bytes(&payload).unwrap();
+    assert_eq!(expected, crate::from_value(&value).unwrap());
+}
+
+#[test]
+fn partial_reads() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct V {
+        s: i16,
+        i: i32,
+        l: i64,
+        f: f32,
+        d: f64,
+        name: String,
+        list: Vec<i64>,
+        ba: ByteArray,
+        ia: IntArray,
+        la: LongArray,
+    }
+
+    let payload = Builder::new()
+        .start_compound("")
+        .short("s", 0x0102)
+        .int("i", 0x01020304)
+        .long("l", 0x0102030405060708)
+        .float("f", 1.5)
+        .double("d", 2.5)
+        .string("name", "a longer string ☃")
+        .start_list("list", Tag::Long, 2)
+        .long_payload(-1)
+        .long_payload(i64::MAX)
+        .byte_array("ba", &[1, 2, 3])
+        .int_array("ia", &[i32::MIN, 5])
+        .long_array("la", &[7, i64::MIN])
+        .end_compound()
+        .build();
+
+    let expected: V = from_bytes(&payload).unwrap();
+    let actual: V = from_reader(OneByteReader(&payload)).unwrap();
+    assert_eq!(expected, actual);
+
+    let expected: Value = from_bytes(&payload).unwrap();
+    let actual: Value = from_reader(OneByteReader(&payload)).unwrap();
+    assert_eq!(expected, actual);
+
+    let framed = to_length_prefixed_bytes(&expected).unwrap();
+    let actual: Value = from_length_prefixed_reader(OneByteReader(&framed)).unwrap();
+    assert_eq!(expected, actual);
+
+    let mut r = NbtReader::new(OneByteReader(&payload));
+    assert_eq!(r.read_tag().unwrap(), Tag::Compound);
+    assert_eq!(r.read_name().unwrap(), "");
+    r.skip_payload(Tag::Compound).unwrap();
+    assert!(r.read_tag().is_err());
+}
+
+#[test]
+fn observer_sees_consumed_bytes() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct V {
+        name: String,
+        list: Vec<i16>,
+        longs: LongArray,
+    }
+
+    let payload = Builder::new()
+        .start_compound("")
+        .string("name", "snow ☃")
+        .start_list("list", Tag::Short, 2)
+        .short_payload(1)
+        .short_payload(-2)
+        .long_array("longs", &[3, 4])
+        // Skipped, but still consumed.
+        .start_compound("ignored")
+        .float("f", 1.5)
+        .double("d", 2.5)
+        .string("s", "☃")
+        .int_array("ia", &[5])
+        .byte_array("ba", &[6])
+        .end_compound()
+        .end_compound()
+        .build();
+
+    let mut input = payload.clone();
+    // Trailing data is not consumed.
+    input.extend_from_slice(&[1, 2, 3]);
+
+    let mut seen = Vec::new();
+    let mut de = Deserializer::from_bytes(&input, DeOpts::new())
+        .with_observer(|chunk: &[u8]| seen.extend_from_slice(chunk));
+    let v = V::deserialize(&mut de).unwrap();
+    drop(de);
+    assert_eq!(payload, seen);
+    assert_eq!("snow ☃", v.name);
+
+    let mut seen = Vec::new();
+    let mut de = Deserializer::from_reader(input.as_slice(), DeOpts::new())
+        .with_observer(|chunk: &[u8]| seen.extend_from_slice(chunk));
+    V::deserialize(&mut de).unwrap();
+    drop(de);
+    assert_eq!(payload, seen);
+
+    let mut seen = Vec::new();
+    let mut de = Deserializer::from_bytes(&input, DeOpts::new())
+        .with_observer(|chunk: &[u8]| seen.extend_from_slice(chunk));
+    Value::deserialize(&mut de).unwrap();
+    drop(de);
+    assert_eq!(payload, seen);
+}
+
+#[test]
+fn boxed_slices_and_strs() {
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct V {
+        ints: Box<[i32]>,
+        empty: Box<[i64]>,
+        name: Box<str>,
+        names: Box<[Box<str>]>,
+    }
+
+    let payload = Builder::new()
+        .start_compound("")
+        .start_list("ints", Tag::Int, 3)
+        .int_payload(1)
+        .int_payload(2)
+        .int_payload(3)
+        .start_list("empty", Tag::End, 0)
+        .string("name", "boxed ☃")
+        .start_list("names", Tag::String, 2)
+        .string_payload("a")
+        .string_payload("b")
+        .end_compound()
+        .build();
+
+    let v: V = from_all(&payload);
+    assert_eq!(
+        V {
+            ints: vec![1, 2, 3].into_boxed_slice(),
+            empty: Vec::new().into_boxed_slice(),
+            name: "boxed ☃".into(),
+            names: vec!["a".into(), "b".into()].into_boxed_slice(),
+        },
+        v
+    );
+    assert_eq!(payload, to_bytes(&v).unwrap());
+}
+
+#[test]
+fn zero_length_arrays() {
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Owned {
+        b: ByteArray,
+        i: IntArray,
+        l: LongArray,
+        after: i8,
+    }
+
+    #[derive(Deserialize)]
+    struct Borrowed<'a> {
+        #[serde(borrow)]
+        b: borrow::ByteArray<'a>,
+        #[serde(borrow)]
+        i: borrow::IntArray<'a>,
+        #[serde(borrow)]
+        l: borrow::LongArray<'a>,
+        after: i8,
+    }
+
+    let payload = Builder::new()
+        .start_compound("")
+        .byte_array("b", &[])
+        .int_array("i", &[])
+        .long_array("l", &[])
+        .byte("after", 7)
+        .end_compound()
+        .build();
+
+    let expected = Owned {
+        b: ByteArray::new(vec![]),
+        i: IntArray::new(vec![]),
+        l: LongArray::new(vec![]),
+        after: 7,
+    };
+
+    // No payload is read, so the following field is intact.
+    assert_eq!(expected, from_all(&payload));
+    assert_eq!(payload, to_bytes(&expected).unwrap());
+
+    let borrowed: Borrowed = from_bytes(&payload).unwrap();
+    assert_eq!(0, borrowed.b.iter().count());
+    assert_eq!(0, borrowed.i.iter().count());
+    assert_eq!(0, borrowed.l.iter().count());
+    assert_eq!(7, borrowed.after);
+
+    let value: Value = from_bytes(&payload).unwrap();
+    assert_eq!(
+        value,
+        nbt!({"b": [B;], "i": [I;], "l": [L;], "after": 7_i8})
+    );
+    let reserialized = to_bytes(&value).unwrap();
+    assert_eq!(value, from_bytes::<Value>(&reserialized).unwrap());
+    assert_eq!(value, from_reader::<_, Value>(payload.as_slice()).unwrap());
+
+    let de = |tag| ArrayDeserializer::new(tag, 0, &[]);
+    assert!(ByteArray::deserialize(de(Tag::ByteArray))
+        .unwrap()
+        .is_empty());
+    assert!(IntArray::deserialize(de(Tag::IntArray)).unwrap().is_empty());
+    assert!(LongArray::deserialize(de(Tag::LongArray))
+        .unwrap()
+        .is_empty());
+
+    let mut r = NbtReader::new(payload.as_slice());
+    assert_eq!(r.read_tag().unwrap(), Tag::Compound);
+    r.read_name().unwrap();
+    r.read_tag().unwrap();
+    r.read_name().unwrap();
+    assert!(r.read_byte_array().unwrap().is_empty());
+    r.read_tag().unwrap();
+    r.read_name().unwrap();
+    assert!(r.read_int_array().unwrap().is_empty());
+    r.read_tag().unwrap();
+    r.read_name().unwrap();
+    assert!(r.read_long_array().unwrap().is_empty());
+    assert_eq!(r.read_tag().unwrap(), Tag::Byte);
+}
+
+#[test]
+fn ignore_fixed_width_lists() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct V {
+        after: i8,
+    }
+
+    let payload = Builder::new()
+        .start_compound("")
+        .start_list("b", Tag::Byte, 2)
+        .byte_payload(1)
+        .byte_payload(2)
+        .start_list("s", Tag::Short, 1)
+        .short_payload(1)
+        .start_list("i", Tag::Int, 1)
+        .int_payload(1)
+        .start_list("f", Tag::Float, 1)
+        .float_payload(1.0)
+        .start_list("l", Tag::Long, 1)
+        .long_payload(1)
+        .start_list("d", Tag::Double, 2)
+        .double_payload(1.0)
+        .double_payload(2.0)
+        .byte("after", 7)
+        .end_compound()
+        .build();
+
+    let v: V = from_all(&payload);
+    assert_eq!(v, V { after: 7 });
+
+    // Truncated in the middle of an ignored list.
+    let truncated = Builder::new()
+        .start_compound("")
+        .start_list("l", Tag::Long, 2)
+        .long_payload(1)
+        .build();
+    assert!(from_bytes::<V>(&truncated).is_err());
+    assert!(from_reader::<_, V>(truncated.as_slice()).is_err());
+}
+
+#[test]
+fn nonzero_integers() {
+    use std::num::{NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroU32};
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct V {
+        b: NonZeroI8,
+        s: NonZeroI16,
+        i: NonZeroI32,
+        l: NonZeroI64,
+        u: NonZeroU32,
+        opt: Option<NonZeroI32>,
+    }
+
+    let payload = Builder::new()
+        .start_compound("")
+        .byte("b", -1)
+        .short("s", 2)
+        .int("i", 3)
+        .long("l", -4)
+        .int("u", 5)
+        .int("opt", 6)
+        .end_compound()
+        .build();
+
+    let v: V = from_all(&payload);
+    let expected = V {
+        b: NonZeroI8::new(-1).unwrap(),
+        s: NonZeroI16::new(2).unwrap(),
+        i: NonZeroI32::new(3).unwrap(),
+        l: NonZeroI64::new(-4).unwrap(),
+        u: NonZeroU32::new(5).unwrap(),
+        opt: NonZeroI32::new(6),
+    };
+    assert_eq!(v, expected);
+    assert_eq!(to_bytes(&expected).unwrap(), payload);
+
+    let zero = Builder::new()
+        .start_compound("")
+        .byte("b", 0)
+        .short("s", 2)
+        .int("i", 3)
+        .long("l", -4)
+        .int("u", 5)
+        .end_compound()
+        .build();
+    let err = from_bytes::<V>(&zero).unwrap_err();
+    assert!(err.to_string().contains("non-zero"), "{}", err);
+    assert!(from_reader::<_, V>(zero.as_slice()).is_err());
+}
+
+#[test]
+fn max_input_bytes() {
+    #[derive(Deserialize, Debug)]
+    struct V {
+        _a: String,
+        _b: ByteArray,
+    }
+
+    let payload = Builder::new()
+        .start_compound("")
+        .string("_a", "hello")
+        .byte_array("_b", &[1, 2, 3])
+        .end_compound()
+        .build();
+
+    let exact = DeOpts::new().max_input_bytes(payload.len());
+    let mut de = Deserializer::from_reader(payload.as_slice(), exact.clone());
+    assert!(V::deserialize(&mut de).is_ok());
+    // Slices are not limited.
+    let short = DeOpts::new().max_input_bytes(payload.len() - 1);
+    assert!(from_bytes_with_opts::<V>(&payload, short.clone()).is_ok());
+
+    let mut de = Deserializer::from_reader(payload.as_slice(), short.clone());
+    let err = V::deserialize(&mut de).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "input exceeded the maximum size of {} bytes",
+            payload.len() - 1
+        )
+    );
+
+    let mut bulk = BulkDeserializer::new(short);
+    assert!(bulk.parse_reader::<_, V>(payload.as_slice()).is_err());
+    let mut bulk = BulkDeserializer::new(exact);
+    assert!(bulk.parse_reader::<_, V>(payload.as_slice()).is_ok());
+
+    // A stream whose sizes claim far more data than the limit, and that never
+    // runs out.
+    let header = Builder::new()
+        .start_compound("")
+        .tag(Tag::ByteArray)
+        .name("huge")
+        .int_payload(i32::MAX)
+        .build();
+    for opts in [
+        DeOpts::new().max_input_bytes(4096),
+        DeOpts::new().max_input_bytes(4096).max_seq_len(usize::MAX),
+    ] {
+        let endless = std::io::Read::chain(header.as_slice(), std::io::repeat(1));
+        let mut de = Deserializer::from_reader(endless, opts);
+        let err = Value::deserialize(&mut de).unwrap_err();
+        assert!(err.to_string().contains("maximum size of 4096"), "{}", err);
+    }
+
+    let list_header = Builder::new()
+        .start_compound("")
+        .start_list("huge", Tag::Long, i32::MAX)
+        .build();
+    let endless = std::io::Read::chain(list_header.as_slice(), std::io::repeat(1));
+    let mut de = Deserializer::from_reader(endless, DeOpts::new().max_input_bytes(4096));
+    assert!(serde::de::IgnoredAny::deserialize(&mut de).is_err());
+}
+
+#[test]
+fn tuple_struct_round_trip_and_arity() {
+    #[derive(Deserialize, Serialize, Debug, PartialEq)]
+    struct Rgb(u8, u8, u8);
+
+    #[derive(Deserialize, Serialize, Debug, PartialEq)]
+    struct V {
+        val: Rgb,
+        after: i8,
+    }
+
+    let v = V {
+        val: Rgb(1, 2, 3),
+        after: 9,
+    };
+    let bytes = to_bytes(&v).unwrap();
+    assert_eq!(from_all::<V>(&bytes), v);
+    assert_eq!(
+        from_bytes::<Value>(&bytes).unwrap(),
+        nbt!({"val": [1_i8, 2_i8, 3_i8], "after": 9_i8})
+    );
+
+    for (n, msg) in [
+        (
+            2,
+            "invalid length 2, expected tuple struct Rgb with 3 elements",
+        ),
+        (4, "invalid length 4, expected fewer elements in list"),
+    ] {
+        let mut builder = Builder::new()
+            .start_compound("")
+            .start_list("val", Tag::Byte, n);
+        for i in 0..n {
+            builder = builder.byte_payload(i as i8);
+        }
+        let input = builder.byte("after", 9).end_compound().build();
+
+        assert_eq!(from_bytes::<V>(&input).unwrap_err().to_string(), msg);
+        assert_eq!(
+            from_reader::<_, V>(input.as_slice())
+                .unwrap_err()
+                .to_string(),
+            msg
+        );
+    }
+}
+
+#[test]
+fn next_value_span_extracts_subtree() {
+    let nested = Builder::new()
+        .start_compound("nested")
+        .start_list("x", Tag::Int, 2)
+        .int_payload(1)
+        .int_payload(2)
+        .string("y", "☃")
+        .start_compound("deeper")
+        .long_array("z", &[1, 2])
+        .end_compound()
+        .end_compound()
+        .build();
+
+    let mut data = Builder::new().start_compound("root").byte("a", 1).build();
+    let nested_start = data.len();
+    data.extend_from_slice(&nested);
+    data.extend(Builder::new().short("b", 2).end_compound().build());
+
+    let mut de = Deserializer::from_bytes(&data, DeOpts::new());
+    assert_eq!(de.next_name().unwrap().as_deref(), Some("root"));
+    de.enter_compound().unwrap();
+
+    assert_eq!(de.next_name().unwrap().as_deref(), Some("a"));
+    de.next_value_span().unwrap();
+
+    assert_eq!(de.next_name().unwrap().as_deref(), Some("nested"));
+    let span = de.next_value_span().unwrap();
+    assert_eq!(span, nested_start..nested_start + nested.len());
+    assert_eq!(&data[span], nested.as_slice());
+
+    assert_eq!(de.next_name().unwrap().as_deref(), Some("b"));
+    de.next_value_span().unwrap();
+    assert_eq!(de.next_name().unwrap(), None);
+    assert!(de.next_value_span().is_err());
+
+    // The extracted bytes are a valid entry to copy into another compound.
+    let mut copy = Builder::new().start_compound("").build();
+    copy.extend_from_slice(&data[nested_start..nested_start + nested.len()]);
+    copy.push(0);
+    let value: Value = from_bytes(&copy).unwrap();
+    assert_eq!(
+        value,
+        nbt!({"nested": {"x": [1, 2], "y": "☃", "deeper": {"z": [L; 1, 2]}}})
+    );
+
+    // The whole document is the root value.
+    let mut de = Deserializer::from_bytes(&data, DeOpts::new());
+    assert_eq!(de.next_value_span().unwrap(), 0..data.len());
+
+    let not_compound = Builder::new().byte("a", 1).build();
+    let mut de = Deserializer::from_bytes(&not_compound, DeOpts::new());
+    assert!(de.enter_compound().is_err());
+}
+
+#[test]
+fn peek_list_header_then_deserialize() {
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Item {
+        id: String,
+    }
+
+    let payload = Builder::new()
+        .start_compound("")
+        .start_list("items", Tag::Compound, 2)
+        .string("id", "a")
+        .end_anon_compound()
+        .string("id", "b")
+        .end_anon_compound()
+        .start_list("ids", Tag::Int, 3)
+        .int_payload(1)
+        .int_payload(2)
+        .int_payload(3)
+        .start_list("empty", Tag::End, 0)
+        .int("x", 1)
+        .end_compound()
+        .build();
+
+    let mut de = Deserializer::from_bytes(&payload, DeOpts::new());
+    de.enter_compound().unwrap();
+
+    // Peeking does not move, so it can be repeated.
+    assert_eq!(de.peek_list_header().unwrap(), (Tag::Compound, 2));
+    assert_eq!(de.peek_list_header().unwrap(), (Tag::Compound, 2));
+    let items: Vec<Item> = de.next_value().unwrap();
+    assert_eq!(items, [Item { id: "a".into() }, Item { id: "b".into() }]);
+
+    assert_eq!(de.peek_list_header().unwrap(), (Tag::Int, 3));
+    assert_eq!(de.next_value::<Vec<i32>>().unwrap(), [1, 2, 3]);
+
+    assert_eq!(de.peek_list_header().unwrap(), (Tag::End, 0));
+    assert!(de.next_value::<Vec<i32>>().unwrap().is_empty());
+
+    // Other values are an error, and are left to be read.
+    let err = de.peek_list_header().unwrap_err();
+    assert_eq!(err.to_string(), "expected list, found int");
+    assert_eq!(de.next_value::<i32>().unwrap(), 1);
+    assert!(de.peek_list_header().is_err());
+}
+
+#[test]
+fn list_elements_use_declared_tag() {
+    // Payloads that look like tags must still be read as the declared type,
+    // as list elements have no tag of their own.
+    let payload = Builder::new()
+        .start_compound("")
+        .start_list("bytes", Tag::Byte, 3)
+        .byte_payload(Tag::String as i8)
+        .byte_payload(Tag::Compound as i8)
+        .byte_payload(Tag::End as i8)
+        .start_list("shorts", Tag::Short, 2)
+        .short_payload(0x0a00)
+        .short_payload(0x0800)
+        .start_list("compounds", Tag::Compound, 2)
+        .int("a", 1)
+        .end_anon_compound()
+        .byte("b", 2)
+        .end_anon_compound()
+        .start_list("lists", Tag::List, 2)
+        .start_anon_list(Tag::Int, 1)
+        .int_payload(0x0a0a_0a0a)
+        .start_anon_list(Tag::String, 1)
+        .string_payload("\u{8}")
+        .int("after", 7)
+        .end_compound()
+        .build();
+
+    let v: Value = from_all(&payload);
+    assert_eq!(
+        v,
+        nbt!({
+            "bytes": [8_i8, 10_i8, 0_i8],
+            "shorts": [0x0a00_i16, 0x0800_i16],
+            "compounds": [{"a": 1}, {"b": 2_i8}],
+            "lists": [[0x0a0a_0a0a], ["\u{8}"]],
+            "after": 7,
+        })
+    );
+
+    // Skipping the lists must consume exactly the same bytes.
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct After {
+        after: i32,
+    }
+    assert_eq!(from_all::<After>(&payload), After { after: 7 });
+}
+
+#[test]
+fn key_decoder() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct V {
+        #[serde(rename = "café")]
+        cafe: i32,
+        name: String,
+    }
+
+    let latin1 =
+        |bytes: &[u8]| -> Result<String> { Ok(bytes.iter().map(|&b| b as char).collect()) };
+
+    // "café" in Latin-1, which is not valid Modified UTF-8.
+    let payload = Builder::new()
+        .start_compound("")
+        .tag(Tag::Int)
+        .raw_str_len(4)
+        .raw_bytes(b"caf\xe9")
+        .int_payload(1)
+        .string("name", "x")
+        .end_compound()
+        .build();
+
+    assert!(from_bytes::<V>(&payload).is_err());
+
+    let opts = || DeOpts::new().key_decoder(latin1);
+    let expected = V {
+        cafe: 1,
+        name: "x".to_string(),
+    };
+    assert_eq!(
+        from_bytes_with_opts::<V>(&payload, opts()).unwrap(),
+        expected
+    );
+
+    let mut de = Deserializer::from_reader(payload.as_slice(), opts());
+    assert_eq!(V::deserialize(&mut de).unwrap(), expected);
+
+    let v: Value = from_bytes_with_opts(&payload, opts()).unwrap();
+    assert_eq!(v, nbt!({"café": 1, "name": "x"}));
+
+    // Errors from the decoder are returned.
+    let failing = DeOpts::new().key_decoder(|_: &[u8]| Err(Error::bespoke("bad key")));
+    let err = from_bytes_with_opts::<Value>(&payload, failing).unwrap_err();
+    assert_eq!(err.to_string(), "bad key");
+}
+
+#[test]
+fn extract_field_skips_and_stops() {
+    let big: Vec<i64> = (0..100_000).collect();
+    let payload = Builder::new()
+        .start_compound("")
+        .long_array("Heightmap", &big)
+        .start_list("Entities", Tag::Compound, 1)
+        .string("id", "cow")
+        .end_anon_compound()
+        .int("DataVersion", 3120)
+        .string("name", "x")
+        .end_compound()
+        .build();
+
+    assert_eq!(
+        crate::extract_field::<i32>(&payload, "DataVersion").unwrap(),
+        Some(3120)
+    );
+    assert_eq!(
+        crate::extract_field::<&str>(&payload, "name").unwrap(),
+        Some("x")
+    );
+    assert_eq!(
+        crate::extract_field::<i32>(&payload, "Missing").unwrap(),
+        None
+    );
+
+    // Nothing after the field is read.
+    let len = payload.len();
+    let truncated = &payload[..len - 9];
+    assert_eq!(
+        crate::extract_field::<i32>(truncated, "DataVersion").unwrap(),
+        Some(3120)
+    );
+    assert!(crate::extract_field::<i32>(truncated, "Missing").is_err());
+
+    // The value must still match the requested type.
+    assert!(crate::extract_field::<String>(&payload, "DataVersion").is_err());
+}
+
+#[test]
+fn root_list() {
+    let payload = Builder::new()
+        .start_list("", Tag::Int, 2)
+        .int_payload(1)
+        .int_payload(2)
+        .build();
+    let v: Vec<Value> = from_all(&payload);
+    assert_eq!(v, [Value::Int(1), Value::Int(2)]);
+    let v: Vec<i32> = from_all(&payload);
+    assert_eq!(v, [1, 2]);
+    let v: Value = from_all(&payload);
+    assert_eq!(v, nbt!([1, 2]));
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Item {
+        id: String,
+    }
+    let payload = Builder::new()
+        .start_list("items", Tag::Compound, 2)
+        .string("id", "stone")
+        .end_anon_compound()
+        .string("id", "dirt")
+        .end_anon_compound()
+        .build();
+    let v: Vec<Item> = from_all(&payload);
+    assert_eq!(v[1].id, "dirt");
+}
+
+#[test]
+fn root_arrays() {
+    let payload = Builder::new().byte_array("", &[1, 2]).build();
+    assert_eq!(from_all::<ByteArray>(&payload), ByteArray::new(vec![1, 2]));
+
+    let payload = Builder::new().int_array("", &[1, 2]).build();
+    assert_eq!(from_all::<IntArray>(&payload), IntArray::new(vec![1, 2]));
+    assert_eq!(from_all::<Value>(&payload), nbt!([I; 1, 2]));
+
+    let payload = Builder::new().long_array("", &[1, 2]).build();
+    assert_eq!(from_all::<LongArray>(&payload), LongArray::new(vec![1, 2]));
+    let borrowed: borrow::LongArray = from_bytes(&payload).unwrap();
+    assert_eq!(borrowed.iter().collect::<Vec<_>>(), [1, 2]);
+}
+
+#[test]
+fn root_primitives() {
+    let payload = Builder::new().int("", 42).build();
+    assert_eq!(from_all::<i32>(&payload), 42);
+    assert_eq!(from_all::<Value>(&payload), Value::Int(42));
+    assert!(from_all::<bool>(&payload));
+
+    let payload = Builder::new().byte("", -1).build();
+    assert_eq!(from_all::<i8>(&payload), -1);
+    assert!(from_bytes::<u8>(&payload).is_err());
+
+    let payload = Builder::new().short("", 7).build();
+    assert_eq!(from_all::<i16>(&payload), 7);
+
+    let payload = Builder::new().long("", 1 << 40).build();
+    assert_eq!(from_all::<i64>(&payload), 1 << 40);
+
+    let payload = Builder::new().float("", 1.5).build();
+    assert_eq!(from_all::<f32>(&payload), 1.5);
+
+    let payload = Builder::new().double("", 2.5).build();
+    assert_eq!(from_all::<f64>(&payload), 2.5);
+
+    let payload = Builder::new().string("name", "hello").build();
+    assert_eq!(from_all::<String>(&payload), "hello");
+    assert_eq!(from_bytes::<&str>(&payload).unwrap(), "hello");
+
+    // The type must still match the root.
+    assert!(from_bytes::<Vec<i32>>(&payload).is_err());
+    assert!(from_bytes::<HashMap<String, i32>>(&payload).is_err());
+    assert!(from_bytes::<i32>(&[0]).is_err());
+    assert!(from_reader::<_, i32>(&[0][..]).is_err());
+}
+
+#[test]
+fn warnings_for_duplicate_keys() {
+    let payload = Builder::new()
+        .start_compound("")
+        .int("a", 1)
+        .start_compound("inner")
+        .byte("b", 1)
+        .byte("b", 2)
+        .end_compound()
+        .int("a", 3)
+        .end_compound()
+        .build();
+
+    let mut warnings = Vec::new();
+    let mut de = Deserializer::from_bytes(&payload, DeOpts::new()).with_warnings(&mut warnings);
+    let v = Value::deserialize(&mut de).unwrap();
+    assert_eq!(v, nbt!({"a": 3, "inner": {"b": 2_i8}}));
+
+    let kinds: Vec<_> = warnings.iter().map(|w| w.kind).collect();
+    assert_eq!(
+        kinds,
+        [WarningKind::DuplicateKey, WarningKind::DuplicateKey]
+    );
+    assert_eq!(warnings[0].message, "duplicate key 'b'");
+    assert_eq!(&payload[warnings[0].offset..][..4], [1, 0, 1, b'b']);
+    assert_eq!(warnings[1].message, "duplicate key 'a'");
+
+    // Offsets are the same when reading.
+    let mut from_reader = Vec::new();
+    let mut de = Deserializer::from_reader(payload.as_slice(), DeOpts::new())
+        .with_warnings(&mut from_reader);
+    HashMap::<String, Value>::deserialize(&mut de).unwrap();
+    assert_eq!(from_reader, warnings);
+}
+
+#[test]
+fn warnings_for_unusual_structure() {
+    let payload = Builder::new()
+        .start_compound("")
+        .start_list("empty", Tag::Int, 0)
+        .start_list("ends", Tag::End, 0)
+        .end_compound()
+        .build();
+
+    let mut warnings = Vec::new();
+    let mut de = Deserializer::from_bytes(&payload, DeOpts::new()).with_warnings(&mut warnings);
+    Value::deserialize(&mut de).unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].kind, WarningKind::TypedEmptyList);
+    assert_eq!(warnings[0].offset, 11);
+    assert_eq!(
+        warnings[0].to_string(),
+        "empty list with element tag int rather than end at offset 11"
+    );
+
+    let payload = Builder::new().int("", 1).build();
+    let mut warnings = Vec::new();
+    let mut de = Deserializer::from_bytes(&payload, DeOpts::new()).with_warnings(&mut warnings);
+    assert_eq!(i32::deserialize(&mut de).unwrap(), 1);
+    assert_eq!(warnings[0].kind, WarningKind::RootNotCompound);
+    assert_eq!(warnings[0].offset, 0);
+
+    // Nothing to warn about.
+    let payload = to_bytes(&nbt!({"a": [1], "b": {"a": 1}})).unwrap();
+    let mut warnings = Vec::new();
+    let mut de = Deserializer::from_bytes(&payload, DeOpts::new()).with_warnings(&mut warnings);
+    Value::deserialize(&mut de).unwrap();
+    assert!(warnings.is_empty());
+}
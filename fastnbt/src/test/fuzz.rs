@@ -1,6 +1,6 @@
 use std::{collections::HashMap, iter::FromIterator};
 
-use crate::{error::Result, from_bytes, test::builder::Builder, Tag, Value};
+use crate::{error::Result, from_bytes, from_reader, stream, test::builder::Builder, Tag, Value};
 
 /// Bugs found via cargo-fuzz.
 
@@ -60,3 +60,122 @@ fn compound_using_nbt_token_key() {
     ];
     assert!(from_bytes::<Value>(data).is_err());
 }
+
+#[test]
+fn stream_list_of_end() {
+    let input = Builder::new()
+        .start_compound("")
+        .start_list("a", Tag::End, 44288)
+        .end_compound()
+        .build();
+
+    let mut parser = stream::Parser::new(input.as_slice());
+    let err = loop {
+        if let Err(e) = parser.next() {
+            break e;
+        }
+    };
+    assert!(!err.is_eof());
+}
+
+#[test]
+fn stream_negative_array_len() {
+    for tag in [Tag::ByteArray, Tag::IntArray, Tag::LongArray] {
+        let input = Builder::new()
+            .start_compound("")
+            .tag(tag)
+            .name("a")
+            .int_payload(-1)
+            .end_compound()
+            .build();
+
+        let mut parser = stream::Parser::new(input.as_slice());
+        assert!(parser.next().is_ok());
+        assert!(parser.next().is_err());
+    }
+}
+
+#[test]
+fn reader_negative_array_len() {
+    for tag in [Tag::ByteArray, Tag::IntArray, Tag::LongArray] {
+        let input = Builder::new()
+            .start_compound("")
+            .tag(tag)
+            .name("a")
+            .int_payload(-1)
+            .end_compound()
+            .build();
+
+        assert!(from_reader::<_, Value>(input.as_slice()).is_err());
+    }
+}
+
+#[test]
+fn reader_parses_value_arrays() {
+    let input = Builder::new()
+        .start_compound("")
+        .byte_array("b", &[1, 2])
+        .int_array("i", &[3])
+        .long_array("l", &[4])
+        .end_compound()
+        .build();
+
+    let from_slice: Value = from_bytes(&input).unwrap();
+    let from_reader: Value = from_reader(input.as_slice()).unwrap();
+    assert_eq!(from_slice, from_reader);
+}
+
+#[test]
+fn fuzz_parse_regressions() {
+    let input = Builder::new()
+        .start_compound("")
+        .start_list("a", Tag::List, 1)
+        .start_anon_list(Tag::End, 44288)
+        .end_compound()
+        .build();
+    crate::fuzz_parse(&input);
+}
+
+/// A root compound containing `lists` lists nested in each other.
+fn nested_lists(lists: usize) -> Vec<u8> {
+    let mut input = vec![10, 0, 0, 9, 0, 1, b'a'];
+    for _ in 1..lists {
+        input.extend([9, 0, 0, 0, 1]);
+    }
+    input.extend([0, 0, 0, 0, 0]);
+    input.push(0);
+    input
+}
+
+#[test]
+fn deep_nesting_is_an_error() {
+    #[derive(serde::Deserialize, Debug)]
+    struct Empty {}
+
+    // Each level of nesting takes a few kilobytes of stack in a debug build,
+    // so use the stack size of a main thread rather than a test thread's.
+    let test = || {
+        // Nesting as deep as Minecraft allows is fine.
+        let input = nested_lists(511);
+        assert!(from_bytes::<Value>(&input).is_ok());
+        assert!(from_reader::<_, Value>(input.as_slice()).is_ok());
+
+        for lists in [512, 3000] {
+            let input = nested_lists(lists);
+            let err = from_bytes::<Value>(&input).unwrap_err();
+            assert_eq!(err.to_string(), "nesting exceeds the limit of 512");
+            assert!(from_reader::<_, Value>(input.as_slice()).is_err());
+
+            // Skipped values are limited too.
+            assert!(from_bytes::<Empty>(&input).is_err());
+            assert!(crate::infer_schema(&input).is_err());
+            crate::fuzz_parse(&input);
+        }
+    };
+    std::thread::Builder::new()
+        .stack_size(8 << 20)
+        .spawn(test)
+        .unwrap()
+        .join()
+        .unwrap();
+}
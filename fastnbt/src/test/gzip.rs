@@ -0,0 +1,37 @@
+use std::io::Write;
+
+use flate2::{write::GzEncoder, Compression};
+
+use crate::{
+    gzip::{from_gzip_reader, from_gzip_reader_all},
+    test::Single,
+    to_bytes,
+};
+
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+#[test]
+fn single_member() {
+    let data = gzip(&to_bytes(&Single { val: 1 }).unwrap());
+
+    let v: Single<i32> = from_gzip_reader(data.as_slice()).unwrap();
+    assert_eq!(v, Single { val: 1 });
+
+    let all: Vec<Single<i32>> = from_gzip_reader_all(data.as_slice()).unwrap();
+    assert_eq!(all, [Single { val: 1 }]);
+}
+
+#[test]
+fn multiple_members() {
+    let mut data = gzip(&to_bytes(&Single { val: 1 }).unwrap());
+    data.extend(gzip(&to_bytes(&Single { val: 2 }).unwrap()));
+
+    assert!(from_gzip_reader::<_, Single<i32>>(data.as_slice()).is_err());
+
+    let all: Vec<Single<i32>> = from_gzip_reader_all(data.as_slice()).unwrap();
+    assert_eq!(all, [Single { val: 1 }, Single { val: 2 }]);
+}
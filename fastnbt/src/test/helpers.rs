@@ -0,0 +1,383 @@
+use std::net::Ipv4Addr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{from_bytes, test::builder::Builder, to_bytes, DeOpts, Tag};
+
+#[test]
+fn bool_array_round_trip() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct V {
+        #[serde(with = "crate::helpers::bool_array")]
+        flags: Vec<bool>,
+    }
+
+    let v = V {
+        flags: vec![true, false, false, true],
+    };
+
+    let bs = to_bytes(&v).unwrap();
+    let expected = Builder::new()
+        .start_compound("")
+        .byte_array("flags", &[1, 0, 0, 1])
+        .end_compound()
+        .build();
+
+    assert_eq!(expected, bs);
+    assert_eq!(v, from_bytes(&bs).unwrap());
+}
+
+#[test]
+fn bool_array_nonzero_is_true() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct V {
+        #[serde(with = "crate::helpers::bool_array")]
+        flags: Vec<bool>,
+    }
+
+    let bs = Builder::new()
+        .start_compound("")
+        .byte_array("flags", &[0, 2, -1])
+        .end_compound()
+        .build();
+
+    let v: V = from_bytes(&bs).unwrap();
+    assert_eq!(v.flags, [false, true, true]);
+}
+
+#[test]
+fn bool_array_rejects_list() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct V {
+        #[serde(with = "crate::helpers::bool_array")]
+        _flags: Vec<bool>,
+    }
+
+    let bs = Builder::new()
+        .start_compound("")
+        .start_list("_flags", Tag::Byte, 1)
+        .byte_payload(1)
+        .end_compound()
+        .build();
+
+    assert!(from_bytes::<V>(&bs).is_err());
+}
+
+#[test]
+fn ipv4_as_int_round_trip() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct V {
+        #[serde(with = "crate::helpers::ipv4_as_int")]
+        ip: Ipv4Addr,
+    }
+
+    let v = V {
+        ip: Ipv4Addr::new(192, 168, 0, 1),
+    };
+
+    let bs = to_bytes(&v).unwrap();
+    let expected = Builder::new()
+        .start_compound("")
+        .int("ip", 0xC0A80001_u32 as i32)
+        .end_compound()
+        .build();
+
+    assert_eq!(expected, bs);
+    assert_eq!(v, from_bytes(&bs).unwrap());
+}
+
+#[test]
+fn byte_array_round_trip_large() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct V {
+        #[serde(with = "crate::helpers::byte_array")]
+        data: Vec<u8>,
+    }
+
+    let data: Vec<u8> = (0..1_000_000u32).map(|i| (i % 251) as u8).collect();
+    let signed: Vec<i8> = data.iter().map(|&b| b as i8).collect();
+    let v = V { data };
+
+    let bs = to_bytes(&v).unwrap();
+    let expected = Builder::new()
+        .start_compound("")
+        .byte_array("data", &signed)
+        .end_compound()
+        .build();
+
+    assert_eq!(expected, bs);
+    assert_eq!(v, from_bytes(&bs).unwrap());
+    assert_eq!(v, crate::from_reader(&bs[..]).unwrap());
+}
+
+#[test]
+fn byte_vec_is_list_by_default() {
+    #[derive(Serialize)]
+    struct V {
+        data: Vec<u8>,
+    }
+
+    let bs = to_bytes(&V { data: vec![1, 2] }).unwrap();
+    let expected = Builder::new()
+        .start_compound("")
+        .start_list("data", Tag::Byte, 2)
+        .byte_payload(1)
+        .byte_payload(2)
+        .end_compound()
+        .build();
+
+    assert_eq!(expected, bs);
+}
+
+#[test]
+fn one_or_many() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Item {
+        id: String,
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    #[serde(bound(serialize = "T: Serialize", deserialize = "T: Deserialize<'de>"))]
+    struct V<T> {
+        #[serde(with = "crate::helpers::one_or_many")]
+        data: Vec<T>,
+    }
+
+    let single = Builder::new()
+        .start_compound("")
+        .int("data", 7)
+        .end_compound()
+        .build();
+    let many = Builder::new()
+        .start_compound("")
+        .start_list("data", Tag::Int, 2)
+        .int_payload(7)
+        .int_payload(8)
+        .end_compound()
+        .build();
+
+    let v: V<i32> = from_bytes(&single).unwrap();
+    assert_eq!(vec![7], v.data);
+    let v: V<i64> = crate::from_reader(&single[..]).unwrap();
+    assert_eq!(vec![7], v.data);
+    let v: V<i32> = from_bytes(&many).unwrap();
+    assert_eq!(vec![7, 8], v.data);
+
+    // Serializing always writes a list.
+    assert_eq!(many, to_bytes(&V { data: vec![7, 8] }).unwrap());
+    let bs = to_bytes(&V { data: vec![7] }).unwrap();
+    let v: V<i32> = from_bytes(&bs).unwrap();
+    assert_eq!(vec![7], v.data);
+
+    // Strings and compounds work as single values too.
+    let strings = Builder::new()
+        .start_compound("")
+        .string("data", "stick")
+        .end_compound()
+        .build();
+    let v: V<String> = from_bytes(&strings).unwrap();
+    assert_eq!(vec!["stick".to_string()], v.data);
+    let v: V<String> = crate::from_reader(&strings[..]).unwrap();
+    assert_eq!(vec!["stick".to_string()], v.data);
+
+    let compound = Builder::new()
+        .start_compound("")
+        .start_compound("data")
+        .string("id", "stick")
+        .end_compound()
+        .end_compound()
+        .build();
+    let v: V<Item> = from_bytes(&compound).unwrap();
+    assert_eq!(
+        vec![Item {
+            id: "stick".to_string()
+        }],
+        v.data
+    );
+
+    // A single value of the wrong type is still an error.
+    assert!(from_bytes::<V<i32>>(&strings).is_err());
+}
+
+#[test]
+fn nested_nbt() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Inner {
+        name: String,
+        scores: Vec<i32>,
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Outer {
+        id: i32,
+        #[serde(with = "crate::helpers::nested_nbt")]
+        inner: Inner,
+        #[cfg(feature = "gzip")]
+        #[serde(with = "crate::helpers::nested_nbt::gzip")]
+        compressed: Inner,
+    }
+
+    let inner = || Inner {
+        name: "Steve".to_owned(),
+        scores: vec![1, 2, 3],
+    };
+    let outer = Outer {
+        id: 7,
+        inner: inner(),
+        #[cfg(feature = "gzip")]
+        compressed: inner(),
+    };
+
+    let bytes = to_bytes(&outer).unwrap();
+    assert_eq!(from_bytes::<Outer>(&bytes).unwrap(), outer);
+    assert_eq!(
+        crate::from_reader::<_, Outer>(bytes.as_slice()).unwrap(),
+        outer
+    );
+
+    // The inner document is stored as the bytes of a ByteArray.
+    let raw: crate::Value = from_bytes(&bytes).unwrap();
+    let stored = match raw.pointer("/inner") {
+        Some(crate::Value::ByteArray(arr)) => arr.iter().map(|b| *b as u8).collect::<Vec<_>>(),
+        other => panic!("expected byte array, got {:?}", other),
+    };
+    assert_eq!(stored, to_bytes(&inner()).unwrap());
+    #[cfg(feature = "gzip")]
+    assert!(matches!(
+        raw.pointer("/compressed"),
+        Some(crate::Value::ByteArray(arr)) if arr[..2] == [0x1f, 0x8b_u8 as i8]
+    ));
+
+    // Inner documents that are not valid NBT are an error.
+    let bad = Builder::new()
+        .start_compound("")
+        .int("id", 7)
+        .byte_array("inner", &[1, 2, 3])
+        .end_compound()
+        .build();
+    assert!(from_bytes::<Outer>(&bad).is_err());
+}
+
+#[test]
+fn fixed_byte_array_round_trip() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct V {
+        #[serde(with = "crate::helpers::fixed_byte_array")]
+        uuid: [u8; 16],
+    }
+
+    let v = V {
+        uuid: [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 254, 255],
+    };
+
+    let bs = to_bytes(&v).unwrap();
+    let expected = Builder::new()
+        .start_compound("")
+        .byte_array(
+            "uuid",
+            &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, -2, -1],
+        )
+        .end_compound()
+        .build();
+    assert_eq!(expected, bs);
+
+    assert_eq!(from_bytes::<V>(&bs).unwrap(), v);
+    assert_eq!(crate::from_reader::<_, V>(bs.as_slice()).unwrap(), v);
+
+    for len in [15, 17, 0] {
+        let wrong = Builder::new()
+            .start_compound("")
+            .byte_array("uuid", &vec![0; len])
+            .end_compound()
+            .build();
+        let err = from_bytes::<V>(&wrong).unwrap_err();
+        assert!(err.to_string().contains("16 bytes"), "{}", err);
+        assert!(crate::from_reader::<_, V>(wrong.as_slice()).is_err());
+    }
+
+    // A list of bytes is not accepted.
+    let list = Builder::new()
+        .start_compound("")
+        .start_list("uuid", Tag::Byte, 16)
+        .raw_bytes(&[0; 16])
+        .end_compound()
+        .build();
+    assert!(from_bytes::<V>(&list).is_err());
+}
+
+#[test]
+fn nested_nbt_outer_limits() {
+    #[derive(Deserialize, Debug)]
+    struct Inner {
+        _scores: Vec<i32>,
+    }
+
+    #[derive(Deserialize, Debug)]
+    struct Outer {
+        #[serde(with = "crate::helpers::nested_nbt")]
+        _inner: Inner,
+    }
+
+    let inner = Builder::new()
+        .start_compound("")
+        .start_list("_scores", Tag::Int, 3)
+        .int_payload(1)
+        .int_payload(2)
+        .int_payload(3)
+        .end_compound()
+        .build();
+    let bytes = Builder::new()
+        .start_compound("")
+        .byte_array(
+            "_inner",
+            &inner.iter().map(|b| *b as i8).collect::<Vec<_>>(),
+        )
+        .end_compound()
+        .build();
+
+    assert!(from_bytes::<Outer>(&bytes).is_ok());
+    let err = crate::from_bytes_with_opts::<Outer>(&bytes, DeOpts::new().max_seq_len(2));
+    assert!(err.is_err());
+
+    // The outer and inner documents share the budget: the outer counts the
+    // key and the inner's bytes, the inner its key and three elements.
+    let total = 16 + 6 + inner.len() + 16 + 7 + 3 * 16;
+    let within = DeOpts::new().max_total_bytes(total);
+    assert!(crate::from_bytes_with_opts::<Outer>(&bytes, within).is_ok());
+    let over = DeOpts::new().max_total_bytes(total - 1);
+    assert!(crate::from_bytes_with_opts::<Outer>(&bytes, over).is_err());
+
+    // Other deserializers are given the inner document's bytes.
+    let value: crate::Value = from_bytes(&bytes).unwrap();
+    assert!(crate::from_value::<Outer>(&value).is_ok());
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn nested_nbt_decompressed_size_is_limited() {
+    #[derive(Serialize, Deserialize, Debug)]
+    struct Inner {
+        data: crate::ByteArray,
+    }
+
+    #[derive(Serialize, Deserialize, Debug)]
+    struct Outer {
+        #[serde(with = "crate::helpers::nested_nbt::gzip")]
+        inner: Inner,
+    }
+
+    // Zeros compress well, so the inner document is far larger than the
+    // outer one.
+    let bytes = to_bytes(&Outer {
+        inner: Inner {
+            data: crate::ByteArray::new(vec![0; 100_000]),
+        },
+    })
+    .unwrap();
+    assert!(bytes.len() < 1000);
+
+    assert!(from_bytes::<Outer>(&bytes).is_ok());
+    let opts = DeOpts::new().max_input_bytes(10_000);
+    let err = crate::from_bytes_with_opts::<Outer>(&bytes, opts).unwrap_err();
+    assert!(err.to_string().contains("maximum size"), "{}", err);
+}
@@ -83,3 +83,64 @@ fn nbt() {
         Value::LongArray(LongArray::new(vec![1, 2, 3]))
     );
 }
+
+#[test]
+fn nbt_snbt_like_literals() {
+    let v = nbt!({
+        "health": 20.0f64,
+        "food": 1.5f32,
+        "count": 5i8,
+        "damage": 2i16,
+        "level": 3,
+        "time": 4i64,
+        "name": "steve",
+        "quoted key": 1,
+        "pos": [I; 1, 2, 3],
+        "bytes": [B; -1, 0],
+        "longs": [L; 1],
+        "list": [1, 2, 3],
+        "nested": {"inner": [{"x": 1i8}]},
+    });
+
+    let expected = Value::compound_from([
+        ("health", Value::Double(20.0)),
+        ("food", Value::Float(1.5)),
+        ("count", Value::Byte(5)),
+        ("damage", Value::Short(2)),
+        ("level", Value::Int(3)),
+        ("time", Value::Long(4)),
+        ("name", Value::String("steve".to_owned())),
+        ("quoted key", Value::Int(1)),
+        ("pos", Value::IntArray(IntArray::new(vec![1, 2, 3]))),
+        ("bytes", Value::ByteArray(ByteArray::new(vec![-1, 0]))),
+        ("longs", Value::LongArray(LongArray::new(vec![1]))),
+        (
+            "list",
+            Value::List(vec![Value::Int(1), Value::Int(2), Value::Int(3)]),
+        ),
+        (
+            "nested",
+            Value::compound_from([(
+                "inner",
+                Value::List(vec![Value::compound_from([("x", Value::Byte(1))])]),
+            )]),
+        ),
+    ]);
+    assert_eq!(v, expected);
+
+    // Parenthesized and other expression keys are evaluated.
+    let key = "level";
+    let suffix = 2;
+    assert_eq!(
+        nbt!({(key): 1, format!("k{}", suffix): 2}),
+        nbt!({"level": 1, "k2": 2})
+    );
+}
+
+#[test]
+fn identifier_keys_are_expressions() {
+    // As with serde_json's json!, a bare identifier key is a variable.
+    let name = "foo";
+    assert_eq!(nbt!({ name: 1 }), nbt!({"foo": 1}));
+    assert_eq!(nbt!({ name: 1, "name": 2 }), nbt!({"foo": 1, "name": 2}));
+}
@@ -117,3 +117,53 @@ fn avoiding_alloc_with_chunk() {
 
     let _chunk: Chunk = from_bytes(CHUNK_RAW).unwrap();
 }
+
+#[test]
+fn index_fields_only() {
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Chunk {
+        #[serde(rename = "DataVersion")]
+        data_version: i32,
+        #[serde(rename = "Level")]
+        level: Level,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Level {
+        #[serde(rename = "xPos")]
+        x_pos: i32,
+        #[serde(rename = "zPos")]
+        z_pos: i32,
+    }
+
+    let value: Value = from_bytes(CHUNK_RAW).unwrap();
+    let expected = Chunk {
+        data_version: value.pointer("/DataVersion").unwrap().as_i64().unwrap() as i32,
+        level: Level {
+            x_pos: value.pointer("/Level/xPos").unwrap().as_i64().unwrap() as i32,
+            z_pos: value.pointer("/Level/zPos").unwrap().as_i64().unwrap() as i32,
+        },
+    };
+
+    let chunk: Chunk = from_bytes(CHUNK_RAW).unwrap();
+    assert_eq!(chunk, expected);
+    let chunk: Chunk = crate::from_reader(CHUNK_RAW).unwrap();
+    assert_eq!(chunk, expected);
+}
+
+#[test]
+fn extract_single_field() {
+    let value: Value = from_bytes(CHUNK_RAW).unwrap();
+
+    let version: Option<i32> = crate::extract_field(CHUNK_RAW, "DataVersion").unwrap();
+    assert_eq!(
+        version.map(i64::from),
+        value.pointer("/DataVersion").unwrap().as_i64()
+    );
+
+    let level: Option<Value> = crate::extract_field(CHUNK_RAW, "Level").unwrap();
+    assert_eq!(level.as_ref(), value.pointer("/Level"));
+
+    let missing: Option<i32> = crate::extract_field(CHUNK_RAW, "Missing").unwrap();
+    assert_eq!(missing, None);
+}
@@ -11,12 +11,19 @@ mod de;
 mod value;
 
 pub mod builder;
+mod columnar;
 mod fuzz;
+#[cfg(feature = "gzip")]
+mod gzip;
+mod helpers;
 mod macros;
 mod minecraft_chunk;
 mod resources;
+mod schema;
 mod ser;
+mod snbt;
 mod stream;
+mod validate;
 
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 struct Single<T: Serialize> {
@@ -26,6 +33,22 @@ struct Single<T: Serialize> {
 #[derive(Serialize, Deserialize)]
 struct Wrap<T: Serialize>(T);
 
+/// A reader that returns at most one byte per call, like a slow socket.
+struct OneByteReader<'a>(&'a [u8]);
+
+impl std::io::Read for OneByteReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match (self.0.split_first(), buf.first_mut()) {
+            (Some((b, rest)), Some(out)) => {
+                *out = *b;
+                self.0 = rest;
+                Ok(1)
+            }
+            _ => Ok(0),
+        }
+    }
+}
+
 fn assert_try_into(tag: Tag) {
     assert_eq!(tag, (tag as u8).try_into().unwrap());
 }
@@ -0,0 +1,136 @@
+use std::collections::BTreeMap;
+
+use crate::{infer_schema, rust_type_for_tag, schema::SchemaNode, to_bytes, Tag};
+
+use super::builder::Builder;
+
+fn compound<const N: usize>(fields: [(&str, SchemaNode); N]) -> SchemaNode {
+    SchemaNode::Compound(
+        fields
+            .into_iter()
+            .map(|(k, v)| (k.to_owned(), v))
+            .collect::<BTreeMap<_, _>>(),
+    )
+}
+
+#[test]
+fn nested_structure() {
+    let data = to_bytes(&nbt!({
+        "DataVersion": 3120,
+        "Level": {
+            "Heights": [L; 1, 2, 3],
+            "Sections": [
+                {"Y": 0_i8, "Blocks": [B; 1, 2]},
+                {"Y": 1_i8, "Light": [B; 1, 2, 3, 4]},
+            ],
+            "Empty": [],
+        },
+    }))
+    .unwrap();
+
+    let expected = compound([
+        ("DataVersion", SchemaNode::Int),
+        (
+            "Level",
+            compound([
+                ("Heights", SchemaNode::LongArray { len: 3 }),
+                (
+                    "Sections",
+                    SchemaNode::List {
+                        element: Some(Box::new(compound([
+                            ("Y", SchemaNode::Byte),
+                            ("Blocks", SchemaNode::ByteArray { len: 2 }),
+                            ("Light", SchemaNode::ByteArray { len: 4 }),
+                        ]))),
+                        len: 2,
+                    },
+                ),
+                (
+                    "Empty",
+                    SchemaNode::List {
+                        element: None,
+                        len: 0,
+                    },
+                ),
+            ]),
+        ),
+    ]);
+
+    assert_eq!(infer_schema(&data).unwrap(), expected);
+}
+
+#[test]
+fn nested_lists_merge_lengths() {
+    let data = to_bytes(&nbt!({
+        "l": [[1_i16], [1_i16, 2_i16, 3_i16], []],
+    }))
+    .unwrap();
+
+    let expected = compound([(
+        "l",
+        SchemaNode::List {
+            element: Some(Box::new(SchemaNode::List {
+                element: Some(Box::new(SchemaNode::Short)),
+                len: 3,
+            })),
+            len: 3,
+        },
+    )]);
+
+    assert_eq!(infer_schema(&data).unwrap(), expected);
+}
+
+#[test]
+fn requires_root_compound() {
+    let data = Builder::new().int("a", 1).build();
+    assert!(infer_schema(&data).is_err());
+
+    let truncated = Builder::new().start_compound("").int("a", 1).build();
+    assert!(infer_schema(&truncated).is_err());
+}
+
+#[test]
+fn rust_types() {
+    let expected = [
+        (Tag::End, "()"),
+        (Tag::Byte, "i8"),
+        (Tag::Short, "i16"),
+        (Tag::Int, "i32"),
+        (Tag::Long, "i64"),
+        (Tag::Float, "f32"),
+        (Tag::Double, "f64"),
+        (Tag::ByteArray, "fastnbt::ByteArray"),
+        (Tag::String, "String"),
+        (Tag::List, "Vec<fastnbt::Value>"),
+        (
+            Tag::Compound,
+            "std::collections::HashMap<String, fastnbt::Value>",
+        ),
+        (Tag::IntArray, "fastnbt::IntArray"),
+        (Tag::LongArray, "fastnbt::LongArray"),
+    ];
+    for (tag, ty) in expected {
+        assert_eq!(rust_type_for_tag(tag), ty);
+    }
+
+    let data = to_bytes(&nbt!({
+        "ints": [1, 2],
+        "nested": [[1_i8], []],
+        "empty": [],
+        "items": [{"id": "stone"}],
+        "heights": [L; 1],
+    }))
+    .unwrap();
+    let fields = match infer_schema(&data).unwrap() {
+        SchemaNode::Compound(fields) => fields,
+        _ => unreachable!(),
+    };
+    assert_eq!(fields["ints"].rust_type(), "Vec<i32>");
+    assert_eq!(fields["nested"].rust_type(), "Vec<Vec<i8>>");
+    assert_eq!(fields["empty"].rust_type(), "Vec<fastnbt::Value>");
+    assert_eq!(
+        fields["items"].rust_type(),
+        "Vec<std::collections::HashMap<String, fastnbt::Value>>"
+    );
+    assert_eq!(fields["heights"].rust_type(), "fastnbt::LongArray");
+}
@@ -1,9 +1,9 @@
 use std::{collections::HashMap, iter::FromIterator};
 
 use crate::{
-    borrow, from_bytes,
+    borrow, from_bytes, from_reader,
     test::{resources::CHUNK_RAW_WITH_ENTITIES, Single, Wrap},
-    to_bytes, ByteArray, IntArray, LongArray, Tag, Value,
+    to_bytes, ByteArray, IntArray, ListElement, LongArray, NbtList, Raw, Tag, Value,
 };
 use serde::{ser::SerializeMap, Deserialize, Serialize};
 use serde_bytes::{ByteBuf, Bytes};
@@ -917,3 +917,368 @@ fn serialize_key_and_value() {
     let actual = to_bytes(&nbt!({"test":"value"})).unwrap();
     assert_eq!(actual, bs);
 }
+
+#[test]
+fn nbt_writer_reproduces_chunk() {
+    use crate::ser::NbtWriter;
+    use crate::stream::{self, Parser};
+
+    let mut parser = Parser::new(CHUNK_RAW_WITH_ENTITIES);
+    let mut writer = NbtWriter::new(Vec::new());
+    let mut depth = 0;
+
+    loop {
+        match parser.next().unwrap() {
+            stream::Value::Compound(name) => {
+                depth += 1;
+                writer.start_compound(&name.unwrap_or_default())
+            }
+            stream::Value::CompoundEnd => {
+                depth -= 1;
+                writer.end_compound()
+            }
+            stream::Value::List(name, tag, len) => {
+                writer.start_list(&name.unwrap_or_default(), tag, len as usize)
+            }
+            stream::Value::ListEnd => writer.end_list(),
+            stream::Value::Byte(name, v) => writer.write_byte(&name.unwrap_or_default(), v),
+            stream::Value::Short(name, v) => writer.write_short(&name.unwrap_or_default(), v),
+            stream::Value::Int(name, v) => writer.write_int(&name.unwrap_or_default(), v),
+            stream::Value::Long(name, v) => writer.write_long(&name.unwrap_or_default(), v),
+            stream::Value::Float(name, v) => writer.write_float(&name.unwrap_or_default(), v),
+            stream::Value::Double(name, v) => writer.write_double(&name.unwrap_or_default(), v),
+            stream::Value::String(name, v) => writer.write_string(&name.unwrap_or_default(), &v),
+            stream::Value::ByteArray(name, v) => {
+                writer.write_byte_array(&name.unwrap_or_default(), &v)
+            }
+            stream::Value::IntArray(name, v) => {
+                writer.write_int_array(&name.unwrap_or_default(), &v)
+            }
+            stream::Value::LongArray(name, v) => {
+                writer.write_long_array(&name.unwrap_or_default(), &v)
+            }
+        }
+        .unwrap();
+
+        if depth == 0 {
+            break;
+        }
+    }
+
+    assert_eq!(writer.finish().unwrap(), CHUNK_RAW_WITH_ENTITIES);
+}
+
+#[test]
+fn nbt_writer_validates_structure() {
+    use crate::ser::NbtWriter;
+
+    let mut writer = NbtWriter::new(Vec::new());
+    assert!(writer.write_int("a", 1).is_err());
+    writer.start_compound("").unwrap();
+    writer.start_list("l", Tag::Int, 2).unwrap();
+    assert!(writer.write_byte("", 1).is_err());
+    writer.write_int("", 1).unwrap();
+    assert!(writer.end_list().is_err());
+    assert!(writer.end_compound().is_err());
+    writer.write_int("", 2).unwrap();
+    assert!(writer.write_int("", 3).is_err());
+    writer.end_list().unwrap();
+    assert!(writer.start_list("e", Tag::End, 1).is_err());
+    writer.start_list("e", Tag::End, 0).unwrap();
+    writer.end_list().unwrap();
+    writer.end_compound().unwrap();
+    assert!(writer.start_compound("").is_err());
+
+    let expected = Builder::new()
+        .start_compound("")
+        .start_list("l", Tag::Int, 2)
+        .int_payload(1)
+        .int_payload(2)
+        .start_list("e", Tag::End, 0)
+        .end_compound()
+        .build();
+    assert_eq!(writer.finish().unwrap(), expected);
+
+    let mut unfinished = NbtWriter::new(Vec::new());
+    unfinished.start_compound("").unwrap();
+    assert!(unfinished.finish().is_err());
+}
+
+#[test]
+fn enum_variants_round_trip() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Shape {
+        Empty,
+        Circle(f32),
+        Point(i32, i32),
+        Nothing(),
+        Rect { w: i32, h: i32 },
+        Blank {},
+    }
+
+    for shape in [
+        Shape::Empty,
+        Shape::Circle(1.5),
+        Shape::Rect { w: 3, h: 4 },
+        Shape::Blank {},
+    ] {
+        let v = Single { val: shape };
+        let bs = to_bytes(&v).unwrap();
+        let back: Single<Shape> = from_bytes(&bs).unwrap();
+        assert_eq!(v.val, back.val);
+
+        // The Value representation is the same.
+        let value: Value = from_bytes(&bs).unwrap();
+        assert_eq!(value, crate::to_value(&v).unwrap());
+        let back: Single<Shape> = crate::from_value(&value).unwrap();
+        assert_eq!(v.val, back.val);
+    }
+
+    // Tuple variants are written as a bare list, which keeps the format of
+    // earlier versions but loses the variant name. The compound form that
+    // Value uses is read back.
+    for shape in [Shape::Point(1, -2), Shape::Nothing()] {
+        let v = Single { val: shape };
+        let value = crate::to_value(&v).unwrap();
+        let bs = to_bytes(&value).unwrap();
+        let back: Single<Shape> = from_bytes(&bs).unwrap();
+        assert_eq!(v.val, back.val);
+    }
+    let bs = to_bytes(&Single {
+        val: Shape::Point(1, -2),
+    })
+    .unwrap();
+    assert_eq!(from_bytes::<Value>(&bs).unwrap(), nbt!({"val": [1, -2]}));
+
+    let expected = Builder::new()
+        .start_compound("")
+        .start_compound("val")
+        .start_compound("Rect")
+        .int("w", 3)
+        .int("h", 4)
+        .end_compound()
+        .end_compound()
+        .end_compound()
+        .build();
+    let v = Single {
+        val: Shape::Rect { w: 3, h: 4 },
+    };
+    assert_eq!(expected, to_bytes(&v).unwrap());
+
+    let expected = Builder::new()
+        .start_compound("")
+        .start_compound("val")
+        .float("Circle", 1.5)
+        .end_compound()
+        .end_compound()
+        .build();
+    let v = Single {
+        val: Shape::Circle(1.5),
+    };
+    assert_eq!(expected, to_bytes(&v).unwrap());
+}
+
+#[test]
+fn enum_variant_compound_with_extra_keys() {
+    #[derive(Serialize, Deserialize, Debug)]
+    enum E {
+        A(i32),
+    }
+
+    let bs = Builder::new()
+        .start_compound("")
+        .start_compound("val")
+        .int("A", 1)
+        .int("B", 2)
+        .end_compound()
+        .end_compound()
+        .build();
+
+    assert!(from_bytes::<Single<E>>(&bs).is_err());
+}
+
+#[test]
+fn empty_nbt_list_keeps_element_tag() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Item {
+        id: String,
+    }
+
+    impl ListElement for Item {
+        const TAG: Tag = Tag::Compound;
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct V {
+        ints: NbtList<i32>,
+        items: NbtList<Item>,
+        nested: NbtList<NbtList<i8>>,
+        plain: Vec<i32>,
+    }
+
+    let v = V {
+        ints: NbtList::new(),
+        items: NbtList::new(),
+        nested: vec![NbtList::new()].into(),
+        plain: Vec::new(),
+    };
+    let bs = to_bytes(&v).unwrap();
+    let expected = Builder::new()
+        .start_compound("")
+        .start_list("ints", Tag::Int, 0)
+        .start_list("items", Tag::Compound, 0)
+        .start_list("nested", Tag::List, 1)
+        .start_anon_list(Tag::Byte, 0)
+        .start_list("plain", Tag::End, 0)
+        .end_compound()
+        .build();
+
+    assert_eq!(expected, bs);
+    assert_eq!(v, from_bytes(&bs).unwrap());
+}
+
+#[test]
+fn nbt_list_serializes_like_vec() {
+    let list: NbtList<i64> = vec![1, 2, 3].into();
+    let bs = to_bytes(&Single { val: list.clone() }).unwrap();
+
+    assert_eq!(
+        to_bytes(&Single {
+            val: vec![1i64, 2, 3]
+        })
+        .unwrap(),
+        bs
+    );
+    assert_eq!(Single { val: list }, from_bytes(&bs).unwrap());
+}
+
+#[test]
+fn borrowed_serializes_like_owned() {
+    #[derive(Serialize)]
+    struct Owned {
+        ints: Vec<i32>,
+        name: String,
+        nested: Single<i64>,
+        tags: Vec<String>,
+        biomes: IntArray,
+        items: NbtList<i16>,
+    }
+
+    #[derive(Serialize)]
+    struct Borrowed<'a> {
+        ints: &'a [i32],
+        name: &'a str,
+        nested: &'a Single<i64>,
+        tags: &'a [&'a str],
+        biomes: borrow::IntArray<'a>,
+        items: NbtList<&'a i16>,
+    }
+
+    let owned = Owned {
+        ints: vec![1, 2, 3],
+        name: "steve".to_string(),
+        nested: Single { val: 4 },
+        tags: vec!["a".to_string(), "b".to_string()],
+        biomes: IntArray::new(vec![5, 6]),
+        items: NbtList::new(),
+    };
+
+    let ints = [1, 2, 3];
+    let nested = Single { val: 4 };
+    let borrowed = Borrowed {
+        ints: &ints,
+        name: "steve",
+        nested: &nested,
+        tags: &["a", "b"],
+        biomes: borrow::IntArray::new(&[5, 6]),
+        items: NbtList::new(),
+    };
+
+    let expected = to_bytes(&owned).unwrap();
+    assert_eq!(to_bytes(&borrowed).unwrap(), expected);
+    assert_eq!(to_bytes(&&borrowed).unwrap(), expected);
+    assert_eq!(to_bytes(&Box::new(&owned)).unwrap(), expected);
+}
+
+#[test]
+fn sets_round_trip_as_lists() {
+    use std::collections::{BTreeSet, HashSet};
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Sets {
+        ids: HashSet<i32>,
+        names: BTreeSet<String>,
+        empty: BTreeSet<i8>,
+    }
+
+    let sets = Sets {
+        ids: HashSet::from_iter([3, 1, 2]),
+        names: BTreeSet::from_iter(["b".to_string(), "a".to_string()]),
+        empty: BTreeSet::new(),
+    };
+
+    let bytes = to_bytes(&sets).unwrap();
+    let v: Value = from_bytes(&bytes).unwrap();
+    assert_eq!(v.pointer("/names"), Some(&Value::list_from(["a", "b"])));
+    assert_eq!(v.pointer("/empty"), Some(&Value::List(vec![])));
+    match v.pointer("/ids") {
+        Some(Value::List(ids)) => {
+            let mut ids: Vec<_> = ids.iter().filter_map(Value::as_i64).collect();
+            ids.sort_unstable();
+            assert_eq!(ids, [1, 2, 3]);
+        }
+        other => panic!("expected list, got {:?}", other),
+    }
+
+    assert_eq!(from_bytes::<Sets>(&bytes).unwrap(), sets);
+
+    // Duplicates in the list collapse into one element.
+    let dupes = Builder::new()
+        .start_compound("")
+        .start_list("ids", Tag::Int, 3)
+        .int_payload(1)
+        .int_payload(1)
+        .int_payload(2)
+        .start_list("names", Tag::String, 0)
+        .start_list("empty", Tag::End, 0)
+        .end_compound()
+        .build();
+    let sets: Sets = from_bytes(&dupes).unwrap();
+    assert_eq!(sets.ids, HashSet::from_iter([1, 2]));
+}
+
+#[test]
+fn raw_field_reserializes_identically() {
+    #[derive(Serialize, Deserialize)]
+    struct Entity {
+        id: String,
+        data: Raw,
+        pos: Raw,
+    }
+
+    // An empty list of ints would become a list of end tags if it went
+    // through a Value.
+    let input = Builder::new()
+        .start_compound("")
+        .string("id", "cow")
+        .start_compound("data")
+        .start_list("empty", Tag::Int, 0)
+        .start_compound("nested")
+        .string("name", "Daisy")
+        .int_array("ids", &[1, 2])
+        .end_compound()
+        .end_compound()
+        .start_list("pos", Tag::Double, 2)
+        .double_payload(1.5)
+        .double_payload(-2.0)
+        .end_compound()
+        .build();
+
+    let entity: Entity = from_bytes(&input).unwrap();
+    assert_eq!(entity.data.tag(), Tag::Compound);
+    assert_eq!(entity.pos.tag(), Tag::List);
+    assert_eq!(to_bytes(&entity).unwrap(), input);
+
+    let entity: Entity = from_reader(input.as_slice()).unwrap();
+    assert_eq!(to_bytes(&entity).unwrap(), input);
+}
@@ -0,0 +1,327 @@
+use crate::{
+    from_snbt,
+    snbt::{
+        from_snbt_with_comments, to_snbt_string, to_snbt_string_with_comments,
+        to_snbt_string_with_opts, to_snbt_writer_with_opts, SnbtComments, SnbtOpts,
+    },
+    to_snbt_writer, Value,
+};
+
+fn nested() -> Value {
+    nbt!({
+        "DataVersion": 3120,
+        "Level": {
+            "xPos": -3,
+            "Status": "minecraft:full",
+            "Sections": [
+                {"Y": 0_i8, "BlockStates": [L; 1, -2, 3]},
+                {"Y": 1_i8, "BlockLight": [B; 0, 15], "Biomes": [I;]},
+            ],
+            "LastUpdate": 123456789_i64,
+            "InhabitedTime": 12_i16,
+            "Heights": [0.5_f32, -1.25_f32],
+            "Motion": [1.5, 64.0, -0.0],
+        },
+        "weird key": "quote \" and backslash \\ and 'single'",
+        "": [],
+        "Empty": {},
+        "Nested": [[1, 2], [], [3]],
+    })
+}
+
+#[test]
+fn writer_round_trip() {
+    let value = nested();
+
+    for opts in [SnbtOpts::new(), SnbtOpts::new().pretty(true)] {
+        let mut out = Vec::new();
+        to_snbt_writer_with_opts(&mut out, &value, opts.clone()).unwrap();
+        let snbt = String::from_utf8(out).unwrap();
+
+        assert_eq!(snbt, to_snbt_string_with_opts(&value, opts));
+        assert_eq!(from_snbt(&snbt).unwrap(), value);
+    }
+}
+
+#[test]
+fn compact_output() {
+    let mut out = Vec::new();
+    to_snbt_writer(
+        &mut out,
+        &nbt!({"b": [1_i8, 2_i8], "a": {"x": [I; 1, 2]}, "s": "hi"}),
+    )
+    .unwrap();
+
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        r#"{a:{x:[I;1,2]},b:[1b,2b],s:"hi"}"#
+    );
+}
+
+#[test]
+fn pretty_output() {
+    let value = nbt!({"a": [1, 2], "b": {}, "c": [B; 1, 2]});
+    assert_eq!(
+        to_snbt_string_with_opts(&value, SnbtOpts::new().pretty(true)),
+        "{\n  a: [\n    1,\n    2\n  ],\n  b: {},\n  c: [B; 1b, 2b]\n}"
+    );
+}
+
+#[test]
+fn parse_minecraft_syntax() {
+    let value = from_snbt(
+        "{ id : 'minecraft:diamond_sword', Count: 1B, Damage:0s, \
+         tag: {Unbreakable: true, Enchantments: [{id: \"sharpness\", lvl: 5s}]}, \
+         big: 5000000000, frac: .5, exp: 1.5e3f, neg: -2l, plus: +3 }",
+    )
+    .unwrap();
+
+    assert_eq!(
+        value,
+        nbt!({
+            "id": "minecraft:diamond_sword",
+            "Count": 1_i8,
+            "Damage": 0_i16,
+            "tag": {
+                "Unbreakable": 1_i8,
+                "Enchantments": [{"id": "sharpness", "lvl": 5_i16}],
+            },
+            // Out of range for an int, so it's a string like in Minecraft.
+            "big": "5000000000",
+            "frac": 0.5,
+            "exp": 1500.0_f32,
+            "neg": -2_i64,
+            "plus": 3,
+        })
+    );
+}
+
+#[test]
+fn parse_errors() {
+    for bad in [
+        "",
+        "{",
+        "{a:1,}",
+        "{a 1}",
+        "[1, 2b]",
+        "[B; 1, 300]",
+        "[I; 1, \"x\"]",
+        "\"unterminated",
+        "\"bad \\n escape\"",
+        "{a:1} trailing",
+    ] {
+        assert!(from_snbt(bad).is_err(), "{:?} should not parse", bad);
+    }
+
+    let err = from_snbt("{a: 1, b: [1, 2b]}").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid snbt: list elements must all be the same type at position 14"
+    );
+}
+
+#[test]
+fn string_version_matches_writer() {
+    let value = nested();
+    let mut out = Vec::new();
+    to_snbt_writer(&mut out, &value).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), to_snbt_string(&value));
+}
+
+#[test]
+fn comments_round_trip() {
+    let input = "\
+// Header for the root.
+{
+  // Two lines
+  // about a.
+  a: [
+    // First element.
+    {
+      x: 1
+      // Inside x's compound, at the end.
+    },
+    {}
+  ],
+  b: {
+    // Nothing in here.
+  },
+  \"c/d~\": [I; 1, 2]
+}
+// The end.";
+
+    let (value, comments) = from_snbt_with_comments(input).unwrap();
+    assert_eq!(value, from_snbt(input).unwrap());
+    assert_eq!(
+        value,
+        nbt!({"a": [{"x": 1}, {}], "b": {}, "c/d~": [I; 1, 2]})
+    );
+
+    assert_eq!(comments.leading(""), Some(" Header for the root."));
+    assert_eq!(comments.leading("/a"), Some(" Two lines\n about a."));
+    assert_eq!(comments.leading("/a/0"), Some(" First element."));
+    assert_eq!(
+        comments.trailing("/a/0"),
+        Some(" Inside x's compound, at the end.")
+    );
+    assert_eq!(comments.trailing("/b"), Some(" Nothing in here."));
+    assert_eq!(comments.footer(), Some(" The end."));
+
+    let pretty = to_snbt_string_with_comments(&value, &comments, SnbtOpts::new().pretty(true));
+    assert_eq!(pretty, input);
+
+    let compact = to_snbt_string_with_comments(&value, &comments, SnbtOpts::new());
+    let (reparsed, recomments) = from_snbt_with_comments(&compact).unwrap();
+    assert_eq!(reparsed, value);
+    assert_eq!(recomments, comments);
+
+    // Without comments the output is unchanged.
+    assert_eq!(
+        to_snbt_string_with_comments(&value, &SnbtComments::new(), SnbtOpts::new()),
+        to_snbt_string(&value)
+    );
+}
+
+#[test]
+fn comments_attach_to_values() {
+    let (_, comments) =
+        from_snbt_with_comments("{a: // after key\n 1, // after comma\n b: 2 // at end\n}")
+            .unwrap();
+    assert_eq!(comments.leading("/a"), Some(" after key"));
+    assert_eq!(comments.leading("/b"), Some(" after comma"));
+    assert_eq!(comments.trailing(""), Some(" at end"));
+
+    let mut comments = SnbtComments::new();
+    assert!(comments.is_empty());
+    comments.set_leading("/list/1", " second");
+    comments.set_trailing("/list", " done");
+    assert_eq!(
+        to_snbt_string_with_comments(&nbt!({"list": [1, 2]}), &comments, SnbtOpts::new()),
+        "{list:[1,// second\n2// done\n]}"
+    );
+
+    assert!(from_snbt_with_comments("[I; 1, // no\n 2]").is_err());
+    assert!(from_snbt_with_comments("[I; 1 // no\n]").is_err());
+    assert_eq!(
+        from_snbt("[I; 1, // skipped\n 2]").unwrap(),
+        nbt!([I; 1, 2])
+    );
+    assert_eq!(
+        from_snbt("{a: \"// not a comment\"}").unwrap(),
+        nbt!({"a": "// not a comment"})
+    );
+}
+
+#[test]
+fn floats_round_trip_exactly() {
+    let mut floats = vec![
+        0.0,
+        -0.0,
+        1.0,
+        -1.0,
+        0.1,
+        1e30,
+        1e-30,
+        f32::MIN,
+        f32::MAX,
+        f32::MIN_POSITIVE,
+        f32::EPSILON,
+        f32::from_bits(1),
+        f32::INFINITY,
+        f32::NEG_INFINITY,
+    ];
+    let mut doubles = vec![
+        0.0,
+        -0.0,
+        1.0,
+        -1.0,
+        0.1,
+        1e300,
+        1e-300,
+        f64::MIN,
+        f64::MAX,
+        f64::MIN_POSITIVE,
+        f64::EPSILON,
+        f64::from_bits(1),
+        f64::INFINITY,
+        f64::NEG_INFINITY,
+    ];
+
+    // A simple LCG, for a spread of bit patterns.
+    let mut state = 0x2545f4914f6cdd1d_u64;
+    for _ in 0..10_000 {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        floats.push(f32::from_bits((state >> 32) as u32));
+        doubles.push(f64::from_bits(state));
+    }
+
+    let value = Value::Compound(
+        [
+            (
+                "f".to_owned(),
+                Value::List(floats.iter().copied().map(Value::Float).collect()),
+            ),
+            (
+                "d".to_owned(),
+                Value::List(doubles.iter().copied().map(Value::Double).collect()),
+            ),
+        ]
+        .into_iter()
+        .collect(),
+    );
+
+    for opts in [SnbtOpts::new(), SnbtOpts::new().pretty(true)] {
+        let snbt = to_snbt_string_with_opts(&value, opts);
+        let parsed = from_snbt(&snbt).unwrap();
+
+        let parsed_floats = match parsed.pointer("/f").unwrap() {
+            Value::List(l) => l,
+            _ => unreachable!(),
+        };
+        for (v, p) in floats.iter().zip(parsed_floats) {
+            match p {
+                Value::Float(p) if v.is_nan() => assert!(p.is_nan()),
+                Value::Float(p) => assert_eq!(v.to_bits(), p.to_bits(), "{} became {}", v, p),
+                _ => panic!("{} became {:?}", v, p),
+            }
+        }
+
+        let parsed_doubles = match parsed.pointer("/d").unwrap() {
+            Value::List(l) => l,
+            _ => unreachable!(),
+        };
+        for (v, p) in doubles.iter().zip(parsed_doubles) {
+            match p {
+                Value::Double(p) if v.is_nan() => assert!(p.is_nan()),
+                Value::Double(p) => assert_eq!(v.to_bits(), p.to_bits(), "{} became {}", v, p),
+                _ => panic!("{} became {:?}", v, p),
+            }
+        }
+    }
+
+    assert_eq!(to_snbt_string(&nbt!([1.0, -0.0, 0.5])), "[1d,-0d,0.5d]");
+    assert_eq!(
+        to_snbt_string(&nbt!([f32::NAN, f32::INFINITY, f32::NEG_INFINITY])),
+        "[NaNf,Infinityf,-Infinityf]"
+    );
+    assert_eq!(
+        from_snbt("[NaN, Infinity]").unwrap(),
+        nbt!(["NaN", "Infinity"])
+    );
+}
+
+#[test]
+fn deep_nesting_is_an_error() {
+    let nested = |n: usize| format!("{}{}", "[".repeat(n), "]".repeat(n));
+    assert!(from_snbt(&nested(512)).is_ok());
+
+    let err = from_snbt(&nested(513)).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid snbt: nesting exceeds the limit of 512 at position 512"
+    );
+    assert!(from_snbt(&"[".repeat(100_000)).is_err());
+    assert!(from_snbt(&"{a:".repeat(100_000)).is_err());
+}
@@ -1,4 +1,4 @@
-use super::builder::Builder;
+use super::{builder::Builder, OneByteReader};
 use crate::stream::{ErrorKind, Name, Parser, Result, Value};
 use crate::Tag;
 
@@ -437,3 +437,35 @@ fn list_of_compounds() -> Result<()> {
     assert!(matches!(parser.next(), Err(e) if e.is_eof()));
     Ok(())
 }
+
+#[test]
+fn partial_reads() -> Result<()> {
+    let payload = Builder::new()
+        .start_compound("")
+        .int("i", 0x01020304)
+        .string("name", "a longer string")
+        .start_list("list", Tag::Long, 2)
+        .long_payload(-1)
+        .long_payload(i64::MAX)
+        .byte_array("ba", &[1, 2, 3])
+        .int_array("ia", &[i32::MIN, 5])
+        .long_array("la", &[7, i64::MIN])
+        .end_compound()
+        .build();
+
+    let mut whole = Parser::new(payload.as_slice());
+    let mut partial = Parser::new(OneByteReader(&payload));
+    loop {
+        let expected = whole.next();
+        let actual = partial.next();
+        match expected {
+            Ok(expected) => assert_eq!(expected, actual?),
+            Err(e) => {
+                assert!(e.is_eof());
+                assert!(actual.unwrap_err().is_eof());
+                break;
+            }
+        }
+    }
+    Ok(())
+}
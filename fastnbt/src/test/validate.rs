@@ -0,0 +1,132 @@
+use crate::{
+    to_bytes,
+    validate::{validate_strict, Limits, Violation},
+    Tag,
+};
+
+use super::builder::Builder;
+
+fn violation(data: &[u8]) -> Violation {
+    validate_strict(data, &Limits::new()).unwrap_err()
+}
+
+#[test]
+fn conformant_data_passes() {
+    let v = nbt!({
+        "DataVersion": 3120,
+        "Level": {
+            "Sections": [{"Y": 0_i8, "BlockStates": [L; 1, 2]}],
+            "Biomes": [I; 1, 2],
+            "Light": [B; 1],
+            "Name": "caf\u{e9} \u{1F600}",
+            "Empty": [],
+        },
+        "Pos": [1.0, 2.0_f64],
+        "Health": 1.0_f32,
+        "Time": 1_i64,
+        "Short": 1_i16,
+    });
+    let data = to_bytes(&v).unwrap();
+    assert_eq!(validate_strict(&data, &Limits::new()), Ok(()));
+}
+
+#[test]
+fn nonconformant_data_fails() {
+    // Minecraft writes empty lists with a real element tag.
+    let data = Builder::new()
+        .start_compound("")
+        .start_list("list", Tag::Int, 0)
+        .end_compound()
+        .build();
+    assert_eq!(
+        violation(&data),
+        Violation {
+            offset: 10,
+            description: "empty list with element tag int rather than end".to_string()
+        }
+    );
+
+    let data = Builder::new()
+        .start_compound("")
+        .int("a", 1)
+        .byte("a", 2)
+        .end_compound()
+        .build();
+    let v = violation(&data);
+    assert_eq!(v.offset, 3 + 8);
+    assert_eq!(v.to_string(), "duplicate key 'a' at offset 11");
+
+    // A zero byte in a name, which Modified UTF-8 encodes as C0 80.
+    let data = Builder::new()
+        .start_compound("")
+        .tag(Tag::Int)
+        .raw_str_len(2)
+        .raw_bytes(&[b'a', 0])
+        .int_payload(1)
+        .end_compound()
+        .build();
+    assert_eq!(violation(&data).description, "name is not Modified UTF-8");
+    assert_eq!(violation(&data).offset, 6);
+
+    // Four-byte UTF-8 rather than a surrogate pair.
+    let data = Builder::new()
+        .start_compound("")
+        .tag(Tag::String)
+        .name("s")
+        .raw_str_len(4)
+        .raw_bytes("\u{1F600}".as_bytes())
+        .end_compound()
+        .build();
+    assert_eq!(violation(&data).description, "string is not Modified UTF-8");
+
+    let data = Builder::new()
+        .start_compound("")
+        .start_list("list", Tag::End, 1)
+        .end_compound()
+        .build();
+    assert_eq!(violation(&data).description, "non-empty list of end tags");
+
+    let data = Builder::new().int("a", 1).build();
+    assert_eq!(violation(&data).description, "root tag is not a compound");
+    assert_eq!(violation(&data).offset, 0);
+
+    let mut data = Builder::new().start_compound("").end_compound().build();
+    data.push(0);
+    assert_eq!(violation(&data).description, "data after the root compound");
+
+    let data = Builder::new()
+        .start_compound("")
+        .tag(Tag::ByteArray)
+        .name("b")
+        .int_payload(-1)
+        .end_compound()
+        .build();
+    assert_eq!(violation(&data).description, "negative length -1");
+
+    let data = Builder::new().start_compound("").int("a", 1).build();
+    assert_eq!(violation(&data).description, "unexpected end of data");
+    assert_eq!(violation(&data).offset, data.len());
+
+    let mut data = Builder::new().start_compound("").build();
+    data.push(13);
+    assert_eq!(violation(&data).description, "invalid tag 13");
+}
+
+#[test]
+fn limits() {
+    let data = to_bytes(&nbt!({"a": {"b": [[1]]}, "arr": [I; 1, 2, 3]})).unwrap();
+
+    assert!(validate_strict(&data, &Limits::new().max_depth(4)).is_ok());
+    assert_eq!(
+        validate_strict(&data, &Limits::new().max_depth(3))
+            .unwrap_err()
+            .description,
+        "nesting exceeds the limit of 3"
+    );
+    assert_eq!(
+        validate_strict(&data, &Limits::new().max_len(2))
+            .unwrap_err()
+            .description,
+        "length 3 exceeds the limit of 2"
+    );
+}
@@ -144,3 +144,31 @@ fn no_root_compound() {
     );
     assert_eq!(Ok(vec![1, 2, 3, 4]), from_value(&nbt!([1, 2, 3, 4])));
 }
+
+#[test]
+fn owned_value() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct V {
+        string: String,
+        list: Vec<i16>,
+        ints: IntArray,
+        uuid: u128,
+    }
+
+    let val = V::deserialize(nbt!({
+        "string": "value",
+        "list": [1_i16, 2_i16],
+        "ints": [I; 1, 2, 3],
+        "uuid": u128::MAX,
+    }))
+    .unwrap();
+
+    let expected = V {
+        string: "value".to_string(),
+        list: vec![1, 2],
+        ints: IntArray::new(vec![1, 2, 3]),
+        uuid: u128::MAX,
+    };
+
+    assert_eq!(expected, val);
+}
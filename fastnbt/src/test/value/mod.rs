@@ -1,9 +1,11 @@
-mod ser;
 mod de;
+mod ser;
 
 use std::collections::HashMap;
 
-use crate::{from_bytes, to_bytes, Tag, Value};
+use serde::{Deserialize, Serialize};
+
+use crate::{from_bytes, to_bytes, to_bytes_with_capacity, value::PrettyOpts, Tag, Value};
 
 use super::builder::Builder;
 
@@ -216,3 +218,920 @@ fn distinguish_compound() {
     let v: Value = from_bytes(&input).unwrap();
     assert_contains!(v, "a", Value::Compound(_));
 }
+
+#[test]
+fn pointer() {
+    let mut v = nbt!({
+        "a": {
+            "list": [{"x": 1}, {"x": 2}],
+            "with/slash": 3,
+            "with~tilde": 4,
+        },
+        "arr": [I; 1, 2],
+    });
+
+    assert_eq!(v.pointer(""), Some(&v.clone()));
+    assert_eq!(v.pointer("/a/list/1/x"), Some(&Value::Int(2)));
+    assert_eq!(v.pointer("/a/with~1slash"), Some(&Value::Int(3)));
+    assert_eq!(v.pointer("/a/with~0tilde"), Some(&Value::Int(4)));
+    assert_eq!(v.pointer("/a/list/2"), None);
+    assert_eq!(v.pointer("/a/list/01"), None);
+    assert_eq!(v.pointer("/arr/0"), None);
+    assert_eq!(v.pointer("a"), None);
+
+    *v.pointer_mut("/a/list/0/x").unwrap() = Value::Int(10);
+    assert_eq!(v.pointer("/a/list/0/x"), Some(&Value::Int(10)));
+}
+
+#[test]
+fn path_exists() {
+    let v = nbt!({
+        "a": {
+            "list": [1, 2],
+        },
+    });
+
+    assert!(v.path_exists(""));
+    assert!(v.path_exists("/a"));
+    assert!(v.path_exists("/a/list/1"));
+    assert!(!v.path_exists("/a/list/2"));
+    assert!(!v.path_exists("/b"));
+}
+
+#[test]
+fn substitute() {
+    let template = nbt!({
+        "${key}": 1,
+        "whole": "${num}",
+        "embedded": "a ${name} b ${num} c ${missing}",
+        "unterminated": "x ${name",
+        "list": ["${name}", "${missing}"],
+        "nested": {"n": "${num}"},
+    });
+
+    let mut vars = HashMap::new();
+    vars.insert("key".to_string(), nbt!("renamed"));
+    vars.insert("name".to_string(), nbt!("steve"));
+    vars.insert("num".to_string(), nbt!(42_i16));
+
+    assert_eq!(
+        template.substitute(&vars),
+        nbt!({
+            "renamed": 1,
+            "whole": 42_i16,
+            "embedded": "a steve b ${num} c ${missing}",
+            "unterminated": "x ${name",
+            "list": ["steve", "${missing}"],
+            "nested": {"n": 42_i16},
+        })
+    );
+}
+
+#[test]
+fn empty_lists_normalized() {
+    let end = Builder::new()
+        .start_compound("")
+        .start_list("list", Tag::End, 0)
+        .end_compound()
+        .build();
+
+    let int = Builder::new()
+        .start_compound("")
+        .start_list("list", Tag::Int, 0)
+        .end_compound()
+        .build();
+
+    let from_end: Value = from_bytes(&end).unwrap();
+    let from_int: Value = from_bytes(&int).unwrap();
+    assert_eq!(from_end, from_int);
+    assert_eq!(to_bytes(&from_int).unwrap(), end);
+}
+
+#[test]
+fn pretty_truncates_depth() {
+    let v = nbt!({
+        "a": {
+            "b": {
+                "c": [1, 2, 3],
+            },
+            "list": [["deep"]],
+            "name": "x \"y\"",
+        },
+        "odd key": [B; 1, 2, 3],
+    });
+
+    let opts = PrettyOpts::new().max_depth(2);
+    assert_eq!(
+        v.to_pretty_string_with(&opts),
+        r#"{
+  a: {
+    b: {...},
+    list: [...],
+    name: "x \"y\"",
+  },
+  "odd key": [B; 1b, 2b, 3b],
+}"#
+    );
+
+    let opts = PrettyOpts::new().indent(1).max_array_elements(1);
+    assert_eq!(
+        v.to_pretty_string_with(&opts),
+        r#"{
+ a: {
+  b: {
+   c: [
+    1,
+    ... 2 more
+   ],
+  },
+  list: [
+   [
+    "deep",
+   ],
+  ],
+  name: "x \"y\"",
+ },
+ "odd key": [B; 1b, ... 2 more],
+}"#
+    );
+
+    assert_eq!(
+        v.to_pretty_string_with(&PrettyOpts::new().max_depth(0)),
+        "{...}"
+    );
+}
+
+#[test]
+fn named_root_round_trip() {
+    let input = Builder::new()
+        .start_compound("Data")
+        .int("a", 1)
+        .end_compound()
+        .build();
+
+    let (name, v) = crate::value::from_bytes_named(&input).unwrap();
+    assert_eq!(name, "Data");
+    assert_eq!(v, nbt!({"a": 1}));
+
+    let output = crate::value::to_bytes_named(&name, &v).unwrap();
+    assert_eq!(output, input);
+
+    let not_compound = Builder::new().int("a", 1).build();
+    assert!(crate::value::from_bytes_named(&not_compound).is_err());
+}
+
+#[test]
+fn try_get_and_index() {
+    let v = nbt!({"list": [1, 2, 3], "n": 1});
+
+    assert_eq!(v.try_get("list").unwrap().try_index(2).unwrap(), &nbt!(3));
+
+    let err = |r: crate::error::Result<&Value>| r.unwrap_err().to_string();
+    assert_eq!(err(v.try_get("missing")), "no key 'missing' in compound");
+    assert_eq!(
+        err(v.try_get("list").unwrap().try_index(5)),
+        "index 5 out of bounds (len 3)"
+    );
+    assert_eq!(
+        err(v.try_get("n").unwrap().try_get("x")),
+        "cannot get key 'x' of int, expected compound"
+    );
+    assert_eq!(err(v.try_index(0)), "cannot index compound, expected list");
+}
+
+#[test]
+fn memory_size() {
+    use std::mem::size_of;
+
+    assert_eq!(Value::Int(1).memory_size(), 0);
+    assert_eq!(
+        Value::LongArray(crate::LongArray::new(Vec::with_capacity(10))).memory_size(),
+        80
+    );
+
+    let list = Value::List(vec![Value::String("abc".to_owned()), Value::Int(1)]);
+    assert_eq!(list.memory_size(), 2 * size_of::<Value>() + 3);
+
+    let mut map = HashMap::new();
+    map.insert("key".to_owned(), list.clone());
+    let compound = Value::Compound(map);
+    let map_capacity = match &compound {
+        Value::Compound(map) => map.capacity(),
+        _ => unreachable!(),
+    };
+    assert_eq!(
+        compound.memory_size(),
+        map_capacity * (size_of::<(String, Value)>() + 1) + 3 + list.memory_size()
+    );
+}
+
+#[test]
+fn changed_paths() {
+    let before = nbt!({
+        "DataVersion": 3120,
+        "Player": {
+            "Health": 20.0_f32,
+            "Inventory": [
+                {"id": "minecraft:stone", "Count": 64_i8},
+                {"id": "minecraft:dirt", "Count": 1_i8},
+            ],
+            "Seen": [I; 1, 2],
+        },
+        "Removed": "gone",
+    });
+    let after = nbt!({
+        "DataVersion": 3120,
+        "Player": {
+            "Health": 20.0_f32,
+            "Inventory": [
+                {"id": "minecraft:stone", "Count": 63_i8},
+                {"id": "minecraft:dirt", "Count": 1_i8},
+                {"id": "minecraft:torch", "Count": 4_i8},
+            ],
+            "Seen": [I; 1, 3],
+        },
+        "Added": {"x": 1},
+    });
+
+    assert_eq!(
+        before.changed_paths(&after),
+        [
+            "Added",
+            "Player.Inventory.0.Count",
+            "Player.Inventory.2",
+            "Player.Seen",
+            "Removed",
+        ]
+    );
+    assert!(before.changed_paths(&before.clone()).is_empty());
+    assert_eq!(nbt!(1).changed_paths(&nbt!("1")), [""]);
+}
+
+#[test]
+fn coerce_to_schema() {
+    let input = nbt!({
+        "b": 1_i8,
+        "s": 2_i16,
+        "f": 1.5_f32,
+        "ints": [I; 1, -2],
+        "items": [{"id": "stone", "Count": 1_i8, "Old": 0}],
+        "empty": [],
+        "unknown": "dropped",
+    });
+    let schema = nbt!({
+        "b": 0_i64,
+        "s": 0.0_f32,
+        "f": 0.0,
+        "ints": [L;],
+        "items": [{"id": "", "Count": 0}],
+        "empty": [0],
+        "missing": 0,
+    });
+
+    assert_eq!(
+        input.coerce_to_schema(&schema).unwrap(),
+        nbt!({
+            "b": 1_i64,
+            "s": 2.0_f32,
+            "f": 1.5,
+            "ints": [L; 1, -2],
+            "items": [{"id": "stone", "Count": 1}],
+            "empty": [],
+        })
+    );
+
+    let narrowing = nbt!({"items": [{"Count": 300}]});
+    let schema = nbt!({"items": [{"Count": 0_i8}]});
+    assert_eq!(
+        narrowing.coerce_to_schema(&schema).unwrap_err().to_string(),
+        "cannot coerce int to byte at '/items/0/Count'"
+    );
+
+    assert!(nbt!(1_i64).coerce_to_schema(&nbt!(0.0)).is_err());
+    assert!(nbt!("1").coerce_to_schema(&nbt!(0)).is_err());
+}
+
+#[test]
+fn into_iter_consumes_children() {
+    let v = nbt!({"a": 1, "b": [1_i8, 2_i8], "c": [L; 5]});
+
+    let mut entries: Vec<_> = v.into_iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut entries = entries.into_iter();
+    assert_eq!(entries.next(), Some((Some("a".to_owned()), nbt!(1))));
+
+    let (key, list) = entries.next().unwrap();
+    assert_eq!(key.as_deref(), Some("b"));
+    assert_eq!(
+        list.into_iter().collect::<Vec<_>>(),
+        [(None, nbt!(1_i8)), (None, nbt!(2_i8))]
+    );
+
+    let (_, arr) = entries.next().unwrap();
+    assert_eq!(
+        arr.into_iter().collect::<Vec<_>>(),
+        [(None, Value::Long(5))]
+    );
+
+    assert_eq!(nbt!(1).into_iter().len(), 0);
+}
+
+#[test]
+fn flatten_unmodelled_keys_into_value() {
+    #[derive(Serialize, Deserialize, Debug)]
+    struct Chunk {
+        #[serde(rename = "DataVersion")]
+        data_version: i32,
+        #[serde(flatten)]
+        extra: Value,
+    }
+
+    let payload = Builder::new()
+        .start_compound("")
+        .int("DataVersion", 3120)
+        .start_compound("ForgeCaps")
+        .byte("flag", 1)
+        .short("s", 2)
+        .long("l", 3)
+        .float("f", 4.5)
+        .double("d", 5.5)
+        .string("name", "mod")
+        .byte_array("bytes", &[1, -2])
+        .int_array("ints", &[3, -4])
+        .long_array("longs", &[5, -6])
+        .start_list("list", Tag::Short, 2)
+        .short_payload(7)
+        .short_payload(8)
+        .start_list("empty", Tag::End, 0)
+        .end_compound()
+        .end_compound()
+        .build();
+
+    let chunk: Chunk = from_bytes(&payload).unwrap();
+    assert_eq!(chunk.data_version, 3120);
+    assert_eq!(
+        chunk.extra,
+        nbt!({
+            "ForgeCaps": {
+                "flag": 1_i8,
+                "s": 2_i16,
+                "l": 3_i64,
+                "f": 4.5_f32,
+                "d": 5.5,
+                "name": "mod",
+                "bytes": [B; 1, -2],
+                "ints": [I; 3, -4],
+                "longs": [L; 5, -6],
+                "list": [7_i16, 8_i16],
+                "empty": [],
+            }
+        })
+    );
+
+    // Compound order is not preserved, so compare by value.
+    let bytes = to_bytes(&chunk).unwrap();
+    let reparsed: Value = from_bytes(&bytes).unwrap();
+    let original: Value = from_bytes(&payload).unwrap();
+    assert_eq!(original, reparsed);
+}
+
+#[test]
+fn size_breakdown() {
+    let v = nbt!({
+        "Sections": [L; 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+        "Pos": [1.0, 2.0, 3.0],
+        "id": "zombie",
+        "é": 1_i8,
+    });
+
+    let sizes = v.size_breakdown();
+    assert_eq!(
+        sizes,
+        [
+            ("Sections".to_string(), 3 + 8 + 4 + 16 * 8),
+            ("Pos".to_string(), 3 + 3 + 5 + 3 * 8),
+            ("id".to_string(), 3 + 2 + 2 + 6),
+            ("é".to_string(), 3 + 2 + 1),
+        ]
+    );
+
+    // The contributions plus the root header and end tag make up the whole.
+    let total: usize = sizes.iter().map(|(_, size)| size).sum();
+    assert_eq!(to_bytes(&v).unwrap().len(), 3 + total + 1);
+
+    // Children can be broken down further.
+    let pos = v.pointer("/Pos").unwrap();
+    assert_eq!(pos.size_breakdown().len(), 3);
+    assert!(pos.size_breakdown().iter().all(|(_, size)| *size == 8));
+    assert!(nbt!(1).size_breakdown().is_empty());
+}
+
+#[test]
+fn serialized_size_is_exact() {
+    let v = nbt!({
+        "Sections": [{"Y": 0_i8, "BlockStates": [L; 1, 2, 3]}, {"Y": 1_i8}],
+        "Biomes": [I; 1, 2],
+        "Light": [B; 1],
+        "Empty": [],
+        "name": "\u{1F600} zombie",
+        "é": {"short": 1_i16, "float": 1.0_f32, "long": 1_i64},
+    });
+
+    let bytes = to_bytes_with_capacity(&v, v.serialized_size()).unwrap();
+    assert_eq!(bytes, to_bytes(&v).unwrap());
+    assert_eq!(bytes.len(), v.serialized_size());
+    assert_eq!(nbt!({}).serialized_size(), 4);
+}
+
+#[test]
+fn shape_hash_ignores_values() {
+    let a = nbt!({
+        "id": "minecraft:cow",
+        "Pos": [1.0, 2.0, 3.0],
+        "Health": 10.0_f32,
+        "Brain": {"memories": {}},
+        "UUID": [I; 1, 2, 3, 4],
+    });
+    let b = nbt!({
+        "UUID": [I; 5, 6, 7, 8, 9],
+        "Brain": {"memories": {}},
+        "Health": 4.5_f32,
+        "Pos": [-1.0, 0.0, 9.0],
+        "id": "minecraft:pig",
+    });
+    assert_eq!(a.shape_hash(), b.shape_hash());
+    assert_eq!(a.shape_hash(), a.clone().shape_hash());
+
+    let different = [
+        // A different key.
+        nbt!({"id": "minecraft:cow", "Pos": [1.0, 2.0, 3.0], "Health": 10.0_f32, "Brain": {"memory": {}}, "UUID": [I; 1]}),
+        // A different tag.
+        nbt!({"id": "minecraft:cow", "Pos": [1.0, 2.0, 3.0], "Health": 10.0, "Brain": {"memories": {}}, "UUID": [I; 1]}),
+        // A missing entry.
+        nbt!({"id": "minecraft:cow", "Pos": [1.0, 2.0, 3.0], "Brain": {"memories": {}}, "UUID": [I; 1]}),
+        // A shorter list.
+        nbt!({"id": "minecraft:cow", "Pos": [1.0, 2.0], "Health": 10.0_f32, "Brain": {"memories": {}}, "UUID": [I; 1]}),
+        // Different nesting.
+        nbt!({"id": "minecraft:cow", "Pos": [1.0, 2.0, 3.0], "Health": 10.0_f32, "Brain": {}, "memories": {}, "UUID": [I; 1]}),
+    ];
+    for v in &different {
+        assert_ne!(a.shape_hash(), v.shape_hash(), "{:?}", v);
+    }
+
+    // Swapping where keys are doesn't cancel out.
+    assert_ne!(
+        nbt!({"a": 1, "b": {"c": 1}}).shape_hash(),
+        nbt!({"c": 1, "b": {"a": 1}}).shape_hash()
+    );
+
+    // The hash is fixed, so it can be stored.
+    assert_eq!(nbt!({}).shape_hash(), 7089497817674790941);
+}
+
+#[test]
+fn arrays_and_lists_convert() {
+    let arrays = nbt!({
+        "b": [B; 1, -2],
+        "i": [I; 3],
+        "l": [L; 4, 5],
+        "empty": [I;],
+        "nested": [{"x": [L; 6]}, {"y": "s"}],
+        "shorts": [1_i16, 2_i16],
+    });
+    let lists = nbt!({
+        "b": [1_i8, -2_i8],
+        "i": [3],
+        "l": [4_i64, 5_i64],
+        "empty": [],
+        "nested": [{"x": [6_i64]}, {"y": "s"}],
+        "shorts": [1_i16, 2_i16],
+    });
+
+    let mut v = arrays.clone();
+    v.arrays_to_lists();
+    assert_eq!(v, lists);
+
+    // Empty arrays become empty lists, and so cannot be converted back.
+    v.lists_to_arrays();
+    let mut expected = arrays;
+    *expected.pointer_mut("/empty").unwrap() = nbt!([]);
+    assert_eq!(v, expected);
+
+    // Lists that are not homogeneous integers stay as lists.
+    let mut mixed = Value::List(vec![nbt!(1), nbt!(2_i64)]);
+    mixed.lists_to_arrays();
+    assert_eq!(mixed, Value::List(vec![nbt!(1), nbt!(2_i64)]));
+}
+
+#[test]
+fn query() {
+    let v = nbt!({
+        "Level": {
+            "Sections": [
+                {"Y": 0_i8, "Palette": [{"Name": "air"}]},
+                {"Y": 1_i8, "Palette": [{"Name": "stone"}, {"Name": "dirt"}]},
+            ],
+            "Entities": [
+                {"id": "zombie", "Passengers": [{"id": "chicken"}]},
+                {"id": "cow"},
+            ],
+        },
+        "odd.key": {"id": "odd"},
+    });
+
+    let q = |query: &str| v.query(query).unwrap();
+
+    assert_eq!(q("$"), [&v]);
+    assert_eq!(q("$.Level.Sections[*].Y"), [&nbt!(0_i8), &nbt!(1_i8)]);
+    assert_eq!(q("$.Level.Sections[1].Y"), [&nbt!(1_i8)]);
+    assert_eq!(q("$['Level'][\"Sections\"][0].Y"), [&nbt!(0_i8)]);
+    assert_eq!(q("$['odd.key'].id"), [&nbt!("odd")]);
+    assert_eq!(
+        q("$.Level.Sections[*].Palette[*].Name"),
+        [&nbt!("air"), &nbt!("stone"), &nbt!("dirt")]
+    );
+
+    // Recursive descent, in document order with keys sorted.
+    assert_eq!(
+        q("$..id"),
+        [
+            &nbt!("zombie"),
+            &nbt!("chicken"),
+            &nbt!("cow"),
+            &nbt!("odd")
+        ]
+    );
+    assert_eq!(q("$.Level.Entities..id").len(), 3);
+    assert_eq!(q("$..Palette[0].Name"), [&nbt!("air"), &nbt!("stone")]);
+    assert_eq!(q("$..Sections..Name").len(), 3);
+    assert_eq!(q("$.Level.Sections[0].*").len(), 2);
+
+    // Missing values match nothing.
+    assert!(q("$.Level.Sections[5]").is_empty());
+    assert!(q("$.Level.Sections.Y").is_empty());
+    assert!(q("$.Missing..id").is_empty());
+
+    for bad in [
+        "",
+        "Level",
+        "$.",
+        "$.Level[",
+        "$.Level[-1]",
+        "$.Level[0:2]",
+        "$.Level[?(@.Y)]",
+        "$.Level['unterminated]",
+        "$.Level[0,1]",
+        "$.[0]",
+    ] {
+        let err = v.query(bad).unwrap_err();
+        assert!(
+            err.to_string().contains("invalid query"),
+            "{}: {}",
+            bad,
+            err
+        );
+    }
+}
+
+#[test]
+fn prune_empty() {
+    let mut v = nbt!({
+        "keep": 1,
+        "empty": {},
+        "nested": {"a": {"b": {"c": []}}},
+        "list": [[], [[]], [1]],
+        "compounds": [{}, {"x": {}}],
+        "arrays": {"b": [B;], "i": [I;], "l": [L;], "full": [L; 1]},
+        "string": "",
+    });
+
+    let mut without_arrays = v.clone();
+    without_arrays.prune_empty(false);
+    assert_eq!(
+        without_arrays,
+        nbt!({
+            "keep": 1,
+            "list": [[1]],
+            "arrays": {"b": [B;], "i": [I;], "l": [L;], "full": [L; 1]},
+            "string": "",
+        })
+    );
+
+    v.prune_empty(true);
+    assert_eq!(
+        v,
+        nbt!({
+            "keep": 1,
+            "list": [[1]],
+            "arrays": {"full": [L; 1]},
+            "string": "",
+        })
+    );
+
+    // The value itself is left, even if empty.
+    let mut v = nbt!({"a": {}});
+    v.prune_empty(true);
+    assert_eq!(v, nbt!({}));
+}
+
+#[test]
+fn compound_and_list_from() {
+    let v = Value::compound_from([
+        ("x", Value::from(1i32)),
+        ("name", "foo".into()),
+        ("pos", Value::list_from([1.5f64, -2.0])),
+        ("tags", Value::list_from(Vec::<Value>::new())),
+    ]);
+    assert_eq!(
+        v,
+        nbt!({"x": 1, "name": "foo", "pos": [1.5, -2.0], "tags": []})
+    );
+
+    let owned_keys = Value::compound_from(vec![(String::from("x"), Value::from(1i32))]);
+    assert_eq!(owned_keys, nbt!({"x": 1}));
+
+    let bytes = to_bytes(&v).unwrap();
+    assert_eq!(v, from_bytes::<Value>(&bytes).unwrap());
+}
+
+#[test]
+fn visit_until_stops_early() {
+    use std::ops::ControlFlow;
+
+    let v = nbt!({
+        "list": [[1, 2], [3, 4], [5, 6]],
+        "arr": [I; 1, 2, 3],
+    });
+
+    let mut visited = 0;
+    let all = v.visit_until(|_| {
+        visited += 1;
+        ControlFlow::<()>::Continue(())
+    });
+    assert_eq!(all, ControlFlow::Continue(()));
+    // The root, the list, three inner lists and six ints. Array elements are
+    // not values.
+    assert_eq!(visited, 12);
+
+    let mut ints = Vec::new();
+    let found = v.pointer("/list").unwrap().visit_until(|v| {
+        if let Value::Int(i) = v {
+            ints.push(*i);
+            if *i == 3 {
+                return ControlFlow::Break(*i);
+            }
+        }
+        ControlFlow::Continue(())
+    });
+    assert_eq!(found, ControlFlow::Break(3));
+    assert_eq!(ints, [1, 2, 3]);
+
+    let mut visited = 0;
+    let root = v.visit_until(|_| {
+        visited += 1;
+        ControlFlow::Break(())
+    });
+    assert_eq!(root, ControlFlow::Break(()));
+    assert_eq!(visited, 1);
+}
+
+#[cfg(feature = "shared-value")]
+#[test]
+fn shared_value_copy_on_write() {
+    use crate::value::SharedValue;
+    use std::sync::Arc;
+
+    let original = nbt!({
+        "Level": {"xPos": 1, "Entities": [{"id": "cow"}]},
+        "Heightmap": [L; 1, 2, 3],
+    });
+    let mut current = SharedValue::from(original.clone());
+    let before = current.clone();
+
+    *current.pointer_mut("/Level/Entities/0/id").unwrap() = SharedValue::String("pig".into());
+    current
+        .pointer_mut("/Level")
+        .unwrap()
+        .as_compound_mut()
+        .unwrap()
+        .insert("zPos".to_owned(), SharedValue::Int(2));
+
+    // The prior clone is unaffected.
+    assert_eq!(Value::from(before.clone()), original);
+    assert_eq!(
+        Value::from(current.clone()),
+        nbt!({
+            "Level": {"xPos": 1, "zPos": 2, "Entities": [{"id": "pig"}]},
+            "Heightmap": [L; 1, 2, 3],
+        })
+    );
+
+    // Everything off the path that changed is still shared.
+    match (
+        before.pointer("/Heightmap").unwrap(),
+        current.pointer("/Heightmap").unwrap(),
+    ) {
+        (SharedValue::LongArray(a), SharedValue::LongArray(b)) => assert!(Arc::ptr_eq(a, b)),
+        _ => panic!("expected long arrays"),
+    }
+
+    // Missing paths copy nothing.
+    let mut copy = before.clone();
+    assert!(copy.pointer_mut("/Level/missing").is_none());
+    match (&before, &copy) {
+        (SharedValue::Compound(a), SharedValue::Compound(b)) => assert!(Arc::ptr_eq(a, b)),
+        _ => panic!("expected compounds"),
+    }
+
+    // Compare values rather than bytes, as the order of compound entries in
+    // the output depends on each map.
+    let bytes = to_bytes(&current).unwrap();
+    assert_eq!(
+        from_bytes::<Value>(&bytes).unwrap(),
+        Value::from(current.clone())
+    );
+    assert_eq!(from_bytes::<SharedValue>(&bytes).unwrap(), current);
+}
+
+#[cfg(feature = "shared-value")]
+#[test]
+fn shared_value_intern_strings() {
+    use crate::value::SharedValue;
+    use std::sync::Arc;
+
+    let original = nbt!({
+        "sections": [
+            {"palette": ["minecraft:stone", "minecraft:air"], "biome": "minecraft:plains"},
+            {"palette": ["minecraft:air", "minecraft:stone"], "biome": "minecraft:plains"},
+        ],
+    });
+    let mut v = SharedValue::from(original.clone());
+    v.intern_strings();
+
+    let string = |path| match v.pointer(path) {
+        Some(SharedValue::String(s)) => s.clone(),
+        _ => panic!("expected string at {}", path),
+    };
+    assert!(Arc::ptr_eq(
+        &string("/sections/0/palette/0"),
+        &string("/sections/1/palette/1")
+    ));
+    assert!(Arc::ptr_eq(
+        &string("/sections/0/biome"),
+        &string("/sections/1/biome")
+    ));
+    assert!(!Arc::ptr_eq(
+        &string("/sections/0/palette/0"),
+        &string("/sections/0/palette/1")
+    ));
+
+    assert_eq!(Value::from(v), original);
+
+    // Containers without a replaced string stay shared with clones.
+    let mut v = SharedValue::from(nbt!({
+        "unique": {"a": "x", "b": ["y"]},
+        "repeated": ["z", "z"],
+    }));
+    let clone = v.clone();
+    v.intern_strings();
+
+    let list = |v: &SharedValue, path| match v.pointer(path) {
+        Some(SharedValue::List(l)) => l.clone(),
+        _ => panic!("expected list at {}", path),
+    };
+    let compound = |v: &SharedValue, path| match v.pointer(path) {
+        Some(SharedValue::Compound(c)) => c.clone(),
+        _ => panic!("expected compound at {}", path),
+    };
+    assert!(Arc::ptr_eq(
+        &compound(&v, "/unique"),
+        &compound(&clone, "/unique")
+    ));
+    assert!(Arc::ptr_eq(
+        &list(&v, "/unique/b"),
+        &list(&clone, "/unique/b")
+    ));
+    assert!(!Arc::ptr_eq(
+        &list(&v, "/repeated"),
+        &list(&clone, "/repeated")
+    ));
+    assert_eq!(v, clone);
+}
+
+#[test]
+fn byte_chunks_concatenate_to_bytes() {
+    let v = nbt!({
+        "DataVersion": 3120,
+        "Level": {
+            "Sections": [
+                {"Y": 0_i8, "BlockStates": [L; 1, 2, 3], "Palette": [{"Name": "air"}]},
+                {"Y": 1_i8, "Biomes": [I; 4], "Light": [B; -1, 0]},
+            ],
+            "Pos": [1.5, 64.0, -3.5],
+            "Tags": [[], [1_i16, 2_i16], ["é"]],
+            "Empty": {},
+        },
+        "Health": 20.0_f32,
+        "Time": 1_i64,
+    });
+
+    let chunks: Vec<_> = v.byte_chunks().collect::<Result<_, _>>().unwrap();
+    assert!(chunks.len() > 10);
+    assert!(chunks.iter().all(|c| !c.is_empty()));
+    assert_eq!(chunks.concat(), to_bytes(&v).unwrap());
+
+    assert_eq!(
+        nbt!({})
+            .byte_chunks()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap(),
+        [vec![10, 0, 0], vec![0]]
+    );
+
+    let int = nbt!(1);
+    let mut chunks = int.byte_chunks();
+    assert!(chunks.next().unwrap().is_err());
+    assert!(chunks.next().is_none());
+
+    let mixed = Value::compound_from([("list", Value::List(vec![nbt!(1), nbt!("a")]))]);
+    let results: Vec<_> = mixed.byte_chunks().collect();
+    assert!(results.last().unwrap().is_err());
+}
+
+#[test]
+fn sort_list_at() {
+    let mut chunk = nbt!({
+        "Level": {
+            "TileEntities": [
+                {"id": "minecraft:sign", "x": 1},
+                {"id": "minecraft:chest", "x": 2},
+                {"x": 3},
+                {"id": "minecraft:chest", "x": 4},
+            ],
+        },
+        "n": 1,
+    });
+
+    chunk
+        .sort_list_at("/Level/TileEntities", |v| {
+            v.pointer("/id").and_then(Value::as_str).map(str::to_owned)
+        })
+        .unwrap();
+
+    // Missing keys sort first, and equal keys keep their order.
+    assert_eq!(
+        chunk.pointer("/Level/TileEntities").unwrap(),
+        &nbt!([
+            {"x": 3},
+            {"id": "minecraft:chest", "x": 2},
+            {"id": "minecraft:chest", "x": 4},
+            {"id": "minecraft:sign", "x": 1},
+        ])
+    );
+
+    let err = |r: crate::error::Result<()>| r.unwrap_err().to_string();
+    assert_eq!(
+        err(chunk.sort_list_at("/n", |_| 0)),
+        "cannot sort int at '/n', expected list"
+    );
+    assert_eq!(
+        err(chunk.sort_list_at("/missing", |_| 0)),
+        "no value at '/missing'"
+    );
+}
+
+#[test]
+fn approx_eq() {
+    let a = nbt!({
+        "Pos": [1.0, 64.0, -3.25],
+        "Rotation": [90.0_f32, 0.5_f32],
+        "Items": [{"id": "stone", "Damage": 0.1}],
+        "Seeds": [L; 1, 2],
+    });
+    let b = nbt!({
+        "Pos": [1.0 + 1e-9, 64.0, -3.25 - 1e-9],
+        "Rotation": [90.00001_f32, 0.5_f32],
+        "Items": [{"id": "stone", "Damage": 0.1 + 1e-12}],
+        "Seeds": [L; 1, 2],
+    });
+
+    assert_ne!(a, b);
+    assert!(a.approx_eq(&b, 1e-4));
+    assert!(b.approx_eq(&a, 1e-4));
+    assert!(!a.approx_eq(&b, 1e-10));
+    assert!(a.approx_eq(&a, 0.0));
+
+    // Everything but floats is compared exactly.
+    let mut other = b.clone();
+    *other.pointer_mut("/Items/0/id").unwrap() = nbt!("dirt");
+    assert!(!a.approx_eq(&other, 1.0));
+    assert!(!a.approx_eq(&nbt!({"Pos": [1.0, 64.0, -3.25]}), 1.0));
+    assert!(!nbt!([1.0, 2.0]).approx_eq(&nbt!([1.0]), 1.0));
+
+    // Floats and doubles are different tags.
+    assert!(!nbt!(1.0_f32).approx_eq(&nbt!(1.0), 1.0));
+
+    assert!(nbt!(f64::INFINITY).approx_eq(&nbt!(f64::INFINITY), 0.0));
+    assert!(!nbt!(f64::NAN).approx_eq(&nbt!(f64::NAN), f64::INFINITY));
+}
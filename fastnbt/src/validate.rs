@@ -0,0 +1,257 @@
+//! Strict validation of NBT data against the specification.
+//!
+//! The deserializer is deliberately lenient, as Minecraft itself writes data
+//! that bends the rules, such as empty lists with an element tag other than
+//! End. [`validate_strict`] is the opposite: it accepts only data that follows
+//! the specification exactly, for testing that a producer of NBT is
+//! conformant. It does not deserialize anything.
+
+use std::{collections::HashSet, fmt};
+
+use crate::Tag;
+
+/// Limits for [`validate_strict`]. This object follows a builder pattern.
+///
+/// By default compounds and lists can be nested 512 deep, as in Minecraft, and
+/// lists and arrays can have up to 10,000,000 elements.
+#[derive(Debug, Clone)]
+pub struct Limits {
+    max_depth: usize,
+    max_len: usize,
+}
+
+impl Limits {
+    /// Create new limits.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Set how deep compounds and lists can be nested. The root compound is
+    /// at depth one.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Set the maximum number of elements of any list or array.
+    pub fn max_len(mut self, len: usize) -> Self {
+        self.max_len = len;
+        self
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_depth: 512,
+            max_len: 10_000_000,
+        }
+    }
+}
+
+/// The first way some data breaks the NBT specification, found by
+/// [`validate_strict`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// Offset into the data of the tag, name, length or value at fault.
+    pub offset: usize,
+    /// What is wrong with the data.
+    pub description: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at offset {}", self.description, self.offset)
+    }
+}
+
+impl std::error::Error for Violation {}
+
+/// Check that `data` is a single root compound that follows the NBT
+/// specification exactly, returning the first violation found.
+///
+/// Besides being well-formed, which the deserializer also requires, the data
+/// must not have:
+///
+/// * an empty list with an element tag other than End, or a list of End that
+///   is not empty,
+/// * a key that appears twice in the same compound,
+/// * a name or string that is not Modified UTF-8, for example one containing
+///   a zero byte or a four-byte UTF-8 sequence,
+/// * a root tag other than a compound, or any data after the root compound,
+/// * lists, arrays or nesting beyond `limits`.
+///
+/// ```
+/// # use fastnbt::{nbt, validate::Limits};
+/// let data = fastnbt::to_bytes(&nbt!({"a": 1, "b": []}))?;
+/// assert!(fastnbt::validate_strict(&data, &Limits::new()).is_ok());
+///
+/// let mut trailing = data.clone();
+/// trailing.push(0);
+/// let violation = fastnbt::validate_strict(&trailing, &Limits::new()).unwrap_err();
+/// assert_eq!(violation.offset, data.len());
+/// # Ok::<(), fastnbt::error::Error>(())
+/// ```
+pub fn validate_strict(data: &[u8], limits: &Limits) -> Result<(), Violation> {
+    let mut v = Validator {
+        data,
+        pos: 0,
+        limits,
+    };
+
+    let start = v.pos;
+    if v.tag()? != Tag::Compound {
+        return Err(v.violation(start, "root tag is not a compound"));
+    }
+    v.string("name")?;
+    v.payload(Tag::Compound, 1)?;
+
+    if v.pos != data.len() {
+        return Err(v.violation(v.pos, "data after the root compound"));
+    }
+    Ok(())
+}
+
+struct Validator<'a> {
+    data: &'a [u8],
+    pos: usize,
+    limits: &'a Limits,
+}
+
+impl<'a> Validator<'a> {
+    fn violation(&self, offset: usize, description: impl Into<String>) -> Violation {
+        Violation {
+            offset,
+            description: description.into(),
+        }
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], Violation> {
+        match self.data.get(self.pos..self.pos.saturating_add(n)) {
+            Some(bytes) => {
+                self.pos += n;
+                Ok(bytes)
+            }
+            None => Err(self.violation(self.pos, "unexpected end of data")),
+        }
+    }
+
+    fn tag(&mut self) -> Result<Tag, Violation> {
+        let start = self.pos;
+        let id = self.take(1)?[0];
+        Tag::try_from(id).map_err(|_| self.violation(start, format!("invalid tag {}", id)))
+    }
+
+    /// Read a length-prefixed string, checking it is Modified UTF-8. Returns
+    /// the raw bytes.
+    fn string(&mut self, what: &str) -> Result<&'a [u8], Violation> {
+        let len = u16::from_be_bytes(self.take(2)?.try_into().unwrap()) as usize;
+        let start = self.pos;
+        let bytes = self.take(len)?;
+        // The decoder accepts plain UTF-8 too, so reject what that has and
+        // Modified UTF-8 does not: zero bytes and four-byte sequences.
+        if bytes.iter().any(|&b| b == 0 || b >= 0xf0) || cesu8::from_java_cesu8(bytes).is_err() {
+            return Err(self.violation(start, format!("{} is not Modified UTF-8", what)));
+        }
+        Ok(bytes)
+    }
+
+    /// Read the length of a list or array.
+    fn len(&mut self) -> Result<usize, Violation> {
+        let start = self.pos;
+        let len = i32::from_be_bytes(self.take(4)?.try_into().unwrap());
+        let len = usize::try_from(len)
+            .map_err(|_| self.violation(start, format!("negative length {}", len)))?;
+        if len > self.limits.max_len {
+            return Err(self.violation(
+                start,
+                format!(
+                    "length {} exceeds the limit of {}",
+                    len, self.limits.max_len
+                ),
+            ));
+        }
+        Ok(len)
+    }
+
+    fn payload(&mut self, tag: Tag, depth: usize) -> Result<(), Violation> {
+        if matches!(tag, Tag::Compound | Tag::List) && depth > self.limits.max_depth {
+            return Err(self.violation(
+                self.pos,
+                format!("nesting exceeds the limit of {}", self.limits.max_depth),
+            ));
+        }
+
+        match tag {
+            Tag::End => return Err(self.violation(self.pos, "unexpected end tag")),
+            Tag::Byte => {
+                self.take(1)?;
+            }
+            Tag::Short => {
+                self.take(2)?;
+            }
+            Tag::Int | Tag::Float => {
+                self.take(4)?;
+            }
+            Tag::Long | Tag::Double => {
+                self.take(8)?;
+            }
+            Tag::String => {
+                self.string("string")?;
+            }
+            Tag::ByteArray => {
+                let len = self.len()?;
+                self.take(len)?;
+            }
+            Tag::IntArray => {
+                let len = self.len()?;
+                self.take(len.saturating_mul(4))?;
+            }
+            Tag::LongArray => {
+                let len = self.len()?;
+                self.take(len.saturating_mul(8))?;
+            }
+            Tag::List => {
+                let start = self.pos;
+                let element = self.tag()?;
+                let len = self.len()?;
+                match (element, len) {
+                    (Tag::End, 0) => {}
+                    (Tag::End, _) => {
+                        return Err(self.violation(start, "non-empty list of end tags"));
+                    }
+                    (_, 0) => {
+                        return Err(self.violation(
+                            start,
+                            format!("empty list with element tag {} rather than end", element),
+                        ));
+                    }
+                    _ => {
+                        for _ in 0..len {
+                            self.payload(element, depth + 1)?;
+                        }
+                    }
+                }
+            }
+            Tag::Compound => {
+                let mut keys = HashSet::new();
+                loop {
+                    let start = self.pos;
+                    let tag = self.tag()?;
+                    if tag == Tag::End {
+                        break;
+                    }
+                    let name = self.string("name")?;
+                    if !keys.insert(name) {
+                        return Err(self.violation(
+                            start,
+                            format!("duplicate key '{}'", String::from_utf8_lossy(name)),
+                        ));
+                    }
+                    self.payload(tag, depth + 1)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
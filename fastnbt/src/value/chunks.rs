@@ -0,0 +1,129 @@
+use std::{collections::hash_map, mem, slice};
+
+use crate::{error::Result, ser::NbtWriter, Tag};
+
+use super::Value;
+
+impl Value {
+    /// Serialize this value incrementally, as an iterator of chunks of NBT
+    /// data. Concatenating the chunks gives the same data as
+    /// [`to_bytes`][`crate::to_bytes`], but only one chunk is held in memory
+    /// at a time, so they can be passed to a sink that is not a
+    /// [`Write`][`std::io::Write`].
+    ///
+    /// There is a chunk for each value: the header of a compound or list, or
+    /// the whole of any other value, such as an array. The end of a compound
+    /// is a chunk of its own.
+    ///
+    /// Like [`to_bytes`][`crate::to_bytes`], the value must be a compound, and
+    /// every list must have elements of a single tag. Otherwise the iterator
+    /// yields an error and then ends.
+    ///
+    /// ```
+    /// # use fastnbt::nbt;
+    /// let v = nbt!({"Pos": [1.0, 2.0], "id": "cow"});
+    ///
+    /// let mut out = Vec::new();
+    /// for chunk in v.byte_chunks() {
+    ///     out.extend(chunk?);
+    /// }
+    /// assert_eq!(out, fastnbt::to_bytes(&v)?);
+    /// # Ok::<(), fastnbt::error::Error>(())
+    /// ```
+    pub fn byte_chunks(&self) -> ByteChunks<'_> {
+        ByteChunks {
+            root: Some(self),
+            writer: NbtWriter::new(Vec::new()),
+            stack: Vec::new(),
+        }
+    }
+}
+
+enum Frame<'a> {
+    Compound(hash_map::Iter<'a, String, Value>),
+    List(slice::Iter<'a, Value>),
+}
+
+/// Iterator of chunks of NBT data. See [`Value::byte_chunks`].
+pub struct ByteChunks<'a> {
+    root: Option<&'a Value>,
+    writer: NbtWriter<Vec<u8>>,
+    stack: Vec<Frame<'a>>,
+}
+
+impl<'a> ByteChunks<'a> {
+    fn write(&mut self, name: &str, v: &'a Value) -> Result<()> {
+        let w = &mut self.writer;
+        match v {
+            Value::Byte(v) => w.write_byte(name, *v),
+            Value::Short(v) => w.write_short(name, *v),
+            Value::Int(v) => w.write_int(name, *v),
+            Value::Long(v) => w.write_long(name, *v),
+            Value::Float(v) => w.write_float(name, *v),
+            Value::Double(v) => w.write_double(name, *v),
+            Value::String(v) => w.write_string(name, v),
+            Value::ByteArray(v) => w.write_byte_array(name, v),
+            Value::IntArray(v) => w.write_int_array(name, v),
+            Value::LongArray(v) => w.write_long_array(name, v),
+            Value::List(list) => {
+                let element = list.first().map_or(Tag::End, Value::tag);
+                w.start_list(name, element, list.len())?;
+                self.stack.push(Frame::List(list.iter()));
+                Ok(())
+            }
+            Value::Compound(map) => {
+                w.start_compound(name)?;
+                self.stack.push(Frame::Compound(map.iter()));
+                Ok(())
+            }
+        }
+    }
+
+    /// Write the next value, or the end of a compound. Returns false once
+    /// everything has been written.
+    fn step(&mut self) -> Result<bool> {
+        if let Some(root) = self.root.take() {
+            self.write("", root)?;
+            return Ok(true);
+        }
+
+        match self.stack.last_mut() {
+            Some(Frame::Compound(entries)) => match entries.next() {
+                Some((k, v)) => self.write(k, v)?,
+                None => {
+                    self.stack.pop();
+                    self.writer.end_compound()?;
+                }
+            },
+            Some(Frame::List(elements)) => match elements.next() {
+                Some(v) => self.write("", v)?,
+                None => {
+                    self.stack.pop();
+                    self.writer.end_list()?;
+                }
+            },
+            None => return Ok(false),
+        }
+        Ok(true)
+    }
+}
+
+impl Iterator for ByteChunks<'_> {
+    type Item = Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.step() {
+                // The end of a list writes nothing, so carry on to the next
+                // value.
+                Ok(true) if self.writer.get_mut().is_empty() => {}
+                Ok(true) => return Some(Ok(mem::take(self.writer.get_mut()))),
+                Ok(false) => return None,
+                Err(e) => {
+                    self.stack.clear();
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+
+use crate::{error::Error, IntArray, LongArray};
+
+use super::Value;
+
+impl Value {
+    /// Convert this value to the shape of `schema`, a template value whose
+    /// types are the ones expected. This is useful for bringing data written
+    /// by an older version up to date with what a newer version expects.
+    ///
+    /// * Compounds keep only the keys that are in the schema compound, with
+    ///   each value coerced to the schema's value. Keys missing from this
+    ///   value are left missing.
+    /// * Each element of a list is coerced to the first element of the schema
+    ///   list. If the schema list is empty the elements are left as they are.
+    /// * Numbers can be widened to a type that can represent every value of
+    ///   the original type: `Byte` to `Short` to `Int` to `Long`, `Byte` and
+    ///   `Short` to `Float`, any of those but `Long` to `Double`. Arrays can
+    ///   similarly be widened from `ByteArray` to `IntArray` to `LongArray`.
+    /// * Anything else, including narrowing a number, is an error. The error
+    ///   names the path of the offending value in the format used by
+    ///   [`Value::pointer`].
+    ///
+    /// The values in the schema are only used for their types.
+    ///
+    /// ```
+    /// # use fastnbt::nbt;
+    /// let old = nbt!({"Version": 3_i8, "Pos": [1, 2], "Legacy": true});
+    /// let schema = nbt!({"Version": 0, "Pos": [0.0]});
+    ///
+    /// assert_eq!(
+    ///     old.coerce_to_schema(&schema)?,
+    ///     nbt!({"Version": 3, "Pos": [1.0, 2.0]})
+    /// );
+    /// # Ok::<(), fastnbt::error::Error>(())
+    /// ```
+    pub fn coerce_to_schema(&self, schema: &Value) -> Result<Value, Error> {
+        coerce(self, schema, &mut String::new())
+    }
+}
+
+fn coerce(value: &Value, schema: &Value, path: &mut String) -> Result<Value, Error> {
+    let coerced = match (value, schema) {
+        (Value::Compound(map), Value::Compound(schema)) => {
+            let mut out = HashMap::with_capacity(schema.len().min(map.len()));
+            for (key, v) in map {
+                if let Some(s) = schema.get(key) {
+                    let len = path.len();
+                    path.push('/');
+                    path.push_str(&key.replace('~', "~0").replace('/', "~1"));
+                    out.insert(key.clone(), coerce(v, s, path)?);
+                    path.truncate(len);
+                }
+            }
+            Value::Compound(out)
+        }
+        (Value::List(list), Value::List(schema)) => match schema.first() {
+            Some(s) => Value::List(
+                list.iter()
+                    .enumerate()
+                    .map(|(i, v)| {
+                        let len = path.len();
+                        path.push('/');
+                        path.push_str(&i.to_string());
+                        let v = coerce(v, s, path);
+                        path.truncate(len);
+                        v
+                    })
+                    .collect::<Result<_, _>>()?,
+            ),
+            None => value.clone(),
+        },
+
+        (Value::Byte(v), Value::Byte(_)) => Value::Byte(*v),
+        (Value::Byte(v), Value::Short(_)) => Value::Short((*v).into()),
+        (Value::Byte(v), Value::Int(_)) => Value::Int((*v).into()),
+        (Value::Byte(v), Value::Long(_)) => Value::Long((*v).into()),
+        (Value::Byte(v), Value::Float(_)) => Value::Float((*v).into()),
+        (Value::Byte(v), Value::Double(_)) => Value::Double((*v).into()),
+        (Value::Short(v), Value::Short(_)) => Value::Short(*v),
+        (Value::Short(v), Value::Int(_)) => Value::Int((*v).into()),
+        (Value::Short(v), Value::Long(_)) => Value::Long((*v).into()),
+        (Value::Short(v), Value::Float(_)) => Value::Float((*v).into()),
+        (Value::Short(v), Value::Double(_)) => Value::Double((*v).into()),
+        (Value::Int(v), Value::Int(_)) => Value::Int(*v),
+        (Value::Int(v), Value::Long(_)) => Value::Long((*v).into()),
+        (Value::Int(v), Value::Double(_)) => Value::Double((*v).into()),
+        (Value::Long(v), Value::Long(_)) => Value::Long(*v),
+        (Value::Float(v), Value::Float(_)) => Value::Float(*v),
+        (Value::Float(v), Value::Double(_)) => Value::Double((*v).into()),
+        (Value::Double(v), Value::Double(_)) => Value::Double(*v),
+        (Value::String(v), Value::String(_)) => Value::String(v.clone()),
+
+        (Value::ByteArray(v), Value::ByteArray(_)) => Value::ByteArray(v.clone()),
+        (Value::ByteArray(v), Value::IntArray(_)) => {
+            Value::IntArray(IntArray::new(v.iter().map(|&b| b.into()).collect()))
+        }
+        (Value::ByteArray(v), Value::LongArray(_)) => {
+            Value::LongArray(LongArray::new(v.iter().map(|&b| b.into()).collect()))
+        }
+        (Value::IntArray(v), Value::IntArray(_)) => Value::IntArray(v.clone()),
+        (Value::IntArray(v), Value::LongArray(_)) => {
+            Value::LongArray(LongArray::new(v.iter().map(|&i| i.into()).collect()))
+        }
+        (Value::LongArray(v), Value::LongArray(_)) => Value::LongArray(v.clone()),
+
+        _ => {
+            return Err(Error::bespoke(format!(
+                "cannot coerce {} to {} at '{}'",
+                value.tag(),
+                schema.tag(),
+                path
+            )))
+        }
+    };
+
+    Ok(coerced)
+}
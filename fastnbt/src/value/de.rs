@@ -8,6 +8,7 @@ use serde::{
     },
     forward_to_deserialize_any, serde_if_integer128, Deserialize, Deserializer,
 };
+use serde_bytes::ByteBuf;
 
 use crate::{error::Error, ByteArray, IntArray, LongArray, Value};
 
@@ -109,18 +110,18 @@ impl<'de> Deserialize<'de> for Value {
                         Ok(Value::Compound(compound))
                     }
                     Some(KeyClass::ByteArray) => {
-                        let data = map.next_value::<&[u8]>()?;
-                        Ok(Value::ByteArray(ByteArray::from_bytes(data)))
+                        let data = map.next_value::<ByteBuf>()?;
+                        Ok(Value::ByteArray(ByteArray::from_bytes(&data)))
                     }
                     Some(KeyClass::IntArray) => {
-                        let data = map.next_value::<&[u8]>()?;
-                        IntArray::from_bytes(data)
+                        let data = map.next_value::<ByteBuf>()?;
+                        IntArray::from_bytes(&data)
                             .map(Value::IntArray)
                             .map_err(|_| serde::de::Error::custom("could not read int array"))
                     }
                     Some(KeyClass::LongArray) => {
-                        let data = map.next_value::<&[u8]>()?;
-                        LongArray::from_bytes(data)
+                        let data = map.next_value::<ByteBuf>()?;
+                        LongArray::from_bytes(&data)
                             .map(Value::LongArray)
                             .map_err(|_| serde::de::Error::custom("could not read long array"))
                     }
@@ -662,13 +663,7 @@ impl<'de> VariantAccess<'de> for VariantDeserializer<'de> {
         V: Visitor<'de>,
     {
         match self.value {
-            Some(Value::List(v)) => {
-                if v.is_empty() {
-                    visitor.visit_unit()
-                } else {
-                    visit_list(v, visitor)
-                }
-            }
+            Some(Value::List(v)) => visit_list(v, visitor),
             Some(other) => Err(serde::de::Error::invalid_type(
                 other.unexpected(),
                 &"tuple variant",
@@ -868,6 +863,532 @@ impl<'de> serde::Deserializer<'de> for MapKeyDeserializer<'de> {
     }
 }
 
+macro_rules! deserialize_number_owned {
+    ($method:ident, $visit:ident, $primitive:ident, $variant:ident) => {
+        fn $method<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'de>,
+        {
+            match self {
+                Value::$variant(v) => visitor.$visit(v as $primitive),
+                _ => Err(self.invalid_type(&visitor)),
+            }
+        }
+    };
+}
+
+fn visit_list_owned<'de, V>(list: Vec<Value>, visitor: V) -> Result<V::Value, Error>
+where
+    V: Visitor<'de>,
+{
+    let len = list.len();
+    let mut deserializer = OwnedSeqDeserializer {
+        iter: list.into_iter(),
+    };
+    let seq = visitor.visit_seq(&mut deserializer)?;
+    let remaining = deserializer.iter.len();
+    if remaining == 0 {
+        Ok(seq)
+    } else {
+        Err(serde::de::Error::invalid_length(
+            len,
+            &"fewer elements in list",
+        ))
+    }
+}
+
+fn visit_compound_owned<'de, V>(
+    compound: HashMap<String, Value>,
+    visitor: V,
+) -> Result<V::Value, Error>
+where
+    V: Visitor<'de>,
+{
+    let len = compound.len();
+    let mut deserializer = OwnedMapDeserializer {
+        iter: compound.into_iter(),
+        value: None,
+    };
+    let map = visitor.visit_map(&mut deserializer)?;
+    let remaining = deserializer.iter.len();
+    if remaining == 0 {
+        Ok(map)
+    } else {
+        Err(serde::de::Error::invalid_length(
+            len,
+            &"fewer elements in map",
+        ))
+    }
+}
+
+/// Deserializing from an owned `Value` behaves the same as deserializing from
+/// `&Value`, but allows the value to be consumed.
+impl<'de> serde::Deserializer<'de> for Value {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::Byte(val) => visitor.visit_i8(val),
+            Value::Short(val) => visitor.visit_i16(val),
+            Value::Int(val) => visitor.visit_i32(val),
+            Value::Long(val) => visitor.visit_i64(val),
+            Value::Float(val) => visitor.visit_f32(val),
+            Value::Double(val) => visitor.visit_f64(val),
+            Value::String(val) => visitor.visit_string(val),
+            Value::ByteArray(val) => visitor.visit_map(OwnedArrayAccess {
+                token: crate::BYTE_ARRAY_TOKEN,
+                data: Some(val.to_bytes()),
+            }),
+            Value::IntArray(val) => visitor.visit_map(OwnedArrayAccess {
+                token: crate::INT_ARRAY_TOKEN,
+                data: Some(val.to_bytes()),
+            }),
+            Value::LongArray(val) => visitor.visit_map(OwnedArrayAccess {
+                token: crate::LONG_ARRAY_TOKEN,
+                data: Some(val.to_bytes()),
+            }),
+            Value::List(val) => visit_list_owned(val, visitor),
+            Value::Compound(val) => visit_compound_owned(val, visitor),
+        }
+    }
+
+    deserialize_number_owned!(deserialize_i8, visit_i8, i8, Byte);
+    deserialize_number_owned!(deserialize_i16, visit_i16, i16, Short);
+    deserialize_number_owned!(deserialize_i32, visit_i32, i32, Int);
+    deserialize_number_owned!(deserialize_i64, visit_i64, i64, Long);
+    deserialize_number_owned!(deserialize_u8, visit_u8, u8, Byte);
+    deserialize_number_owned!(deserialize_u16, visit_u16, u16, Short);
+    deserialize_number_owned!(deserialize_u32, visit_u32, u32, Int);
+    deserialize_number_owned!(deserialize_u64, visit_u64, u64, Long);
+    deserialize_number_owned!(deserialize_f32, visit_f32, f32, Float);
+    deserialize_number_owned!(deserialize_f64, visit_f64, f64, Double);
+
+    serde_if_integer128! {
+        fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'de>,
+        {
+            visitor.visit_i128(get_i128_value(&self)?)
+        }
+
+        fn deserialize_u128<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'de>,
+        {
+            visitor.visit_u128(get_i128_value(&self)? as u128)
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        let (variant, value) = match self {
+            Value::Compound(value) => {
+                let mut iter = value.into_iter();
+                let (variant, value) = match iter.next() {
+                    Some(v) => v,
+                    None => {
+                        return Err(serde::de::Error::invalid_value(
+                            Unexpected::Map,
+                            &"map with a single key",
+                        ));
+                    }
+                };
+                // enums are encoded in nbt as maps with a single key:value pair
+                if iter.next().is_some() {
+                    return Err(serde::de::Error::invalid_value(
+                        Unexpected::Map,
+                        &"map with a single key",
+                    ));
+                }
+                (variant, Some(value))
+            }
+            Value::String(variant) => (variant, None),
+            other => {
+                return Err(serde::de::Error::invalid_type(
+                    other.unexpected(),
+                    &"string or map",
+                ));
+            }
+        };
+
+        visitor.visit_enum(OwnedEnumDeserializer { variant, value })
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::Byte(v) => visitor.visit_bool(v != 0),
+            Value::Short(v) => visitor.visit_bool(v != 0),
+            Value::Int(v) => visitor.visit_bool(v != 0),
+            Value::Long(v) => visitor.visit_bool(v != 0),
+            _ => Err(self.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_char<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::Int(v) => match char::from_u32(v as u32) {
+                Some(v) => visitor.visit_char(v),
+                None => Err(serde::de::Error::invalid_value(
+                    self.unexpected(),
+                    &"invalid character code",
+                )),
+            },
+            Value::String(ref v) => match v.chars().next() {
+                Some(v) => visitor.visit_char(v),
+                None => Err(serde::de::Error::invalid_value(
+                    self.unexpected(),
+                    &"string contains no character",
+                )),
+            },
+            _ => Err(self.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::String(v) => visitor.visit_string(v),
+            _ => Err(self.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::String(v) => visitor.visit_string(v),
+            Value::List(v) => visit_list_owned(v, visitor),
+            _ => Err(self.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::List(v) => visit_list_owned(v, visitor),
+            _ => Err(self.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::List(v) => visit_list_owned(v, visitor),
+            Value::Compound(v) => visit_compound_owned(v, visitor),
+            _ => Err(self.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Value {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+struct OwnedArrayAccess {
+    token: &'static str,
+    data: Option<Vec<u8>>,
+}
+
+impl<'de> MapAccess<'de> for OwnedArrayAccess {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        if self.data.is_some() {
+            seed.deserialize(BorrowedStrDeserializer::new(self.token))
+                .map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        match self.data.take() {
+            Some(data) => seed.deserialize(BytesDeserializer::new(&data)),
+            None => Err(serde::de::Error::custom("value is missing")),
+        }
+    }
+}
+
+struct OwnedEnumDeserializer {
+    variant: String,
+    value: Option<Value>,
+}
+
+impl<'de> EnumAccess<'de> for OwnedEnumDeserializer {
+    type Error = Error;
+    type Variant = OwnedVariantDeserializer;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let variant = self.variant.into_deserializer();
+        let visitor = OwnedVariantDeserializer { value: self.value };
+        seed.deserialize(variant).map(|v| (v, visitor))
+    }
+}
+
+struct OwnedVariantDeserializer {
+    value: Option<Value>,
+}
+
+impl<'de> VariantAccess<'de> for OwnedVariantDeserializer {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        match self.value {
+            Some(value) => serde::Deserialize::deserialize(value),
+            None => Ok(()),
+        }
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.value {
+            Some(value) => seed.deserialize(value),
+            None => Err(serde::de::Error::invalid_type(
+                Unexpected::UnitVariant,
+                &"newtype variant",
+            )),
+        }
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Some(Value::List(v)) => visit_list_owned(v, visitor),
+            Some(other) => Err(serde::de::Error::invalid_type(
+                other.unexpected(),
+                &"tuple variant",
+            )),
+            None => Err(serde::de::Error::invalid_type(
+                Unexpected::UnitVariant,
+                &"tuple variant",
+            )),
+        }
+    }
+
+    fn struct_variant<V>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Some(Value::Compound(v)) => visit_compound_owned(v, visitor),
+            Some(other) => Err(serde::de::Error::invalid_type(
+                other.unexpected(),
+                &"struct variant",
+            )),
+            None => Err(serde::de::Error::invalid_type(
+                Unexpected::UnitVariant,
+                &"struct variant",
+            )),
+        }
+    }
+}
+
+struct OwnedSeqDeserializer {
+    iter: std::vec::IntoIter<Value>,
+}
+
+impl<'de> SeqAccess<'de> for OwnedSeqDeserializer {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.iter.next() {
+            Some(value) => seed.deserialize(value).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        match self.iter.size_hint() {
+            (lower, Some(upper)) if lower == upper => Some(upper),
+            _ => None,
+        }
+    }
+}
+
+struct OwnedMapDeserializer {
+    iter: <HashMap<String, Value> as IntoIterator>::IntoIter,
+    value: Option<Value>,
+}
+
+impl<'de> MapAccess<'de> for OwnedMapDeserializer {
+    type Error = Error;
+
+    fn next_key_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.iter.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                let key_de = MapKeyDeserializer {
+                    key: Cow::Owned(key),
+                };
+                seed.deserialize(key_de).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<T>(&mut self, seed: T) -> Result<T::Value, Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.value.take() {
+            Some(value) => seed.deserialize(value),
+            None => Err(serde::de::Error::custom("value is missing")),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        match self.iter.size_hint() {
+            (lower, Some(upper)) if lower == upper => Some(upper),
+            _ => None,
+        }
+    }
+}
+
 impl Value {
     #[cold]
     fn invalid_type<E>(&self, exp: &dyn Expected) -> E
@@ -0,0 +1,132 @@
+use std::{collections::hash_map, ops::ControlFlow, vec};
+
+use super::Value;
+
+impl Value {
+    /// Call `f` with this value and then every value nested within it, depth
+    /// first, stopping as soon as `f` returns [`ControlFlow::Break`]. The
+    /// result is that `Break`, or `Continue` if every value was visited. This
+    /// makes checks like "does anything match" cheap when a match is found
+    /// early.
+    ///
+    /// Compound entries are visited in arbitrary order. Elements of NBT arrays
+    /// are not visited, as they are not values.
+    ///
+    /// ```
+    /// # use fastnbt::{nbt, Value};
+    /// use std::ops::ControlFlow;
+    ///
+    /// let data = nbt!({"Items": [{"id": "stone"}, {"id": "diamond"}]});
+    ///
+    /// let found = data.visit_until(|v| match v {
+    ///     Value::String(s) if s == "diamond" => ControlFlow::Break(v),
+    ///     _ => ControlFlow::Continue(()),
+    /// });
+    /// assert_eq!(found, ControlFlow::Break(&nbt!("diamond")));
+    /// ```
+    pub fn visit_until<'a, B>(
+        &'a self,
+        mut f: impl FnMut(&'a Value) -> ControlFlow<B>,
+    ) -> ControlFlow<B> {
+        self.visit_until_inner(&mut f)
+    }
+
+    fn visit_until_inner<'a, B>(
+        &'a self,
+        f: &mut impl FnMut(&'a Value) -> ControlFlow<B>,
+    ) -> ControlFlow<B> {
+        f(self)?;
+        match self {
+            Value::Compound(map) => {
+                for v in map.values() {
+                    v.visit_until_inner(f)?;
+                }
+            }
+            Value::List(list) => {
+                for v in list {
+                    v.visit_until_inner(f)?;
+                }
+            }
+            _ => {}
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+/// Owning iterator over the children of a [`Value`], created by its
+/// [`IntoIterator`] implementation.
+///
+/// Each item is the child's key, for children of a compound, and the child
+/// itself. Compound entries are produced in arbitrary order. Elements of lists
+/// have no key, and neither do elements of NBT arrays, which are produced as
+/// [`Value::Byte`], [`Value::Int`] or [`Value::Long`]. Values that are not
+/// containers have no children.
+pub struct IntoIter {
+    inner: Inner,
+}
+
+enum Inner {
+    Empty,
+    Compound(hash_map::IntoIter<String, Value>),
+    List(vec::IntoIter<Value>),
+    ByteArray(vec::IntoIter<i8>),
+    IntArray(vec::IntoIter<i32>),
+    LongArray(vec::IntoIter<i64>),
+}
+
+impl Iterator for IntoIter {
+    type Item = (Option<String>, Value);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.inner {
+            Inner::Empty => None,
+            Inner::Compound(it) => it.next().map(|(k, v)| (Some(k), v)),
+            Inner::List(it) => it.next().map(|v| (None, v)),
+            Inner::ByteArray(it) => it.next().map(|v| (None, Value::Byte(v))),
+            Inner::IntArray(it) => it.next().map(|v| (None, Value::Int(v))),
+            Inner::LongArray(it) => it.next().map(|v| (None, Value::Long(v))),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.inner {
+            Inner::Empty => (0, Some(0)),
+            Inner::Compound(it) => it.size_hint(),
+            Inner::List(it) => it.size_hint(),
+            Inner::ByteArray(it) => it.size_hint(),
+            Inner::IntArray(it) => it.size_hint(),
+            Inner::LongArray(it) => it.size_hint(),
+        }
+    }
+}
+
+impl ExactSizeIterator for IntoIter {}
+
+impl IntoIterator for Value {
+    type Item = (Option<String>, Value);
+    type IntoIter = IntoIter;
+
+    /// Consume this value, iterating over its children without cloning them.
+    /// See [`IntoIter`] for what is produced.
+    ///
+    /// ```
+    /// # use fastnbt::nbt;
+    /// let data = nbt!({"Name": "Steve"});
+    ///
+    /// for (key, value) in data {
+    ///     assert_eq!(key.as_deref(), Some("Name"));
+    ///     assert_eq!(value, "Steve");
+    /// }
+    /// ```
+    fn into_iter(self) -> IntoIter {
+        let inner = match self {
+            Value::Compound(map) => Inner::Compound(map.into_iter()),
+            Value::List(list) => Inner::List(list.into_iter()),
+            Value::ByteArray(arr) => Inner::ByteArray(arr.into_inner().into_iter()),
+            Value::IntArray(arr) => Inner::IntArray(arr.into_inner().into_iter()),
+            Value::LongArray(arr) => Inner::LongArray(arr.into_inner().into_iter()),
+            _ => Inner::Empty,
+        };
+        IntoIter { inner }
+    }
+}
@@ -1,20 +1,57 @@
 mod array_serializer;
+mod chunks;
+mod coerce;
 mod de;
+mod iter;
+mod pretty;
+mod query;
 mod ser;
+#[cfg(feature = "shared-value")]
+mod shared;
 
-use std::collections::HashMap;
+use std::{collections::HashMap, hash::Hasher};
 
 use serde::{serde_if_integer128, Deserialize, Serialize};
 
-use crate::{error::Error, ByteArray, IntArray, LongArray};
+use crate::{error::Error, ser::WriteNbt, ByteArray, IntArray, LongArray, Tag};
 
+pub use self::chunks::ByteChunks;
+pub use self::iter::IntoIter;
+pub use self::pretty::PrettyOpts;
 pub use self::ser::Serializer;
+#[cfg(feature = "shared-value")]
+pub use self::shared::SharedValue;
 
 /// Value is a complete NBT value. It owns its data. Compounds and Lists are
 /// resursively deserialized. This type takes care to preserve all the
 /// information from the original NBT, with the exception of the name of the
 /// root compound (which is usually the empty string).
 ///
+/// Another exception is the element type of empty lists. Depending on the
+/// version, Minecraft writes an empty list with either the `End` tag or a real
+/// element tag. A `Value::List` does not record the element type, so both
+/// forms deserialize to the same value, and an empty list is always serialized
+/// with the `End` tag. This means values from different versions can be
+/// compared directly.
+///
+/// A `Value` can also be used with `#[serde(flatten)]` to capture every key of
+/// a compound that a struct does not model, such as data added by mods. The
+/// captured keys are a `Value::Compound` and are written back out with their
+/// original tags when the struct is serialized, though not in their original
+/// order.
+///
+/// ```
+/// # use fastnbt::Value;
+/// # use serde::{Deserialize, Serialize};
+/// #[derive(Serialize, Deserialize)]
+/// struct Chunk {
+///     #[serde(rename = "DataVersion")]
+///     data_version: i32,
+///     #[serde(flatten)]
+///     extra: Value,
+/// }
+/// ```
+///
 /// ```no_run
 /// # use fastnbt::Value;
 /// # use fastnbt::error::Result;
@@ -117,6 +154,38 @@ impl<'a> arbitrary::Arbitrary<'a> for Value {
 }
 
 impl Value {
+    /// Creates a [`Value::Compound`] from key-value pairs. Later pairs replace
+    /// earlier ones with the same key.
+    ///
+    /// ```
+    /// # use fastnbt::{nbt, Value};
+    /// let v = Value::compound_from([("x", 1i32.into()), ("name", "foo".into())]);
+    /// assert_eq!(v, nbt!({"x": 1, "name": "foo"}));
+    /// ```
+    pub fn compound_from<K, I>(entries: I) -> Value
+    where
+        K: Into<String>,
+        I: IntoIterator<Item = (K, Value)>,
+    {
+        Value::Compound(entries.into_iter().map(|(k, v)| (k.into(), v)).collect())
+    }
+
+    /// Creates a [`Value::List`] from its elements. NBT lists can only hold
+    /// one type of value, so the elements should all be the same variant.
+    ///
+    /// ```
+    /// # use fastnbt::{nbt, Value};
+    /// let v = Value::list_from([1i8, 2, 3]);
+    /// assert_eq!(v, nbt!([1_i8, 2_i8, 3_i8]));
+    /// ```
+    pub fn list_from<T, I>(elements: I) -> Value
+    where
+        T: Into<Value>,
+        I: IntoIterator<Item = T>,
+    {
+        Value::List(elements.into_iter().map(Into::into).collect())
+    }
+
     pub fn as_i64(&self) -> Option<i64> {
         match *self {
             Value::Byte(v) => Some(v as i64),
@@ -159,6 +228,732 @@ impl Value {
             _ => None,
         }
     }
+
+    /// Looks up a value by a JSON Pointer-like path.
+    ///
+    /// The pointer is a series of `/` separated tokens, each being a key into a
+    /// compound or an index into a list. As in [RFC
+    /// 6901](https://tools.ietf.org/html/rfc6901), `~1` and `~0` in a token
+    /// are unescaped to `/` and `~` respectively. The empty string points to
+    /// the value itself.
+    ///
+    /// NBT arrays such as [`IntArray`] cannot be indexed into, as their
+    /// elements are not `Value`s.
+    ///
+    /// ```
+    /// # use fastnbt::nbt;
+    /// let data = nbt!({
+    ///     "Level": {
+    ///         "Sections": [{"Y": 1_i8}, {"Y": 2_i8}],
+    ///     },
+    /// });
+    ///
+    /// assert_eq!(data.pointer("/Level/Sections/1/Y"), Some(&nbt!(2_i8)));
+    /// assert_eq!(data.pointer("/Level/Missing"), None);
+    /// ```
+    pub fn pointer(&self, pointer: &str) -> Option<&Value> {
+        if pointer.is_empty() {
+            return Some(self);
+        }
+        if !pointer.starts_with('/') {
+            return None;
+        }
+        pointer.split('/').skip(1).map(unescape_token).try_fold(
+            self,
+            |target, token| match target {
+                Value::Compound(map) => map.get(&token),
+                Value::List(list) => parse_index(&token).and_then(|i| list.get(i)),
+                _ => None,
+            },
+        )
+    }
+
+    /// Looks up a value by a JSON Pointer-like path and returns a mutable
+    /// reference to it. See [`Value::pointer`] for the syntax of the path.
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut Value> {
+        if pointer.is_empty() {
+            return Some(self);
+        }
+        if !pointer.starts_with('/') {
+            return None;
+        }
+        pointer.split('/').skip(1).map(unescape_token).try_fold(
+            self,
+            |target, token| match target {
+                Value::Compound(map) => map.get_mut(&token),
+                Value::List(list) => parse_index(&token).and_then(move |i| list.get_mut(i)),
+                _ => None,
+            },
+        )
+    }
+
+    /// Whether a value exists at the given path. See [`Value::pointer`] for the
+    /// syntax of the path.
+    ///
+    /// ```
+    /// # use fastnbt::nbt;
+    /// let data = nbt!({"Data": {"Version": 3120}});
+    ///
+    /// assert!(data.path_exists("/Data/Version"));
+    /// assert!(!data.path_exists("/Data/Name"));
+    /// ```
+    pub fn path_exists(&self, pointer: &str) -> bool {
+        self.pointer(pointer).is_some()
+    }
+
+    /// Get the value of `key` in this compound, with an error describing the
+    /// problem if this is not a compound or does not contain `key`.
+    ///
+    /// ```
+    /// # use fastnbt::nbt;
+    /// let data = nbt!({"Data": {"Version": 3120}});
+    ///
+    /// assert_eq!(data.try_get("Data")?.try_get("Version")?, &nbt!(3120));
+    /// assert_eq!(
+    ///     data.try_get("Level").unwrap_err().to_string(),
+    ///     "no key 'Level' in compound"
+    /// );
+    /// # Ok::<(), fastnbt::error::Error>(())
+    /// ```
+    pub fn try_get(&self, key: &str) -> Result<&Value, Error> {
+        match self {
+            Value::Compound(map) => map
+                .get(key)
+                .ok_or_else(|| Error::bespoke(format!("no key '{}' in compound", key))),
+            _ => Err(Error::bespoke(format!(
+                "cannot get key '{}' of {}, expected compound",
+                key,
+                self.tag()
+            ))),
+        }
+    }
+
+    /// Get the element at `index` in this list, with an error describing the
+    /// problem if this is not a list or the index is out of bounds.
+    ///
+    /// ```
+    /// # use fastnbt::nbt;
+    /// let data = nbt!([1, 2, 3]);
+    ///
+    /// assert_eq!(data.try_index(1)?, &nbt!(2));
+    /// assert_eq!(
+    ///     data.try_index(5).unwrap_err().to_string(),
+    ///     "index 5 out of bounds (len 3)"
+    /// );
+    /// # Ok::<(), fastnbt::error::Error>(())
+    /// ```
+    pub fn try_index(&self, index: usize) -> Result<&Value, Error> {
+        match self {
+            Value::List(list) => list.get(index).ok_or_else(|| {
+                Error::bespoke(format!(
+                    "index {} out of bounds (len {})",
+                    index,
+                    list.len()
+                ))
+            }),
+            _ => Err(Error::bespoke(format!(
+                "cannot index {}, expected list",
+                self.tag()
+            ))),
+        }
+    }
+
+    /// Sort the list at `pointer` by the key `key` returns for each element.
+    /// This is for lists that are logically unordered, such as the block
+    /// entities of a chunk, so they can be put in a canonical order before
+    /// comparing or hashing them. See [`Value::pointer`] for the syntax of the
+    /// path.
+    ///
+    /// The sort is stable, so elements with equal keys keep their order. The
+    /// list is serialized in its new order, so this changes the data written
+    /// to disk.
+    ///
+    /// ```
+    /// # use fastnbt::{nbt, Value};
+    /// let mut chunk = nbt!({"BlockEntities": [{"id": "sign"}, {"id": "chest"}]});
+    ///
+    /// chunk.sort_list_at("/BlockEntities", |v| {
+    ///     v.pointer("/id").and_then(Value::as_str).map(str::to_owned)
+    /// })?;
+    /// assert_eq!(chunk, nbt!({"BlockEntities": [{"id": "chest"}, {"id": "sign"}]}));
+    /// # Ok::<(), fastnbt::error::Error>(())
+    /// ```
+    pub fn sort_list_at<K: Ord>(
+        &mut self,
+        pointer: &str,
+        key: impl FnMut(&Value) -> K,
+    ) -> Result<(), Error> {
+        match self.pointer_mut(pointer) {
+            Some(Value::List(list)) => {
+                list.sort_by_key(key);
+                Ok(())
+            }
+            Some(v) => Err(Error::bespoke(format!(
+                "cannot sort {} at '{}', expected list",
+                v.tag(),
+                pointer
+            ))),
+            None => Err(Error::bespoke(format!("no value at '{}'", pointer))),
+        }
+    }
+
+    /// Estimate the number of heap bytes used by this value, including
+    /// everything nested inside it. This counts the capacity of strings,
+    /// arrays, lists and compounds rather than just their length, and is
+    /// useful for bounding caches of values by memory.
+    ///
+    /// This does not include the size of the `Value` itself, which is
+    /// `std::mem::size_of::<Value>()`. The overhead of a compound's hash table
+    /// is an approximation.
+    ///
+    /// ```
+    /// # use fastnbt::Value;
+    /// let v = Value::String(String::with_capacity(100));
+    /// assert_eq!(v.memory_size(), 100);
+    /// ```
+    pub fn memory_size(&self) -> usize {
+        use std::mem::size_of;
+
+        match self {
+            Value::Byte(_)
+            | Value::Short(_)
+            | Value::Int(_)
+            | Value::Long(_)
+            | Value::Float(_)
+            | Value::Double(_) => 0,
+            Value::String(s) => s.capacity(),
+            Value::ByteArray(a) => a.capacity() * size_of::<i8>(),
+            Value::IntArray(a) => a.capacity() * size_of::<i32>(),
+            Value::LongArray(a) => a.capacity() * size_of::<i64>(),
+            Value::List(list) => {
+                list.capacity() * size_of::<Value>()
+                    + list.iter().map(Value::memory_size).sum::<usize>()
+            }
+            Value::Compound(map) => {
+                // Each bucket holds an entry plus a byte of control data.
+                map.capacity() * (size_of::<(String, Value)>() + 1)
+                    + map
+                        .iter()
+                        .map(|(k, v)| k.capacity() + v.memory_size())
+                        .sum::<usize>()
+            }
+        }
+    }
+
+    /// Returns the number of bytes each child of this value contributes to
+    /// its serialized NBT, largest first. This is useful for finding which
+    /// parts of a structure dominate its size, and so are worth compressing
+    /// or pruning.
+    ///
+    /// For a compound the entries are keyed by name and include the tag and
+    /// name of each entry. For a list they are keyed by index and include
+    /// only the payload. Other values have no children and return an empty
+    /// list. Call this on a child to break its size down further.
+    ///
+    /// ```
+    /// # use fastnbt::nbt;
+    /// let v = nbt!({"Sections": [B; 0, 0, 0, 0], "y": 1_i8});
+    /// assert_eq!(
+    ///     v.size_breakdown(),
+    ///     [("Sections".to_string(), 19), ("y".to_string(), 5)]
+    /// );
+    /// ```
+    pub fn size_breakdown(&self) -> Vec<(String, usize)> {
+        let mut sizes: Vec<(String, usize)> = match self {
+            Value::Compound(map) => map
+                .iter()
+                .map(|(k, v)| (k.clone(), 3 + java_str_len(k) + payload_size(v)))
+                .collect(),
+            Value::List(list) => list
+                .iter()
+                .enumerate()
+                .map(|(i, v)| (i.to_string(), payload_size(v)))
+                .collect(),
+            _ => Vec::new(),
+        };
+        sizes.sort_unstable_by(|(ka, a), (kb, b)| b.cmp(a).then_with(|| ka.cmp(kb)));
+        sizes
+    }
+
+    /// Returns the number of bytes [`to_bytes`][`crate::to_bytes`] writes for
+    /// this value as the root compound, for example to reserve a buffer with
+    /// [`to_bytes_with_capacity`][`crate::to_bytes_with_capacity`] or to
+    /// allocate space for the data ahead of writing it.
+    ///
+    /// This is exact for a compound that serializes successfully. For any
+    /// other value it is the size the value would be as a root.
+    ///
+    /// ```
+    /// # use fastnbt::nbt;
+    /// let v = nbt!({"Pos": [1.0, 2.0], "id": "cow"});
+    /// assert_eq!(v.serialized_size(), fastnbt::to_bytes(&v)?.len());
+    /// # Ok::<(), fastnbt::error::Error>(())
+    /// ```
+    pub fn serialized_size(&self) -> usize {
+        // Tag and empty name of the root.
+        3 + payload_size(self)
+    }
+
+    /// Returns a hash of the structure of this value: its tags, the keys of
+    /// its compounds and how they nest, but not the numbers, strings and
+    /// arrays in it. Values with the same structure, such as documents of the
+    /// same schema, hash the same, so this is a cheap way to group them.
+    ///
+    /// The hash does not depend on the order of compound entries, and is the
+    /// same across runs and platforms. Lists are hashed element by element,
+    /// so lists of different lengths differ, as they do for
+    /// [`columnar::encode`][`crate::columnar::encode`]. As with any hash,
+    /// different structures can collide.
+    ///
+    /// ```
+    /// # use fastnbt::nbt;
+    /// let cow = nbt!({"id": "cow", "Pos": [1.0, 2.0, 3.0]});
+    /// let pig = nbt!({"Pos": [4.0, 5.0, 6.0], "id": "pig"});
+    /// let item = nbt!({"id": "stone", "Count": 1_i8});
+    /// assert_eq!(cow.shape_hash(), pig.shape_hash());
+    /// assert_ne!(cow.shape_hash(), item.shape_hash());
+    /// ```
+    pub fn shape_hash(&self) -> u64 {
+        let mut hasher = Fnv::new();
+        hash_shape(self, &mut hasher);
+        hasher.finish()
+    }
+
+    /// The tag of the value.
+    pub fn tag(&self) -> Tag {
+        match self {
+            Value::Byte(_) => Tag::Byte,
+            Value::Short(_) => Tag::Short,
+            Value::Int(_) => Tag::Int,
+            Value::Long(_) => Tag::Long,
+            Value::Float(_) => Tag::Float,
+            Value::Double(_) => Tag::Double,
+            Value::String(_) => Tag::String,
+            Value::ByteArray(_) => Tag::ByteArray,
+            Value::IntArray(_) => Tag::IntArray,
+            Value::LongArray(_) => Tag::LongArray,
+            Value::List(_) => Tag::List,
+            Value::Compound(_) => Tag::Compound,
+        }
+    }
+
+    /// Returns a copy of this value with `${name}` placeholders replaced by
+    /// values from `vars`. This makes it easy to use a `Value` as a template
+    /// for generating many similar values.
+    ///
+    /// * A string value that consists only of a placeholder, such as
+    ///   `"${x}"`, is replaced by the variable's value, whatever type it is.
+    /// * Placeholders within a larger string, or within a compound key, are
+    ///   replaced only if the variable is a [`Value::String`].
+    ///
+    /// Placeholders for variables that are not in `vars`, or that cannot be
+    /// substituted, are left as they are.
+    ///
+    /// ```
+    /// # use fastnbt::{nbt, Value};
+    /// # use std::collections::HashMap;
+    /// let template = nbt!({
+    ///     "id": "minecraft:${mob}",
+    ///     "Pos": ["${x}", 64.0, "${z}"],
+    /// });
+    ///
+    /// let mut vars = HashMap::new();
+    /// vars.insert("mob".to_string(), nbt!("zombie"));
+    /// vars.insert("x".to_string(), nbt!(10.5));
+    /// vars.insert("z".to_string(), nbt!(-3.0));
+    ///
+    /// assert_eq!(
+    ///     template.substitute(&vars),
+    ///     nbt!({
+    ///         "id": "minecraft:zombie",
+    ///         "Pos": [10.5, 64.0, -3.0],
+    ///     })
+    /// );
+    /// ```
+    pub fn substitute(&self, vars: &HashMap<String, Value>) -> Value {
+        match self {
+            Value::String(s) => {
+                let whole = s
+                    .strip_prefix("${")
+                    .and_then(|rest| rest.strip_suffix('}'))
+                    .filter(|name| !name.contains('}'))
+                    .and_then(|name| vars.get(name));
+
+                match whole {
+                    Some(v) => v.clone(),
+                    None => Value::String(substitute_str(s, vars)),
+                }
+            }
+            Value::List(list) => Value::List(list.iter().map(|v| v.substitute(vars)).collect()),
+            Value::Compound(map) => Value::Compound(
+                map.iter()
+                    .map(|(k, v)| (substitute_str(k, vars), v.substitute(vars)))
+                    .collect(),
+            ),
+            other => other.clone(),
+        }
+    }
+
+    /// Returns the paths at which `other` differs from this value, in sorted
+    /// order. This is a lightweight alternative to a full diff, useful for
+    /// logging what changed between two versions of some data.
+    ///
+    /// Paths are the compound keys and list indices leading to each
+    /// difference, joined by `.`. A key present in only one of the two
+    /// compounds, or a list element present in only one of the two lists, is
+    /// reported at its own path. Values of different types, and NBT arrays
+    /// that differ, are reported as a whole. If the values are of different
+    /// types at the top level, the single path reported is the empty string.
+    ///
+    /// ```
+    /// # use fastnbt::nbt;
+    /// let before = nbt!({"Health": 20.0_f32, "Pos": [1.0, 64.0, 2.0], "Name": "Steve"});
+    /// let after = nbt!({"Health": 15.0_f32, "Pos": [1.0, 64.0, 7.0], "Xp": 3});
+    ///
+    /// assert_eq!(before.changed_paths(&after), ["Health", "Name", "Pos.2", "Xp"]);
+    /// ```
+    pub fn changed_paths(&self, other: &Value) -> Vec<String> {
+        let mut paths = Vec::new();
+        changed_paths(self, other, &mut String::new(), &mut paths);
+        paths.sort_unstable();
+        paths
+    }
+
+    /// Compare with `other`, allowing floats to differ by up to `epsilon`.
+    /// This is useful for checking data that has been through a conversion
+    /// that may round floats, such as to and from a text format.
+    ///
+    /// Floats are compared by absolute difference. A [`Value::Float`] is
+    /// widened to `f64` first, so the same `epsilon` works for both, but a
+    /// `Float` never equals a [`Value::Double`]. Infinities equal themselves
+    /// and NaN equals nothing, as with `==`. Everything else, including the
+    /// tags of values and the contents of NBT arrays, must be exactly equal.
+    ///
+    /// ```
+    /// # use fastnbt::nbt;
+    /// let before = nbt!({"Pos": [1.0, 64.0], "Health": 20.0_f32});
+    /// let after = nbt!({"Pos": [1.0000001, 64.0], "Health": 19.999998_f32});
+    ///
+    /// assert!(before != after);
+    /// assert!(before.approx_eq(&after, 1e-5));
+    /// assert!(!before.approx_eq(&after, 1e-9));
+    /// ```
+    pub fn approx_eq(&self, other: &Value, epsilon: f64) -> bool {
+        let close = |a: f64, b: f64| a == b || (a - b).abs() <= epsilon;
+        match (self, other) {
+            (Value::Float(a), Value::Float(b)) => close(*a as f64, *b as f64),
+            (Value::Double(a), Value::Double(b)) => close(*a, *b),
+            (Value::List(a), Value::List(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.approx_eq(b, epsilon))
+            }
+            (Value::Compound(a), Value::Compound(b)) => {
+                a.len() == b.len()
+                    && a.iter()
+                        .all(|(key, a)| b.get(key).is_some_and(|b| a.approx_eq(b, epsilon)))
+            }
+            (a, b) => a == b,
+        }
+    }
+
+    /// Converts every NBT array within this value, including this value
+    /// itself, into an equivalent list: a [`Value::ByteArray`] becomes a
+    /// list of [`Value::Byte`], and so on. This is useful for exchanging data
+    /// with tools that do not understand arrays.
+    ///
+    /// ```
+    /// # use fastnbt::nbt;
+    /// let mut v = nbt!({"heights": [I; 1, 2]});
+    /// v.arrays_to_lists();
+    /// assert_eq!(v, nbt!({"heights": [1, 2]}));
+    /// ```
+    pub fn arrays_to_lists(&mut self) {
+        let list = match self {
+            Value::ByteArray(a) => a.iter().map(|v| Value::Byte(*v)).collect(),
+            Value::IntArray(a) => a.iter().map(|v| Value::Int(*v)).collect(),
+            Value::LongArray(a) => a.iter().map(|v| Value::Long(*v)).collect(),
+            Value::List(list) => return list.iter_mut().for_each(Value::arrays_to_lists),
+            Value::Compound(map) => return map.values_mut().for_each(Value::arrays_to_lists),
+            _ => return,
+        };
+        *self = Value::List(list);
+    }
+
+    /// Recursively removes empty compounds and empty lists from within this
+    /// value, including any that only become empty once their own empty
+    /// children are removed. If `arrays` is true, empty NBT arrays are removed
+    /// too. This is useful for minimizing output that contains meaningless
+    /// empty containers.
+    ///
+    /// This value itself is never removed, so it may be left empty.
+    ///
+    /// ```
+    /// # use fastnbt::nbt;
+    /// let mut v = nbt!({"a": {"b": {}, "c": []}, "d": [{}, 1], "e": [I;]});
+    /// v.prune_empty(false);
+    /// assert_eq!(v, nbt!({"d": [1], "e": [I;]}));
+    ///
+    /// v.prune_empty(true);
+    /// assert_eq!(v, nbt!({"d": [1]}));
+    /// ```
+    pub fn prune_empty(&mut self, arrays: bool) {
+        match self {
+            Value::Compound(map) => map.retain(|_, v| {
+                v.prune_empty(arrays);
+                !v.is_prunable(arrays)
+            }),
+            Value::List(list) => list.retain_mut(|v| {
+                v.prune_empty(arrays);
+                !v.is_prunable(arrays)
+            }),
+            _ => {}
+        }
+    }
+
+    fn is_prunable(&self, arrays: bool) -> bool {
+        match self {
+            Value::Compound(map) => map.is_empty(),
+            Value::List(list) => list.is_empty(),
+            Value::ByteArray(a) => arrays && a.is_empty(),
+            Value::IntArray(a) => arrays && a.is_empty(),
+            Value::LongArray(a) => arrays && a.is_empty(),
+            _ => false,
+        }
+    }
+
+    /// Converts lists within this value, including this value itself, into
+    /// NBT arrays where possible. This is the reverse of
+    /// [`arrays_to_lists`][`Value::arrays_to_lists`].
+    ///
+    /// This is lossy in that it cannot tell which lists were arrays to begin
+    /// with. Only non-empty lists whose elements are all [`Value::Byte`], all
+    /// [`Value::Int`] or all [`Value::Long`] are converted. Empty lists, and
+    /// lists of any other type, are left as they are.
+    ///
+    /// ```
+    /// # use fastnbt::nbt;
+    /// let mut v = nbt!({"heights": [1, 2], "names": ["a"], "empty": []});
+    /// v.lists_to_arrays();
+    /// assert_eq!(v, nbt!({"heights": [I; 1, 2], "names": ["a"], "empty": []}));
+    /// ```
+    pub fn lists_to_arrays(&mut self) {
+        let array = match self {
+            Value::List(list) => {
+                list.iter_mut().for_each(Value::lists_to_arrays);
+                match list.first() {
+                    Some(Value::Byte(_)) => list
+                        .iter()
+                        .map(|v| match v {
+                            Value::Byte(v) => Some(*v),
+                            _ => None,
+                        })
+                        .collect::<Option<_>>()
+                        .map(|data| Value::ByteArray(ByteArray::new(data))),
+                    Some(Value::Int(_)) => list
+                        .iter()
+                        .map(|v| match v {
+                            Value::Int(v) => Some(*v),
+                            _ => None,
+                        })
+                        .collect::<Option<_>>()
+                        .map(|data| Value::IntArray(IntArray::new(data))),
+                    Some(Value::Long(_)) => list
+                        .iter()
+                        .map(|v| match v {
+                            Value::Long(v) => Some(*v),
+                            _ => None,
+                        })
+                        .collect::<Option<_>>()
+                        .map(|data| Value::LongArray(LongArray::new(data))),
+                    _ => None,
+                }
+            }
+            Value::Compound(map) => return map.values_mut().for_each(Value::lists_to_arrays),
+            _ => return,
+        };
+        if let Some(array) = array {
+            *self = array;
+        }
+    }
+}
+
+/// Size of a value's serialized payload, excluding its tag and name.
+fn payload_size(v: &Value) -> usize {
+    match v {
+        Value::Byte(_) => 1,
+        Value::Short(_) => 2,
+        Value::Int(_) | Value::Float(_) => 4,
+        Value::Long(_) | Value::Double(_) => 8,
+        Value::String(s) => 2 + java_str_len(s),
+        Value::ByteArray(a) => 4 + a.len(),
+        Value::IntArray(a) => 4 + a.len() * 4,
+        Value::LongArray(a) => 4 + a.len() * 8,
+        // Element tag and length, then the payloads.
+        Value::List(list) => 5 + list.iter().map(payload_size).sum::<usize>(),
+        // Each entry, then the end tag.
+        Value::Compound(map) => {
+            1 + map
+                .iter()
+                .map(|(k, v)| 3 + java_str_len(k) + payload_size(v))
+                .sum::<usize>()
+        }
+    }
+}
+
+/// Feed the structure of `v` to `hasher`. See [`Value::shape_hash`].
+fn hash_shape(v: &Value, hasher: &mut Fnv) {
+    hasher.write_u8(v.tag() as u8);
+    match v {
+        Value::List(list) => {
+            hasher.write_usize(list.len());
+            for v in list {
+                hash_shape(v, hasher);
+            }
+        }
+        Value::Compound(map) => {
+            // Entries are hashed separately and summed, so that the order of
+            // the map doesn't matter. The key is followed by 0xff, which never
+            // appears in UTF-8, to separate it from the value.
+            let entries = map
+                .iter()
+                .map(|(k, v)| {
+                    let mut entry = Fnv::new();
+                    entry.write(k.as_bytes());
+                    entry.write_u8(0xff);
+                    hash_shape(v, &mut entry);
+                    mix(entry.finish())
+                })
+                .fold(0u64, u64::wrapping_add);
+            hasher.write_usize(map.len());
+            hasher.write_u64(entries);
+        }
+        _ => {}
+    }
+}
+
+/// 64-bit FNV-1a. Unlike the standard library's hasher, its output is fixed,
+/// so [`Value::shape_hash`] can be stored.
+struct Fnv(u64);
+
+impl Fnv {
+    fn new() -> Self {
+        Fnv(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv {
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= *b as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    // Integers are written little endian whatever the platform, and usize as
+    // 64 bits.
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Spread the bits of an FNV hash, which are poorly mixed, before summing it
+/// with others. This is the finalizer from MurmurHash3.
+fn mix(mut h: u64) -> u64 {
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    h ^ (h >> 33)
+}
+
+/// Length of `s` in Java's Modified UTF-8, worked out without converting it.
+/// Characters outside the Basic Multilingual Plane take six bytes rather than
+/// four, and the null character takes two rather than one.
+fn java_str_len(s: &str) -> usize {
+    s.len()
+        + s.bytes()
+            .map(|b| match b {
+                0 => 1,
+                0xf0.. => 2,
+                _ => 0,
+            })
+            .sum::<usize>()
+}
+
+fn changed_paths(a: &Value, b: &Value, path: &mut String, out: &mut Vec<String>) {
+    let mut child = |path: &mut String, key: &str, a: Option<&Value>, b: Option<&Value>| {
+        let len = path.len();
+        if !path.is_empty() {
+            path.push('.');
+        }
+        path.push_str(key);
+        match (a, b) {
+            (Some(a), Some(b)) => changed_paths(a, b, path, out),
+            _ => out.push(path.clone()),
+        }
+        path.truncate(len);
+    };
+
+    match (a, b) {
+        (Value::Compound(a), Value::Compound(b)) => {
+            for (key, v) in a {
+                child(path, key, Some(v), b.get(key));
+            }
+            for (key, v) in b.iter().filter(|(key, _)| !a.contains_key(*key)) {
+                child(path, key, None, Some(v));
+            }
+        }
+        (Value::List(a), Value::List(b)) => {
+            for i in 0..a.len().max(b.len()) {
+                child(path, &i.to_string(), a.get(i), b.get(i));
+            }
+        }
+        (a, b) if a != b => out.push(path.clone()),
+        _ => {}
+    }
+}
+
+fn substitute_str(s: &str, vars: &HashMap<String, Value>) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+
+    while let Some(start) = rest.find("${") {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        // An unterminated placeholder is copied verbatim below.
+        let end = match rest.find('}') {
+            Some(end) => end,
+            None => break,
+        };
+
+        match vars.get(&rest[2..end]) {
+            Some(Value::String(v)) => out.push_str(v),
+            _ => out.push_str(&rest[..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+fn unescape_token(token: &str) -> String {
+    token.replace("~1", "/").replace("~0", "~")
+}
+
+fn parse_index(s: &str) -> Option<usize> {
+    if s.starts_with('+') || (s.starts_with('0') && s.len() != 1) {
+        return None;
+    }
+    s.parse().ok()
 }
 
 // ------------- From<T> impls -------------
@@ -447,3 +1242,40 @@ where
 {
     T::deserialize(value)
 }
+
+/// Deserialize a [`Value`] from some NBT data, along with the name of the root
+/// compound. [`from_bytes`][`crate::from_bytes`] discards this name, which is
+/// usually empty, but some files such as older `level.dat` files use it.
+///
+/// ```
+/// # use fastnbt::{nbt, value};
+/// let data = value::to_bytes_named("Data", &nbt!({"a": 1}))?;
+/// let (name, v) = value::from_bytes_named(&data)?;
+///
+/// assert_eq!(name, "Data");
+/// assert_eq!(v, nbt!({"a": 1}));
+/// # Ok::<(), fastnbt::error::Error>(())
+/// ```
+pub fn from_bytes_named(data: &[u8]) -> Result<(String, Value), Error> {
+    let mut reader = crate::de::NbtReader::new(data);
+    if reader.read_tag()? != Tag::Compound {
+        return Err(Error::no_root_compound());
+    }
+    let name = reader.read_name()?;
+
+    Ok((name, crate::from_bytes(data)?))
+}
+
+/// Serialize a [`Value`] into NBT data, giving the root compound the name
+/// `name`. This is the counterpart of [`from_bytes_named`].
+pub fn to_bytes_named(name: &str, value: &Value) -> Result<Vec<u8>, Error> {
+    // The serializer always writes an unnamed root: the compound tag followed
+    // by a zero length name.
+    let unnamed = crate::to_bytes(value)?;
+
+    let mut data = Vec::with_capacity(unnamed.len() + name.len());
+    data.write_tag(Tag::Compound)?;
+    data.write_size_prefixed_str(name)?;
+    data.extend_from_slice(&unnamed[3..]);
+    Ok(data)
+}
@@ -0,0 +1,216 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Write},
+};
+
+use super::Value;
+
+/// Options for [`Value::to_pretty_string_with`]. This object follows a
+/// builder pattern.
+///
+/// By default everything is printed, indented by two spaces.
+#[derive(Debug, Clone)]
+pub struct PrettyOpts {
+    indent: usize,
+    max_depth: Option<usize>,
+    max_array_elements: Option<usize>,
+}
+
+impl PrettyOpts {
+    /// Create new options.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Set the number of spaces to indent each level by.
+    pub fn indent(mut self, spaces: usize) -> Self {
+        self.indent = spaces;
+        self
+    }
+
+    /// Set how many levels of compounds and lists are printed. Anything nested
+    /// deeper is collapsed to `{...}` or `[...]`. A depth of zero collapses
+    /// even the value being printed.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Set how many elements of each list and NBT array are printed. Any
+    /// further elements are replaced by a count of how many were omitted.
+    pub fn max_array_elements(mut self, count: usize) -> Self {
+        self.max_array_elements = Some(count);
+        self
+    }
+}
+
+impl Default for PrettyOpts {
+    fn default() -> Self {
+        Self {
+            indent: 2,
+            max_depth: None,
+            max_array_elements: None,
+        }
+    }
+}
+
+impl Value {
+    /// Format this value as indented, SNBT-like text, limited according to
+    /// `opts`. This is useful for logging large values such as entire chunks.
+    ///
+    /// Compound keys are printed in sorted order so that the output is
+    /// deterministic.
+    ///
+    /// ```
+    /// # use fastnbt::{nbt, value::PrettyOpts};
+    /// let chunk = nbt!({
+    ///     "Level": {
+    ///         "Sections": [{"Y": 0_i8}],
+    ///     },
+    ///     "Heights": [L; 1, 2, 3, 4],
+    /// });
+    ///
+    /// let opts = PrettyOpts::new().max_depth(2).max_array_elements(2);
+    /// assert_eq!(
+    ///     chunk.to_pretty_string_with(&opts),
+    ///     "{\n  Heights: [L; 1L, 2L, ... 2 more],\n  Level: {\n    Sections: [...],\n  },\n}"
+    /// );
+    /// ```
+    pub fn to_pretty_string_with(&self, opts: &PrettyOpts) -> String {
+        let mut out = String::new();
+        Pretty {
+            opts,
+            out: &mut out,
+        }
+        .value(self, 0)
+        .expect("writing to a String cannot fail");
+        out
+    }
+}
+
+struct Pretty<'a, W> {
+    opts: &'a PrettyOpts,
+    out: W,
+}
+
+impl<'a, W: Write> Pretty<'a, W> {
+    fn value(&mut self, value: &Value, depth: usize) -> fmt::Result {
+        match value {
+            Value::Byte(v) => write!(self.out, "{}b", v),
+            Value::Short(v) => write!(self.out, "{}s", v),
+            Value::Int(v) => write!(self.out, "{}", v),
+            Value::Long(v) => write!(self.out, "{}L", v),
+            Value::Float(v) => write!(self.out, "{}f", v),
+            Value::Double(v) => write!(self.out, "{}d", v),
+            Value::String(v) => self.string(v),
+            Value::ByteArray(v) => self.array("B", v.iter().map(|v| format!("{}b", v))),
+            Value::IntArray(v) => self.array("I", v.iter()),
+            Value::LongArray(v) => self.array("L", v.iter().map(|v| format!("{}L", v))),
+            Value::List(v) => self.list(v, depth),
+            Value::Compound(v) => self.compound(v, depth),
+        }
+    }
+
+    fn collapsed(&self, depth: usize) -> bool {
+        self.opts.max_depth.is_some_and(|max| depth >= max)
+    }
+
+    fn array<T: Display>(
+        &mut self,
+        prefix: &str,
+        elements: impl ExactSizeIterator<Item = T>,
+    ) -> fmt::Result {
+        let len = elements.len();
+        let shown = self.opts.max_array_elements.unwrap_or(len).min(len);
+
+        write!(self.out, "[{};", prefix)?;
+        for (i, el) in elements.take(shown).enumerate() {
+            let sep = if i == 0 { " " } else { ", " };
+            write!(self.out, "{}{}", sep, el)?;
+        }
+        if shown < len {
+            let sep = if shown == 0 { " " } else { ", " };
+            write!(self.out, "{}... {} more", sep, len - shown)?;
+        }
+        self.out.write_char(']')
+    }
+
+    fn list(&mut self, list: &[Value], depth: usize) -> fmt::Result {
+        if list.is_empty() {
+            return self.out.write_str("[]");
+        }
+        if self.collapsed(depth) {
+            return self.out.write_str("[...]");
+        }
+
+        let shown = self.opts.max_array_elements.unwrap_or(list.len());
+
+        self.out.write_str("[\n")?;
+        for el in list.iter().take(shown) {
+            self.indent(depth + 1)?;
+            self.value(el, depth + 1)?;
+            self.out.write_str(",\n")?;
+        }
+        if shown < list.len() {
+            self.indent(depth + 1)?;
+            writeln!(self.out, "... {} more", list.len() - shown)?;
+        }
+        self.indent(depth)?;
+        self.out.write_char(']')
+    }
+
+    fn compound(&mut self, map: &HashMap<String, Value>, depth: usize) -> fmt::Result {
+        if map.is_empty() {
+            return self.out.write_str("{}");
+        }
+        if self.collapsed(depth) {
+            return self.out.write_str("{...}");
+        }
+
+        let mut entries: Vec<_> = map.iter().collect();
+        entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+
+        self.out.write_str("{\n")?;
+        for (key, value) in entries {
+            self.indent(depth + 1)?;
+            self.key(key)?;
+            self.out.write_str(": ")?;
+            self.value(value, depth + 1)?;
+            self.out.write_str(",\n")?;
+        }
+        self.indent(depth)?;
+        self.out.write_char('}')
+    }
+
+    fn key(&mut self, key: &str) -> fmt::Result {
+        let bare = !key.is_empty()
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '+'));
+
+        if bare {
+            self.out.write_str(key)
+        } else {
+            self.string(key)
+        }
+    }
+
+    fn string(&mut self, s: &str) -> fmt::Result {
+        self.out.write_char('"')?;
+        for c in s.chars() {
+            match c {
+                '"' => self.out.write_str("\\\"")?,
+                '\\' => self.out.write_str("\\\\")?,
+                c => self.out.write_char(c)?,
+            }
+        }
+        self.out.write_char('"')
+    }
+
+    fn indent(&mut self, depth: usize) -> fmt::Result {
+        for _ in 0..depth * self.opts.indent {
+            self.out.write_char(' ')?;
+        }
+        Ok(())
+    }
+}
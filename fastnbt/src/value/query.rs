@@ -0,0 +1,227 @@
+use crate::error::Error;
+
+use super::Value;
+
+impl Value {
+    /// Select every value matching a JSONPath-like `query`. This is useful for
+    /// bulk selection, such as the `Y` of every section of a chunk, where
+    /// [`Value::pointer`] can only select a single value.
+    ///
+    /// A query starts with `$`, the value itself, followed by any number of
+    /// these selectors:
+    ///
+    /// * `.name` or `['name']`: the value of a compound with the given key.
+    ///   Names containing `.`, `[`, `]` or quotes need the bracketed form,
+    ///   where `\` escapes the quote or itself.
+    /// * `[n]`: the element of a list at index `n`.
+    /// * `.*` or `[*]`: every value in a compound or list.
+    /// * `..name`, `..*` or `..[...]`: recursive descent, which applies the
+    ///   selector to the value and everything nested inside it.
+    ///
+    /// Selectors that do not match, such as a key on a list, simply select
+    /// nothing. Anything else, including filters, slices and unions, is an
+    /// error. Matches are in document order, with the entries of compounds
+    /// visited in key order.
+    ///
+    /// ```
+    /// # use fastnbt::nbt;
+    /// let chunk = nbt!({
+    ///     "Level": {
+    ///         "Sections": [{"Y": 0_i8}, {"Y": 1_i8}],
+    ///         "Entities": [{"id": "zombie", "Passengers": [{"id": "chicken"}]}],
+    ///     }
+    /// });
+    ///
+    /// assert_eq!(chunk.query("$.Level.Sections[*].Y")?, [&nbt!(0_i8), &nbt!(1_i8)]);
+    /// assert_eq!(chunk.query("$..id")?, [&nbt!("zombie"), &nbt!("chicken")]);
+    /// # Ok::<(), fastnbt::error::Error>(())
+    /// ```
+    pub fn query(&self, query: &str) -> Result<Vec<&Value>, Error> {
+        let segments = parse(query)?;
+
+        let mut current = vec![self];
+        for segment in &segments {
+            let mut next = Vec::new();
+            for v in current {
+                if segment.recursive {
+                    descend(v, &mut |v| select(v, &segment.selector, &mut next));
+                } else {
+                    select(v, &segment.selector, &mut next);
+                }
+            }
+            current = next;
+        }
+
+        Ok(current)
+    }
+}
+
+enum Selector {
+    Key(String),
+    Index(usize),
+    Wildcard,
+}
+
+struct Segment {
+    recursive: bool,
+    selector: Selector,
+}
+
+fn select<'a>(v: &'a Value, selector: &Selector, out: &mut Vec<&'a Value>) {
+    match (v, selector) {
+        (Value::Compound(map), Selector::Key(key)) => out.extend(map.get(key)),
+        (Value::List(list), Selector::Index(i)) => out.extend(list.get(*i)),
+        (Value::Compound(map), Selector::Wildcard) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_unstable_by_key(|&(key, _)| key);
+            out.extend(entries.into_iter().map(|(_, v)| v));
+        }
+        (Value::List(list), Selector::Wildcard) => out.extend(list),
+        _ => {}
+    }
+}
+
+/// Call `f` with `v` and then everything nested in it, in document order.
+fn descend<'a>(v: &'a Value, f: &mut impl FnMut(&'a Value)) {
+    f(v);
+    match v {
+        Value::Compound(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_unstable_by_key(|&(key, _)| key);
+            for (_, v) in entries {
+                descend(v, f);
+            }
+        }
+        Value::List(list) => {
+            for v in list {
+                descend(v, f);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn parse(query: &str) -> Result<Vec<Segment>, Error> {
+    let mut parser = Parser { query, pos: 0 };
+    if !parser.eat('$') {
+        return Err(parser.error("expected '$'"));
+    }
+
+    let mut segments = Vec::new();
+    while let Some(c) = parser.peek() {
+        let segment = match c {
+            '.' => {
+                parser.pos += 1;
+                let recursive = parser.eat('.');
+                let selector = match parser.peek() {
+                    Some('[') if recursive => parser.bracket()?,
+                    Some('*') => {
+                        parser.pos += 1;
+                        Selector::Wildcard
+                    }
+                    _ => Selector::Key(parser.name()?),
+                };
+                Segment {
+                    recursive,
+                    selector,
+                }
+            }
+            '[' => Segment {
+                recursive: false,
+                selector: parser.bracket()?,
+            },
+            _ => return Err(parser.error("expected '.' or '['")),
+        };
+        segments.push(segment);
+    }
+
+    Ok(segments)
+}
+
+struct Parser<'a> {
+    query: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<char> {
+        self.query[self.pos..].chars().next()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        let found = self.peek() == Some(c);
+        if found {
+            self.pos += c.len_utf8();
+        }
+        found
+    }
+
+    fn error(&self, msg: &str) -> Error {
+        Error::bespoke(format!("invalid query: {} at position {}", msg, self.pos))
+    }
+
+    fn name(&mut self) -> Result<String, Error> {
+        let rest = &self.query[self.pos..];
+        let len = rest
+            .find(['.', '[', ']', '\'', '"', '*'])
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.error("expected a name"));
+        }
+        self.pos += len;
+        Ok(rest[..len].to_string())
+    }
+
+    /// Parse a bracketed selector, starting at the `[`.
+    fn bracket(&mut self) -> Result<Selector, Error> {
+        self.pos += 1;
+        let selector = match self.peek() {
+            Some('*') => {
+                self.pos += 1;
+                Selector::Wildcard
+            }
+            Some(quote @ ('\'' | '"')) => {
+                self.pos += 1;
+                Selector::Key(self.quoted(quote)?)
+            }
+            Some(c) if c.is_ascii_digit() => {
+                let rest = &self.query[self.pos..];
+                let len = rest
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(rest.len());
+                let index = rest[..len]
+                    .parse()
+                    .map_err(|_| self.error("index too large"))?;
+                self.pos += len;
+                Selector::Index(index)
+            }
+            _ => return Err(self.error("unsupported selector")),
+        };
+
+        if !self.eat(']') {
+            return Err(self.error("unsupported selector"));
+        }
+        Ok(selector)
+    }
+
+    /// Parse the rest of a quoted name, after the opening quote.
+    fn quoted(&mut self, quote: char) -> Result<String, Error> {
+        let mut name = String::new();
+        let mut chars = self.query[self.pos..].chars();
+        while let Some(c) = chars.next() {
+            self.pos += c.len_utf8();
+            match c {
+                '\\' => match chars.next() {
+                    Some(c) => {
+                        self.pos += c.len_utf8();
+                        name.push(c);
+                    }
+                    None => break,
+                },
+                c if c == quote => return Ok(name),
+                c => name.push(c),
+            }
+        }
+        Err(self.error("unterminated name"))
+    }
+}
@@ -230,7 +230,11 @@ impl<'a> serde::Serializer for &'a mut Serializer {
                 ser: self,
                 tag: Tag::LongArray,
             }),
-            _ => todo!("newtype variants that are not nbt arrays"),
+            _ => {
+                let mut object = HashMap::new();
+                object.insert(variant.to_owned(), value.serialize(self)?);
+                Ok(Value::Compound(object))
+            }
         }
     }
 
@@ -457,7 +461,7 @@ impl serde::ser::SerializeMap for SerializeMap {
 struct MapKeySerializer;
 
 fn key_must_be_a_string() -> Error {
-    Error::bespoke("Key must be a string".to_string())
+    Error::bespoke("Key must be a string")
 }
 
 impl serde::Serializer for MapKeySerializer {
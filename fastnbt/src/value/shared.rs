@@ -0,0 +1,263 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{ByteArray, IntArray, LongArray, Value};
+
+use super::{parse_index, unescape_token};
+
+/// Copy-on-write version of [`Value`], where every compound, list, array and
+/// string is shared behind an [`Arc`]. This requires the `shared-value`
+/// feature.
+///
+/// Cloning a `SharedValue` only clones the `Arc`s of its children, however
+/// large the tree beneath them is, so keeping many versions of a value around,
+/// such as for an editor's undo history, is cheap. Mutating it through
+/// [`pointer_mut`][`SharedValue::pointer_mut`] or similar only copies the
+/// containers along the way to the changed value, and only if they are still
+/// shared with another clone. Everything else stays shared.
+///
+/// This is not free, and [`Value`] is the better choice for data that is
+/// deserialized, read and dropped:
+///
+/// * Each container is a separate allocation with a reference count, and
+///   reading a value through it is an extra indirection.
+/// * Reference counts are atomic, so every clone and drop of a container
+///   costs an atomic operation.
+/// * The first mutation of a shared container copies it, including the
+///   `Arc`s of all its children, so a change deep in a wide tree costs the
+///   width of each container on the path to it.
+///
+/// Convert to and from [`Value`] with [`From`]. It (de)serializes exactly as
+/// the equivalent [`Value`] does.
+///
+/// ```
+/// # use fastnbt::{nbt, value::SharedValue};
+/// let mut current = SharedValue::from(nbt!({"Level": {"xPos": 1}, "Big": [I; 1, 2, 3]}));
+/// let undo = current.clone();
+///
+/// *current.pointer_mut("/Level/xPos").unwrap() = SharedValue::Int(2);
+///
+/// assert_eq!(undo.pointer("/Level/xPos"), Some(&SharedValue::Int(1)));
+/// assert_eq!(current.pointer("/Level/xPos"), Some(&SharedValue::Int(2)));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum SharedValue {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    String(Arc<str>),
+    ByteArray(Arc<ByteArray>),
+    IntArray(Arc<IntArray>),
+    LongArray(Arc<LongArray>),
+    List(Arc<Vec<SharedValue>>),
+    Compound(Arc<HashMap<String, SharedValue>>),
+}
+
+impl SharedValue {
+    /// Looks up a value by a JSON Pointer-like path. See [`Value::pointer`]
+    /// for the syntax of the path.
+    pub fn pointer(&self, pointer: &str) -> Option<&SharedValue> {
+        if pointer.is_empty() {
+            return Some(self);
+        }
+        if !pointer.starts_with('/') {
+            return None;
+        }
+        pointer.split('/').skip(1).map(unescape_token).try_fold(
+            self,
+            |target, token| match target {
+                SharedValue::Compound(map) => map.get(&token),
+                SharedValue::List(list) => parse_index(&token).and_then(|i| list.get(i)),
+                _ => None,
+            },
+        )
+    }
+
+    /// Looks up a value by a JSON Pointer-like path and returns a mutable
+    /// reference to it, copying any containers on the way to it that are
+    /// shared with another clone. See [`Value::pointer`] for the syntax of the
+    /// path.
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut SharedValue> {
+        // Check first, so that nothing is copied for a path that is missing.
+        self.pointer(pointer)?;
+
+        pointer.split('/').skip(1).map(unescape_token).try_fold(
+            self,
+            |target, token| match target {
+                SharedValue::Compound(map) => Arc::make_mut(map).get_mut(&token),
+                SharedValue::List(list) => {
+                    parse_index(&token).and_then(move |i| Arc::make_mut(list).get_mut(i))
+                }
+                _ => None,
+            },
+        )
+    }
+
+    /// The entries of this value if it is a compound, copying them first if
+    /// they are shared with another clone.
+    pub fn as_compound_mut(&mut self) -> Option<&mut HashMap<String, SharedValue>> {
+        match self {
+            SharedValue::Compound(map) => Some(Arc::make_mut(map)),
+            _ => None,
+        }
+    }
+
+    /// The elements of this value if it is a list, copying them first if they
+    /// are shared with another clone.
+    pub fn as_list_mut(&mut self) -> Option<&mut Vec<SharedValue>> {
+        match self {
+            SharedValue::List(list) => Some(Arc::make_mut(list)),
+            _ => None,
+        }
+    }
+
+    /// Make all equal strings in this value share a single allocation, such
+    /// as the block and biome names repeated in every section of a chunk.
+    /// Compound keys are not affected. Only the lists and compounds holding a
+    /// string that is replaced are copied, so clones of this value keep sharing
+    /// the rest.
+    ///
+    /// ```
+    /// # use fastnbt::{nbt, value::SharedValue};
+    /// let mut v = SharedValue::from(nbt!({"a": "minecraft:stone", "b": ["minecraft:stone"]}));
+    /// v.intern_strings();
+    ///
+    /// match (v.pointer("/a"), v.pointer("/b/0")) {
+    ///     (Some(SharedValue::String(a)), Some(SharedValue::String(b))) => {
+    ///         assert!(std::sync::Arc::ptr_eq(a, b))
+    ///     }
+    ///     _ => unreachable!(),
+    /// }
+    /// ```
+    pub fn intern_strings(&mut self) {
+        // Returns the interned value if any string within it was replaced.
+        fn interned(value: &SharedValue, seen: &mut HashSet<Arc<str>>) -> Option<SharedValue> {
+            match value {
+                SharedValue::String(s) => match seen.get(s) {
+                    Some(canonical) if !Arc::ptr_eq(canonical, s) => {
+                        Some(SharedValue::String(canonical.clone()))
+                    }
+                    Some(_) => None,
+                    None => {
+                        seen.insert(s.clone());
+                        None
+                    }
+                },
+                SharedValue::List(list) => {
+                    let mut copy: Option<Vec<SharedValue>> = None;
+                    for (i, v) in list.iter().enumerate() {
+                        if let Some(v) = interned(v, seen) {
+                            copy.get_or_insert_with(|| list.to_vec())[i] = v;
+                        }
+                    }
+                    copy.map(|l| SharedValue::List(Arc::new(l)))
+                }
+                SharedValue::Compound(map) => {
+                    let mut copy: Option<HashMap<String, SharedValue>> = None;
+                    for (k, v) in map.iter() {
+                        if let Some(v) = interned(v, seen) {
+                            copy.get_or_insert_with(|| (**map).clone())
+                                .insert(k.clone(), v);
+                        }
+                    }
+                    copy.map(|m| SharedValue::Compound(Arc::new(m)))
+                }
+                _ => None,
+            }
+        }
+
+        if let Some(v) = interned(self, &mut HashSet::new()) {
+            *self = v;
+        }
+    }
+}
+
+impl From<Value> for SharedValue {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Byte(v) => SharedValue::Byte(v),
+            Value::Short(v) => SharedValue::Short(v),
+            Value::Int(v) => SharedValue::Int(v),
+            Value::Long(v) => SharedValue::Long(v),
+            Value::Float(v) => SharedValue::Float(v),
+            Value::Double(v) => SharedValue::Double(v),
+            Value::String(v) => SharedValue::String(v.into()),
+            Value::ByteArray(v) => SharedValue::ByteArray(Arc::new(v)),
+            Value::IntArray(v) => SharedValue::IntArray(Arc::new(v)),
+            Value::LongArray(v) => SharedValue::LongArray(Arc::new(v)),
+            Value::List(v) => SharedValue::List(Arc::new(v.into_iter().map(Into::into).collect())),
+            Value::Compound(v) => SharedValue::Compound(Arc::new(
+                v.into_iter().map(|(k, v)| (k, v.into())).collect(),
+            )),
+        }
+    }
+}
+
+impl From<SharedValue> for Value {
+    /// Convert back into a [`Value`], which copies anything still shared with
+    /// another clone.
+    fn from(value: SharedValue) -> Self {
+        match value {
+            SharedValue::Byte(v) => Value::Byte(v),
+            SharedValue::Short(v) => Value::Short(v),
+            SharedValue::Int(v) => Value::Int(v),
+            SharedValue::Long(v) => Value::Long(v),
+            SharedValue::Float(v) => Value::Float(v),
+            SharedValue::Double(v) => Value::Double(v),
+            SharedValue::String(v) => Value::String(v.to_string()),
+            SharedValue::ByteArray(v) => Value::ByteArray(Arc::unwrap_or_clone(v)),
+            SharedValue::IntArray(v) => Value::IntArray(Arc::unwrap_or_clone(v)),
+            SharedValue::LongArray(v) => Value::LongArray(Arc::unwrap_or_clone(v)),
+            SharedValue::List(v) => Value::List(
+                Arc::unwrap_or_clone(v)
+                    .into_iter()
+                    .map(Into::into)
+                    .collect(),
+            ),
+            SharedValue::Compound(v) => Value::Compound(
+                Arc::unwrap_or_clone(v)
+                    .into_iter()
+                    .map(|(k, v)| (k, v.into()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl Serialize for SharedValue {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            SharedValue::Byte(v) => serializer.serialize_i8(*v),
+            SharedValue::Short(v) => serializer.serialize_i16(*v),
+            SharedValue::Int(v) => serializer.serialize_i32(*v),
+            SharedValue::Long(v) => serializer.serialize_i64(*v),
+            SharedValue::Float(v) => serializer.serialize_f32(*v),
+            SharedValue::Double(v) => serializer.serialize_f64(*v),
+            SharedValue::String(v) => serializer.serialize_str(v),
+            SharedValue::ByteArray(v) => v.serialize(serializer),
+            SharedValue::IntArray(v) => v.serialize(serializer),
+            SharedValue::LongArray(v) => v.serialize(serializer),
+            SharedValue::List(v) => v.serialize(serializer),
+            SharedValue::Compound(v) => v.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for SharedValue {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        Value::deserialize(deserializer).map(Into::into)
+    }
+}